@@ -41,6 +41,13 @@ void jsonnet_gc_growth_trigger(struct JsonnetVm *vm, double v);
 /** Expect a string as output and don't JSON encode it. */
 void jsonnet_string_output(struct JsonnetVm *vm, int v);
 
+/** Jrsonnet addition.
+ *
+ * Manifest output as YAML instead of JSON, the same way as `jrsonnet --yaml` does.
+ * Overrides format set by jsonnet_string_output, passing 0 resets output format to JSON.
+ */
+void jsonnet_yaml_output(struct JsonnetVm *vm, int v);
+
 /** Callback used to load imports.
  *
  * The returned char* should be allocated with jsonnet_realloc.  It will be cleaned up by
@@ -72,13 +72,20 @@ mod common {
 					max_trace: 20,
 					resolver: PathResolver::new_cwd_fallback(),
 					padding: 4,
+					elision: None,
+				});
+			}
+			1 => {
+				vm.trace_format = Box::new(JsFormat {
+					max_trace: 20,
+					elision: None,
 				});
 			}
-			1 => vm.trace_format = Box::new(JsFormat { max_trace: 20 }),
 			2 => {
 				vm.trace_format = Box::new(ExplainingFormat {
 					resolver: PathResolver::new_cwd_fallback(),
 					max_trace: 20,
+					elision: None,
 				});
 			}
 			_ => panic!("unknown trace format"),
@@ -21,17 +21,19 @@ use std::{
 
 use jrsonnet_evaluator::{
 	apply_tla, bail,
+	error::ErrorKind::ManifestTypeMismatch,
 	function::TlaArg,
 	gc::{GcHashMap, TraceBox},
 	manifest::{JsonFormat, ManifestFormat, ToStringFormat},
 	stack::set_stack_depth_limit,
 	tb,
 	trace::{CompactFormat, PathResolver, TraceFormat},
+	typed::ValType,
 	FileImportResolver, IStr, ImportResolver, Result, State, Val,
 };
 use jrsonnet_gcmodule::Trace;
 use jrsonnet_parser::SourcePath;
-use jrsonnet_stdlib::ContextInitializer;
+use jrsonnet_stdlib::{ContextInitializer, YamlFormat};
 
 /// WASM stub
 #[cfg(target_arch = "wasm32")]
@@ -93,6 +95,10 @@ impl ImportResolver for VMImportResolver {
 		self.inner.borrow().load_file_contents(resolved)
 	}
 
+	fn transform_contents(&self, resolved: &SourcePath, contents: Vec<u8>) -> Result<Vec<u8>> {
+		self.inner.borrow().transform_contents(resolved, contents)
+	}
+
 	fn resolve_from(&self, from: &SourcePath, path: &str) -> Result<SourcePath> {
 		self.inner.borrow().resolve_from(from, path)
 	}
@@ -188,6 +194,8 @@ pub extern "C" fn jsonnet_gc_min_objects(_vm: &VM, _v: c_uint) {}
 pub extern "C" fn jsonnet_gc_growth_trigger(_vm: &VM, _v: c_double) {}
 
 /// Expect a string as output and don't JSON encode it.
+///
+/// Overrides format set by [`jsonnet_yaml_output`], passing 0 resets output format to JSON.
 #[no_mangle]
 pub extern "C" fn jsonnet_string_output(vm: &mut VM, v: c_int) {
 	vm.manifest_format = match v {
@@ -197,6 +205,22 @@ pub extern "C" fn jsonnet_string_output(vm: &mut VM, v: c_int) {
 	};
 }
 
+/// Manifest output as YAML instead of JSON, the same way as `jrsonnet --yaml` does.
+///
+/// Overrides format set by [`jsonnet_string_output`], passing 0 resets output format to JSON.
+#[no_mangle]
+pub extern "C" fn jsonnet_yaml_output(vm: &mut VM, v: c_int) {
+	vm.manifest_format = match v {
+		0 => Box::new(JsonFormat::default()),
+		1 => Box::new(YamlFormat::cli(
+			2,
+			#[cfg(feature = "exp-preserve-order")]
+			false,
+		)),
+		_ => panic!("incorrect output format"),
+	};
+}
+
 /// Allocate, resize, or free a buffer.  This will abort if the memory cannot be allocated. It will
 /// only return NULL if sz was zero.
 ///
@@ -316,7 +340,10 @@ pub unsafe extern "C" fn jsonnet_evaluate_snippet(
 
 fn val_to_multi(val: Val, format: &dyn ManifestFormat) -> Result<Vec<(IStr, IStr)>> {
 	let Val::Obj(val) = val else {
-		bail!("expected object as multi output")
+		bail!(ManifestTypeMismatch {
+			expected: ValType::Obj,
+			got: val.value_type(),
+		})
 	};
 	let mut out = Vec::new();
 	for (k, v) in val.iter(
@@ -403,7 +430,10 @@ pub unsafe extern "C" fn jsonnet_evaluate_snippet_multi(
 
 fn val_to_stream(val: Val, format: &dyn ManifestFormat) -> Result<Vec<IStr>> {
 	let Val::Arr(val) = val else {
-		bail!("expected array as stream output")
+		bail!(ManifestTypeMismatch {
+			expected: ValType::Arr,
+			got: val.value_type(),
+		})
 	};
 	let mut out = Vec::new();
 	for item in val.iter() {
@@ -482,3 +512,70 @@ pub unsafe extern "C" fn jsonnet_evaluate_snippet_stream(
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::ptr::NonNull;
+
+	use super::*;
+
+	fn evaluate(vm: &VM, snippet: &CStr) -> (c_int, String) {
+		let mut error = 0;
+		let out =
+			unsafe { jsonnet_evaluate_snippet(vm, c"snip".as_ptr(), snippet.as_ptr(), &mut error) };
+		let out = unsafe { CString::from_raw(out.cast_mut()) };
+		(error, out.into_string().expect("utf-8 output"))
+	}
+
+	#[test]
+	fn yaml_output() {
+		let vm = NonNull::new(jsonnet_make()).expect("vm is created");
+		let vm = unsafe { &mut *vm.as_ptr() };
+
+		jsonnet_yaml_output(vm, 1);
+		assert_eq!(
+			evaluate(vm, c"{ a: 1, b: ['x', { c: true }] }"),
+			(0, "a: 1\nb:\n  - x\n  - c: true".to_owned()),
+		);
+
+		jsonnet_string_output(vm, 1);
+		assert_eq!(evaluate(vm, c"'plain'"), (0, "plain".to_owned()));
+
+		jsonnet_yaml_output(vm, 0);
+		assert_eq!(
+			evaluate(vm, c"{ a: 1 }"),
+			(0, "{\n    \"a\": 1\n}".to_owned())
+		);
+
+		jsonnet_destroy(unsafe { Box::from_raw(vm) });
+	}
+
+	#[test]
+	fn multi_and_stream_type_mismatch() {
+		let vm = NonNull::new(jsonnet_make()).expect("vm is created");
+		let vm = unsafe { &mut *vm.as_ptr() };
+
+		let mut error = 0;
+		let out = unsafe {
+			jsonnet_evaluate_snippet_multi(vm, c"snip".as_ptr(), c"[1]".as_ptr(), &mut error)
+		};
+		let out = unsafe { CString::from_raw(out.cast_mut()) };
+		assert_eq!(error, 1);
+		assert!(out
+			.to_str()
+			.expect("utf-8 output")
+			.starts_with("manifest output should be object, got array"));
+
+		let out = unsafe {
+			jsonnet_evaluate_snippet_stream(vm, c"snip".as_ptr(), c"{}".as_ptr(), &mut error)
+		};
+		let out = unsafe { CString::from_raw(out.cast_mut()) };
+		assert_eq!(error, 1);
+		assert!(out
+			.to_str()
+			.expect("utf-8 output")
+			.starts_with("manifest output should be array, got object"));
+
+		jsonnet_destroy(unsafe { Box::from_raw(vm) });
+	}
+}
@@ -16,8 +16,11 @@ pub enum CommentLocation {
 
 #[allow(clippy::too_many_lines, clippy::cognitive_complexity)]
 pub fn format_comments(comments: &ChildTrivia, loc: CommentLocation, out: &mut PrintItems) {
-	for c in comments {
+	// Blank lines before the first comment are handled by `should_start_with_newline` of the item
+	let mut printed_comment = false;
+	for (i, c) in comments.iter().enumerate() {
 		let Ok(c) = c else {
+			printed_comment = true;
 			let mut text = c.as_ref().unwrap_err() as &str;
 			while !text.is_empty() {
 				let pos = text.find(['\n', '\t']).unwrap_or(text.len());
@@ -35,8 +38,32 @@ pub fn format_comments(comments: &ChildTrivia, loc: CommentLocation, out: &mut P
 			}
 			continue;
 		};
+		if c.kind() == TriviaKind::Whitespace {
+			// Keep at most one blank line after comment, unless it is the end of comment block
+			// Single-line comments include their trailing newline
+			let comment_newline = i
+				.checked_sub(1)
+				.and_then(|prev| comments[prev].as_ref().ok())
+				.is_some_and(|prev| prev.text().ends_with('\n'));
+			let is_blank = c.text().bytes().filter(|b| *b == b'\n').count()
+				+ usize::from(comment_newline)
+				>= 2;
+			let followed = match loc {
+				CommentLocation::AboveItem => true,
+				CommentLocation::ItemInline => false,
+				CommentLocation::EndOfItems => comments[i + 1..].iter().any(|c| {
+					c.as_ref()
+						.map_or(true, |c| c.kind() != TriviaKind::Whitespace)
+				}),
+			};
+			if printed_comment && is_blank && followed {
+				p!(out, nl);
+			}
+			continue;
+		}
+		printed_comment = true;
 		match c.kind() {
-			TriviaKind::Whitespace => {}
+			TriviaKind::Whitespace => unreachable!("handled above"),
 			TriviaKind::MultiLineComment => {
 				let mut text = c
 					.text()
@@ -27,10 +27,12 @@ use jrsonnet_rowan_parser::{
 use crate::{
 	children::trivia_after,
 	comments::{format_comments, CommentLocation},
+	sort_keys::{enable_sort_keys, sort_members},
 };
 
 mod children;
 mod comments;
+mod sort_keys;
 #[cfg(test)]
 mod tests;
 
@@ -444,6 +446,7 @@ impl Printable for ObjBody {
 					p!(out, str("{ }"));
 					return;
 				}
+				let children = sort_members(children);
 				p!(out, str("{") >i nl);
 				for (i, mem) in children.into_iter().enumerate() {
 					if mem.should_start_with_newline && i != 0 {
@@ -704,6 +707,8 @@ impl Printable for SourceFile {
 struct FormatOptions {
 	// 0 for hard tabs
 	indent: u8,
+	/// Reorder object fields alphabetically
+	sort_keys: bool,
 }
 fn format(input: &str, opts: &FormatOptions) -> Option<String> {
 	let (parsed, errors) = jrsonnet_rowan_parser::parse(input);
@@ -731,6 +736,7 @@ fn format(input: &str, opts: &FormatOptions) -> Option<String> {
 		// TODO: Verify how formatter interacts in cases of missing positional values, i.e `if cond then /*missing Expr*/ else residual`.
 		return None;
 	}
+	let _sort_keys = enable_sort_keys(opts.sort_keys);
 	Some(dprint_core::formatting::format(
 		|| {
 			let mut out = PrintItems::new();
@@ -775,6 +781,11 @@ struct Opts {
 	/// Force hard tab for indentation
 	#[arg(long)]
 	hard_tabs: bool,
+	/// Reorder object fields alphabetically, comments are moved together with their fields.
+	///
+	/// Locals, assertions, fields with computed names and object comprehensions are kept in place.
+	#[arg(long)]
+	sort_keys: bool,
 
 	/// Debug option: how many times to call reformatting in case of unstable dprint output resolution.
 	///
@@ -827,6 +838,7 @@ fn main_result() -> Result<(), Error> {
 				} else {
 					opts.indent
 				},
+				sort_keys: opts.sort_keys,
 			},
 		) else {
 			return Err(Error::Parse);
@@ -0,0 +1,77 @@
+use std::cell::Cell;
+
+use jrsonnet_rowan_parser::{
+	nodes::{FieldName, Member, TextKind},
+	AstToken as _,
+};
+
+use crate::children::Child;
+
+thread_local! {
+	/// Printers have no access to [`crate::FormatOptions`], this flag is set for the duration of [`crate::format`] call
+	static SORT_KEYS: Cell<bool> = const { Cell::new(false) };
+}
+
+pub struct SortKeysGuard(bool);
+impl Drop for SortKeysGuard {
+	fn drop(&mut self) {
+		SORT_KEYS.set(self.0);
+	}
+}
+
+pub fn enable_sort_keys(enabled: bool) -> SortKeysGuard {
+	SortKeysGuard(SORT_KEYS.replace(enabled))
+}
+
+/// Field name, which is used for sorting, `None` for members which should be kept in place
+fn member_sort_key(member: &Member) -> Option<String> {
+	let name = match member {
+		Member::MemberFieldNormal(n) => n.field_name()?,
+		Member::MemberFieldMethod(m) => m.field_name()?,
+		Member::MemberBindStmt(_) | Member::MemberAssertStmt(_) => return None,
+	};
+	let FieldName::FieldNameFixed(name) = name else {
+		return None;
+	};
+	if let Some(id) = name.id() {
+		return Some(id.ident_lit()?.text().to_owned());
+	}
+	let text = name.text()?;
+	let kind = text.kind();
+	let text = text.syntax().text();
+	// Escapes are kept as is, it only affects order of fields with escaped names
+	let unquoted = match kind {
+		TextKind::StringDouble | TextKind::StringSingle => &text[1..text.len() - 1],
+		TextKind::StringDoubleVerbatim | TextKind::StringSingleVerbatim => &text[2..text.len() - 1],
+		_ => return None,
+	};
+	Some(unquoted.to_owned())
+}
+
+/// Reorders fixed-name fields alphabetically, if enabled with [`enable_sort_keys`].
+///
+/// Comments are attached to the members, and move with them. Locals, assertions and fields with computed names
+/// are kept at their positions, sorted fields are placed in the slots which were taken by fixed-name fields.
+pub fn sort_members(members: Vec<Child<Member>>) -> Vec<Child<Member>> {
+	if !SORT_KEYS.get() {
+		return members;
+	}
+	let mut keys = members
+		.iter()
+		.enumerate()
+		.filter_map(|(i, m)| Some((member_sort_key(&m.value)?, i)))
+		.collect::<Vec<_>>();
+	let slots = keys.iter().map(|(_, i)| *i).collect::<Vec<_>>();
+	// Index is a part of the key, so duplicate names are kept in the original order
+	keys.sort();
+
+	let mut order = (0..members.len()).collect::<Vec<_>>();
+	for (slot, (_, from)) in slots.into_iter().zip(keys) {
+		order[slot] = from;
+	}
+	let mut members = members.into_iter().map(Some).collect::<Vec<_>>();
+	order
+		.into_iter()
+		.map(|i| members[i].take().expect("every member is moved once"))
+		.collect()
+}
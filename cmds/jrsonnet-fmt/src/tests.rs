@@ -1,7 +1,7 @@
 use dprint_core::formatting::{PrintItems, PrintOptions};
 use indoc::indoc;
 
-use crate::Printable;
+use crate::{sort_keys::enable_sort_keys, Printable};
 
 fn reformat(input: &str) -> String {
 	let (source, _) = jrsonnet_rowan_parser::parse(input);
@@ -77,3 +77,42 @@ fn complex_comments_snapshot() {
         }"
 	)));
 }
+
+#[test]
+fn sort_keys_snapshot() {
+	let _sort_keys = enable_sort_keys(true);
+	insta::assert_snapshot!(reformat(indoc!(
+		"{
+		  // Comment for c
+		  c: 3,
+		  local l = 1,
+		  b: 2, // Inline comment for b
+		  ['dyn']: 0,
+		  /* Comment for a */
+		  a(x): x,
+		  'aa': 4,
+		  nested: { z: 1, y: 2 },
+		  comp: { [k]: 1 for k in ['z', 'y'] },
+		}"
+	)));
+}
+
+#[test]
+fn blank_lines_snapshot() {
+	insta::assert_snapshot!(reformat(indoc!(
+		"{
+		  a: 1,
+
+
+
+		  b: 2,
+		  // Detached comment
+
+		  c: 3,
+		  // First comment
+
+		  // Second comment
+		  d: 4,
+		}"
+	)));
+}
@@ -1,6 +1,7 @@
 use std::{
 	fs::{create_dir_all, File},
 	io::{Read, Write},
+	path::{Component, Path, PathBuf},
 };
 
 use clap::{CommandFactory, Parser};
@@ -8,9 +9,19 @@ use clap_complete::Shell;
 use jrsonnet_cli::{GcOpts, ManifestOpts, MiscOpts, OutputOpts, StdOpts, TlaOpts, TraceOpts};
 use jrsonnet_evaluator::{
 	apply_tla, bail,
-	error::{Error as JrError, ErrorKind},
-	ResultExt, State, Val,
+	error::{Error as JrError, ErrorKind, StackTraceElement},
+	function::TlaArg,
+	gc::GcHashMap,
+	trace::TraceFormat,
+	typed::ValType,
+	val::ThunkValue,
+	IStr, ImportResolver, ObjValue, ObjValueBuilder, ResultExt, State, Thunk, Val,
 };
+use jrsonnet_gcmodule::Trace;
+use jrsonnet_parser::{ParserSettings, Source};
+
+#[cfg(test)]
+mod tests;
 
 #[cfg(feature = "mimalloc")]
 #[global_allocator]
@@ -32,17 +43,50 @@ struct DebugOpts {
 	/// This shouldn't be changed unless jrsonnet is failing with stack overflow error.
 	#[clap(long, name = "size")]
 	pub os_stack: Option<usize>,
+	/// Print builtin call statistics to stderr after evaluation.
+	#[cfg(feature = "exp-profile")]
+	#[clap(long)]
+	pub profile: bool,
 }
 
 #[derive(Parser)]
 #[clap(next_help_heading = "INPUT")]
+#[allow(clippy::struct_excessive_bools)]
 struct InputOpts {
 	/// Treat input as code, evaluate it instead of reading file.
 	#[clap(long, short = 'e')]
 	pub exec: bool,
 
 	/// Path to the file to be compiled if `--exec` is unset, otherwise code itself.
-	pub input: Option<String>,
+	/// Multiple inputs are only accepted with `--merge`.
+	pub input: Vec<String>,
+
+	/// Evaluate every input, and deep-merge resulting objects from left to right.
+	/// Nested objects are merged, other values are replaced by the rightmost input.
+	/// Hidden fields are not merged, and do not appear in the result.
+	#[clap(long)]
+	pub merge: bool,
+
+	/// Evaluate every file listed (one path per line) in the specified file, writing outputs to
+	/// the `--multi` directory, keyed by input path.
+	/// Files are evaluated in a single process, sharing imports between them.
+	#[clap(
+		long,
+		name = "list",
+		requires = "multi",
+		conflicts_with_all = ["input", "exec", "merge"],
+	)]
+	pub files_from: Option<PathBuf>,
+
+	/// With `--files-from`, stop on the first failed file, instead of reporting the error and
+	/// continuing with the rest of the files.
+	#[clap(long, requires = "list")]
+	pub fail_fast: bool,
+
+	/// Only check that inputs parse, without evaluating them.
+	/// Nothing is printed on success, syntax errors are reported for every failed input.
+	#[clap(long, conflicts_with_all = ["list", "merge"])]
+	pub parse_only: bool,
 
 	/// After executing input, apply specified code.
 	/// Output of the initial input will be accessible using `_`.
@@ -134,6 +178,25 @@ enum Error {
 	Utf8(#[from] std::str::Utf8Error),
 	#[error("missing input argument")]
 	MissingInputArgument,
+	#[error("multiple inputs are only supported with --merge")]
+	MultipleInputs,
+	#[error("{0} of {1} inputs failed")]
+	BatchFailed(usize, usize),
+}
+impl Error {
+	/// Adds frame to the evaluation error stack trace, other errors are returned as is
+	fn with_frame(self, desc: String) -> Self {
+		match self {
+			Self::Evaluation(mut e) => {
+				e.trace_mut().0.push(StackTraceElement {
+					location: None,
+					desc,
+				});
+				Self::Evaluation(e)
+			}
+			e => e,
+		}
+	}
 }
 impl From<JrError> for Error {
 	fn from(e: JrError) -> Self {
@@ -148,23 +211,273 @@ impl From<ErrorKind> for Error {
 
 fn main_catch(opts: Opts) -> bool {
 	let trace = opts.trace.trace_format();
-	if let Err(e) = main_real(opts) {
-		if let Error::Evaluation(e) = e {
-			let mut out = String::new();
-			trace.write_trace(&mut out, &e).expect("format error");
-			eprintln!("{out}");
-		} else {
-			eprintln!("{e}");
+	#[cfg(feature = "exp-profile")]
+	let profile = opts.debug.profile;
+	let result = main_real(opts);
+	#[cfg(feature = "exp-profile")]
+	if profile {
+		for entry in jrsonnet_evaluator::profile::dump() {
+			eprintln!("{entry}");
 		}
+	}
+	if let Err(e) = result {
+		print_error(&*trace, &e);
 		return false;
 	}
 	true
 }
 
+fn print_error(trace: &dyn TraceFormat, e: &Error) {
+	if let Error::Evaluation(e) = e {
+		let mut out = String::new();
+		trace.write_trace(&mut out, e).expect("format error");
+		eprintln!("{out}");
+	} else {
+		eprintln!("{e}");
+	}
+}
+
+fn evaluate_input(s: &State, exec: bool, input: &str) -> Result<Val, Error> {
+	Ok(if exec {
+		s.evaluate_snippet("<cmdline>".to_owned(), input)?
+	} else if input == "-" {
+		let mut input = Vec::new();
+		std::io::stdin().read_to_end(&mut input)?;
+		let input_str = std::str::from_utf8(&input)?;
+		s.evaluate_snippet("<stdin>".to_owned(), input_str)?
+	} else {
+		s.import(input)?
+	})
+}
+
+/// Parses a single `--parse-only` input
+fn parse_input(resolver: &dyn ImportResolver, exec: bool, input: &str) -> Result<(), Error> {
+	let source = if exec {
+		Source::new_virtual("<cmdline>".into(), input.into())
+	} else if input == "-" {
+		let mut input = Vec::new();
+		std::io::stdin().read_to_end(&mut input)?;
+		Source::new_virtual("<stdin>".into(), std::str::from_utf8(&input)?.into())
+	} else {
+		let path = resolver.resolve(Path::new(input))?;
+		let code = resolver.load_transformed_contents(&path)?;
+		Source::new(path, std::str::from_utf8(&code)?.into())
+	};
+	jrsonnet_parser::parse(
+		source.code(),
+		&ParserSettings {
+			source: source.clone(),
+		},
+	)
+	.map_err(|error| ErrorKind::ImportSyntaxError {
+		path: source,
+		error: Box::new(error),
+	})?;
+	Ok(())
+}
+
+/// Checks that every input parses, neither stdlib nor evaluator is involved
+fn parse_inputs(opts: &Opts) -> Result<(), Error> {
+	let resolver = opts.misc.import_resolver();
+	let trace = opts.trace.trace_format();
+	let inputs = &opts.input.input;
+	if inputs.is_empty() {
+		return Err(Error::MissingInputArgument);
+	}
+	let mut failed = 0;
+	for input in inputs {
+		match parse_input(&resolver, opts.input.exec, input) {
+			Ok(()) => {}
+			Err(e) if inputs.len() == 1 => return Err(e),
+			Err(e) => {
+				failed += 1;
+				print_error(&*trace, &e);
+			}
+		}
+	}
+	if failed != 0 {
+		return Err(Error::BatchFailed(failed, inputs.len()));
+	}
+	Ok(())
+}
+
+/// Value of the field, which is present in both merged objects
+#[derive(Trace)]
+struct MergedField {
+	a: Thunk<Val>,
+	b: Thunk<Val>,
+	#[cfg(feature = "exp-preserve-order")]
+	preserve_order: bool,
+}
+impl ThunkValue for MergedField {
+	type Output = Val;
+
+	fn get(self: Box<Self>) -> Result<Val, JrError> {
+		// Overridden non-object values are not evaluated
+		Ok(match self.b.evaluate()? {
+			Val::Obj(b) => match self.a.evaluate()? {
+				Val::Obj(a) => Val::Obj(deep_merge(
+					&a,
+					&b,
+					#[cfg(feature = "exp-preserve-order")]
+					self.preserve_order,
+				)),
+				_ => Val::Obj(b),
+			},
+			b => b,
+		})
+	}
+}
+
+/// Merges `b` into `a`: fields present in both are merged recursively if both values are objects,
+/// otherwise value from `b` wins. Field values are merged lazily, on access.
+///
+/// Only visible fields are kept, as merged value is only used for manifestification, and hidden fields would
+/// never be written anyway
+fn deep_merge(
+	a: &ObjValue,
+	b: &ObjValue,
+	#[cfg(feature = "exp-preserve-order")] preserve_order: bool,
+) -> ObjValue {
+	let mut out = ObjValueBuilder::new();
+	for field in a.fields(
+		#[cfg(feature = "exp-preserve-order")]
+		preserve_order,
+	) {
+		let a_value = a.get_lazy(field.clone()).expect("field exists");
+		let value = if b.has_field(field.clone()) {
+			Thunk::new(MergedField {
+				a: a_value,
+				b: b.get_lazy(field.clone()).expect("field exists"),
+				#[cfg(feature = "exp-preserve-order")]
+				preserve_order,
+			})
+		} else {
+			a_value
+		};
+		out.field(field).thunk(value);
+	}
+	for field in b.fields(
+		#[cfg(feature = "exp-preserve-order")]
+		preserve_order,
+	) {
+		if !a.has_field(field.clone()) {
+			let value = b.get_lazy(field.clone()).expect("field exists");
+			out.field(field).thunk(value);
+		}
+	}
+	out.build()
+}
+
+/// Evaluates every input, and deep-merges them from left to right
+fn evaluate_merged(
+	s: &State,
+	exec: bool,
+	inputs: &[String],
+	tla: &GcHashMap<IStr, TlaArg>,
+	#[cfg(feature = "exp-preserve-order")] preserve_order: bool,
+) -> Result<ObjValue, Error> {
+	let mut merged: Option<ObjValue> = None;
+	for input in inputs {
+		let obj = evaluate_input(s, exec, input)
+			.and_then(|val| {
+				let val = apply_tla(s.clone(), tla, val)?;
+				let Val::Obj(obj) = val else {
+					bail!("merged input should be an object, got {}", val.value_type())
+				};
+				Ok(obj)
+			})
+			.map_err(|e| e.with_frame(format!("merging input {input}")))?;
+		merged = Some(match merged {
+			Some(merged) => deep_merge(
+				&merged,
+				&obj,
+				#[cfg(feature = "exp-preserve-order")]
+				preserve_order,
+			),
+			None => obj,
+		});
+	}
+	Ok(merged.expect("inputs are not empty"))
+}
+
+/// Evaluates and manifests a single `--files-from` input, returns path of the written file
+fn evaluate_batch_input(
+	s: &State,
+	opts: &Opts,
+	multi: &Path,
+	tla: &GcHashMap<IStr, TlaArg>,
+	input: &str,
+) -> Result<PathBuf, Error> {
+	let mut path = multi.to_owned();
+	for component in Path::new(input).components() {
+		match component {
+			Component::Normal(c) => path.push(c),
+			Component::ParentDir => bail!("input path {input} escapes the output directory"),
+			Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+		}
+	}
+
+	let val = apply_tla(s.clone(), tla, s.import(input)?)?;
+	let manifest_format = opts.manifest.manifest_format()?;
+	let data = val.manifest_bytes(&manifest_format)?;
+
+	if opts.output.create_output_dirs {
+		let mut dir = path.clone();
+		dir.pop();
+		create_dir_all(dir)?;
+	}
+	let mut file = File::create(&path)?;
+	file.write_all(&data)?;
+	if manifest_format.file_trailing_newline() {
+		writeln!(file)?;
+	}
+	file.flush()?;
+	Ok(path)
+}
+
+/// Evaluates every file listed in `list` using the same state, failed files are reported and
+/// skipped unless `--fail-fast` is set
+fn evaluate_batch(
+	s: &State,
+	opts: &Opts,
+	list: &Path,
+	tla: &GcHashMap<IStr, TlaArg>,
+) -> Result<(), Error> {
+	let multi = opts.output.multi.as_ref().expect("required by clap");
+	let trace = opts.trace.trace_format();
+	let list = std::fs::read_to_string(list)?;
+
+	let mut total = 0;
+	let mut failed = 0;
+	for input in list.lines().map(str::trim).filter(|l| !l.is_empty()) {
+		total += 1;
+		match evaluate_batch_input(s, opts, multi, tla, input)
+			.map_err(|e| e.with_frame(format!("evaluating input {input}")))
+		{
+			Ok(path) => println!("{}", path.to_str().expect("path")),
+			Err(e) if opts.input.fail_fast => return Err(e),
+			Err(e) => {
+				failed += 1;
+				print_error(&*trace, &e);
+			}
+		}
+	}
+	if failed != 0 {
+		return Err(Error::BatchFailed(failed, total));
+	}
+	Ok(())
+}
+
+#[allow(clippy::too_many_lines)]
 fn main_real(opts: Opts) -> Result<(), Error> {
+	if opts.input.parse_only {
+		return parse_inputs(&opts);
+	}
 	let _gc_leak_guard = opts.gc.leak_on_exit();
 	let _gc_print_stats = opts.gc.stats_printer();
 	let _stack_depth_override = opts.misc.stack_size_override();
+	let _thunk_budget_override = opts.misc.thunk_budget_override();
 
 	let import_resolver = opts.misc.import_resolver();
 	let std = opts.std.context_initializer()?;
@@ -173,25 +486,29 @@ fn main_real(opts: Opts) -> Result<(), Error> {
 	s.import_resolver(import_resolver).context_initializer(std);
 	let s = s.build();
 
-	let input = opts.input.input.ok_or(Error::MissingInputArgument)?;
-	let val = if opts.input.exec {
-		s.evaluate_snippet("<cmdline>".to_owned(), &input as &str)?
-	} else if input == "-" {
-		let mut input = Vec::new();
-		std::io::stdin().read_to_end(&mut input)?;
-		let input_str = std::str::from_utf8(&input)?;
-		s.evaluate_snippet("<stdin>".to_owned(), input_str)?
-	} else {
-		s.import(&input)?
-	};
-
 	let tla = opts.tla.tla_opts()?;
+	if let Some(list) = &opts.input.files_from {
+		return evaluate_batch(&s, &opts, list, &tla);
+	}
 	#[allow(
 		// It is not redundant/unused in exp-apply
 		unused_mut,
-		clippy::redundant_clone,
 	)]
-	let mut val = apply_tla(s.clone(), &tla, val)?;
+	let mut val = match opts.input.input.as_slice() {
+		[] => return Err(Error::MissingInputArgument),
+		[input] if !opts.input.merge => {
+			apply_tla(s.clone(), &tla, evaluate_input(&s, opts.input.exec, input)?)?
+		}
+		_ if !opts.input.merge => return Err(Error::MultipleInputs),
+		inputs => Val::Obj(evaluate_merged(
+			&s,
+			opts.input.exec,
+			inputs,
+			&tla,
+			#[cfg(feature = "exp-preserve-order")]
+			opts.manifest.preserve_order,
+		)?),
+	};
 
 	#[cfg(feature = "exp-apply")]
 	for apply in opts.input.exp_apply {
@@ -203,7 +520,7 @@ fn main_real(opts: Opts) -> Result<(), Error> {
 		)?;
 	}
 
-	let manifest_format = opts.manifest.manifest_format();
+	let manifest_format = opts.manifest.manifest_format()?;
 	if let Some(multi) = opts.output.multi {
 		if opts.output.create_output_dirs {
 			let mut dir = multi.clone();
@@ -211,10 +528,11 @@ fn main_real(opts: Opts) -> Result<(), Error> {
 			create_dir_all(dir)?;
 		}
 		let Val::Obj(obj) = val else {
-			bail!(
-				"value should be object for --multi manifest, got {}",
-				val.value_type()
-			)
+			return Err(ErrorKind::ManifestTypeMismatch {
+				expected: ValType::Obj,
+				got: val.value_type(),
+			}
+			.into());
 		};
 		for (field, data) in obj.iter(
 			#[cfg(feature = "exp-preserve-order")]
@@ -231,10 +549,9 @@ fn main_real(opts: Opts) -> Result<(), Error> {
 			}
 			println!("{}", path.to_str().expect("path"));
 			let mut file = File::create(path)?;
-			write!(
-				file,
-				"{}",
-				data.manifest(&manifest_format)
+			file.write_all(
+				&data
+					.manifest_bytes(&manifest_format)
 					.with_description(|| format!("manifesting {field}"))?,
 			)?;
 			if manifest_format.file_trailing_newline() {
@@ -249,7 +566,15 @@ fn main_real(opts: Opts) -> Result<(), Error> {
 			create_dir_all(dir)?;
 		}
 		let mut file = File::create(path)?;
-		writeln!(file, "{}", val.manifest(manifest_format)?)?;
+		file.write_all(&val.manifest_bytes(&manifest_format)?)?;
+		if !manifest_format.is_binary() {
+			writeln!(file)?;
+		}
+	} else if manifest_format.is_binary() {
+		let output = val.manifest_bytes(manifest_format)?;
+		let mut stdout = std::io::stdout().lock();
+		stdout.write_all(&output)?;
+		stdout.flush()?;
 	} else {
 		let output = val.manifest(manifest_format)?;
 		if !output.is_empty() {
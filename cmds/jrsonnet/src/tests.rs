@@ -0,0 +1,275 @@
+use std::{
+	any::Any,
+	fs,
+	path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use jrsonnet_evaluator::{
+	error::ErrorKind, parser::SourcePath, typed::ValType, FileImportResolver, ImportResolver,
+};
+use jrsonnet_gcmodule::Trace;
+use tempfile::TempDir;
+
+use crate::{main_real, parse_input, Error, Opts};
+
+/// Fresh directory, which is removed once dropped, even if the test fails
+fn temp_dir() -> TempDir {
+	tempfile::tempdir().expect("temp dir is creatable")
+}
+
+fn fixture(dir: &TempDir, name: &str, contents: &str) -> PathBuf {
+	let path = dir.path().join(name);
+	fs::write(&path, contents).expect("fixture is writable");
+	path
+}
+
+fn parse_only(inputs: &[&PathBuf]) -> Result<(), Error> {
+	let mut args = vec!["jrsonnet".into(), "--parse-only".into()];
+	args.extend(inputs.iter().map(|p| p.as_os_str().to_owned()));
+	main_real(Opts::parse_from(args))
+}
+
+#[test]
+fn parse_only_reports_syntax_errors() {
+	let dir = temp_dir();
+	// Would fail if evaluated
+	let valid = fixture(
+		&dir,
+		"valid.jsonnet",
+		"local a = error 'a'; { a: a, b: std.missing }",
+	);
+	let invalid = fixture(&dir, "invalid.jsonnet", "{ a: 1,, }");
+
+	parse_only(&[&valid]).expect("valid file parses");
+
+	let Err(Error::Evaluation(e)) = parse_only(&[&invalid]) else {
+		panic!("syntax error expected");
+	};
+	let ErrorKind::ImportSyntaxError { path, .. } = e.error() else {
+		panic!("syntax error expected, got {e:?}");
+	};
+	assert_eq!(
+		path.source_path().path(),
+		Some(invalid.canonicalize().expect("exists").as_path())
+	);
+
+	assert!(matches!(
+		parse_only(&[&valid, &invalid, &invalid]),
+		Err(Error::BatchFailed(2, 3))
+	));
+}
+
+/// Replaces contents of every file with `{}`
+#[derive(Trace)]
+struct EmptyObjectResolver(FileImportResolver);
+impl ImportResolver for EmptyObjectResolver {
+	fn resolve(&self, path: &Path) -> jrsonnet_evaluator::Result<SourcePath> {
+		self.0.resolve(path)
+	}
+	fn load_file_contents(&self, resolved: &SourcePath) -> jrsonnet_evaluator::Result<Vec<u8>> {
+		self.0.load_file_contents(resolved)
+	}
+	fn transform_contents(
+		&self,
+		_resolved: &SourcePath,
+		_contents: Vec<u8>,
+	) -> jrsonnet_evaluator::Result<Vec<u8>> {
+		Ok(b"{}".to_vec())
+	}
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+}
+
+#[test]
+fn parse_only_transforms_contents() {
+	let dir = temp_dir();
+	let invalid = fixture(&dir, "transformed.jsonnet", "{ a: 1,, }");
+	let input = invalid.to_str().expect("temp dir is utf-8");
+
+	parse_input(&FileImportResolver::default(), false, input).expect_err("file is invalid");
+	parse_input(
+		&EmptyObjectResolver(FileImportResolver::default()),
+		false,
+		input,
+	)
+	.expect("transformed file parses");
+}
+
+fn manifest_error(args: &[&str]) -> ErrorKind {
+	let args = std::iter::once(&"jrsonnet").chain(args);
+	let Err(Error::Evaluation(e)) = main_real(Opts::parse_from(args)) else {
+		panic!("evaluation error expected");
+	};
+	e.error().clone()
+}
+
+#[test]
+fn manifest_type_mismatch() {
+	let multi = temp_dir();
+	let multi = multi.path().to_str().expect("utf-8 path");
+
+	let ErrorKind::ManifestTypeMismatch { expected, got } =
+		manifest_error(&["-e", "[1, 2]", "--multi", multi])
+	else {
+		panic!("manifest type mismatch expected");
+	};
+	assert_eq!((expected, got), (ValType::Obj, ValType::Arr));
+
+	let e = manifest_error(&["-e", "{ a: 1 }", "--yaml-stream"]);
+	assert_eq!(e.to_string(), "manifest output should be array, got object");
+	assert!(matches!(
+		e,
+		ErrorKind::ManifestTypeMismatch {
+			expected: ValType::Arr,
+			got: ValType::Obj,
+		}
+	));
+}
+
+fn merge(inputs: &[&str]) -> Result<serde_json::Value, Error> {
+	let dir = temp_dir();
+	let output = dir.path().join("merged.json");
+	let mut args = vec!["jrsonnet", "-e", "--merge", "-o"];
+	args.push(output.to_str().expect("utf-8 path"));
+	args.extend(inputs);
+	main_real(Opts::parse_from(args))?;
+	let out = fs::read_to_string(&output).expect("output is written");
+	Ok(serde_json::from_str(&out).expect("output is json"))
+}
+
+#[test]
+fn merge_nested_objects() {
+	let merged = merge(&[
+		"{ a: { b: 1, c: 2 }, d: 1 }",
+		"{ a: { c: 3, e: 4 }, f: 5 }",
+		"{ a: { e: { g: 6 } } }",
+	])
+	.expect("merged");
+	assert_eq!(
+		merged,
+		serde_json::json!({ "a": { "b": 1, "c": 3, "e": { "g": 6 } }, "d": 1, "f": 5 })
+	);
+}
+
+#[test]
+fn merge_non_object_override() {
+	let merged = merge(&["{ a: { b: 1 }, c: 1 }", "{ a: 2, c: { d: 3 } }"]).expect("merged");
+	assert_eq!(merged, serde_json::json!({ "a": 2, "c": { "d": 3 } }));
+
+	// Overridden values are never evaluated
+	let merged = merge(&["{ a: error 'unused' }", "{ a: 1 }"]).expect("merged");
+	assert_eq!(merged, serde_json::json!({ "a": 1 }));
+}
+
+#[test]
+fn merge_drops_hidden_fields() {
+	let merged = merge(&["{ a:: 1, b: 2 }", "{ b:: 3, c: 4 }"]).expect("merged");
+	assert_eq!(merged, serde_json::json!({ "b": 2, "c": 4 }));
+}
+
+#[test]
+fn merge_reports_failed_input() {
+	let Err(Error::Evaluation(e)) = merge(&["{ a: 1 }", "error 'boom'"]) else {
+		panic!("evaluation error expected");
+	};
+	assert_eq!(e.error().to_string(), "runtime error: boom");
+	assert_eq!(
+		e.trace().0.last().expect("frame exists").desc,
+		"merging input error 'boom'"
+	);
+
+	let Err(Error::Evaluation(e)) = merge(&["{ a: 1 }", "[1]"]) else {
+		panic!("evaluation error expected");
+	};
+	assert_eq!(
+		e.error().to_string(),
+		"runtime error: merged input should be an object, got array"
+	);
+	assert_eq!(
+		e.trace().0.last().expect("frame exists").desc,
+		"merging input [1]"
+	);
+}
+
+/// Writes inputs into a fresh directory, and evaluates them using `--files-from`, outputs are written
+/// to `out` subdirectory
+fn files_from(fail_fast: bool) -> (TempDir, Result<(), Error>) {
+	let temp = temp_dir();
+	let dir = temp.path();
+
+	fs::write(dir.join("a.jsonnet"), "{ a: 1 }").expect("fixture is writable");
+	fs::write(dir.join("bad.jsonnet"), "error 'bad'").expect("fixture is writable");
+	fs::write(dir.join("c.jsonnet"), "{ c: 3 }").expect("fixture is writable");
+	let list = dir.join("list.txt");
+	fs::write(
+		&list,
+		format!(
+			"{a}\n\n  \n{bad}\n{c}\n",
+			a = dir.join("a.jsonnet").display(),
+			bad = dir.join("bad.jsonnet").display(),
+			c = dir.join("c.jsonnet").display(),
+		),
+	)
+	.expect("fixture is writable");
+
+	let mut args = vec!["jrsonnet".into(), "-c".into(), "--files-from".into()];
+	args.push(list.into_os_string());
+	args.push("--multi".into());
+	args.push(dir.join("out").into_os_string());
+	if fail_fast {
+		args.push("--fail-fast".into());
+	}
+	let result = main_real(Opts::parse_from(args));
+	(temp, result)
+}
+
+/// Path, under which output for the input is written
+fn batch_output(dir: &Path, input: &str) -> PathBuf {
+	let input = dir.join(input);
+	dir.join("out")
+		.join(input.strip_prefix("/").expect("temp dir is absolute"))
+}
+
+#[test]
+fn files_from_reports_failed_inputs() {
+	let (dir, result) = files_from(false);
+	let dir = dir.path();
+	assert!(matches!(result, Err(Error::BatchFailed(1, 3))));
+
+	let a = fs::read_to_string(batch_output(dir, "a.jsonnet")).expect("output is written");
+	assert_eq!(a, "{\n   \"a\": 1\n}\n");
+	assert!(!batch_output(dir, "bad.jsonnet").exists());
+	assert!(batch_output(dir, "c.jsonnet").exists());
+}
+
+#[test]
+fn files_from_fail_fast() {
+	let (dir, result) = files_from(true);
+	let dir = dir.path();
+	let Err(Error::Evaluation(e)) = result else {
+		panic!("evaluation error expected");
+	};
+	assert_eq!(e.error().to_string(), "runtime error: bad");
+	assert_eq!(
+		e.trace().0.last().expect("frame exists").desc,
+		format!("evaluating input {}", dir.join("bad.jsonnet").display())
+	);
+
+	assert!(batch_output(dir, "a.jsonnet").exists());
+	// Evaluation is stopped after the first failure
+	assert!(!batch_output(dir, "c.jsonnet").exists());
+}
+
+#[test]
+fn yaml_indent_rejects_tabs() {
+	let e = manifest_error(&["-e", "{ a: [1] }", "--format", "yaml", "--indent", "\\t"]);
+	assert_eq!(
+		e.to_string(),
+		"runtime error: --indent can't contain tabs for YAML output, YAML doesn't allow tabs in indentation"
+	);
+}
@@ -7,6 +7,7 @@ use std::{env, marker::PhantomData, path::PathBuf};
 
 use clap::Parser;
 use jrsonnet_evaluator::{
+	budget::{limit_thunks, ThunkBudgetOverrideGuard},
 	stack::{limit_stack_depth, StackDepthLimitOverrideGuard},
 	FileImportResolver,
 };
@@ -35,12 +36,23 @@ pub struct MiscOpts {
 	#[clap(long, short = 's', default_value = "512")]
 	max_stack: usize,
 
+	/// Maximal allowed number of evaluated lazy values,
+	/// evaluation budget error will be raised if this number gets exceeded.
+	/// Unlimited by default.
+	#[clap(long)]
+	max_thunks: Option<usize>,
+
 	/// Library search dirs. (right-most wins)
 	/// Any not found `imported` file will be searched in these.
 	/// This can also be specified via `JSONNET_PATH` variable,
 	/// which should contain a colon-separated (semicolon-separated on Windows) list of directories.
 	#[clap(long, short = 'J')]
 	jpath: Vec<PathBuf>,
+
+	/// Directory to resolve imports from `--exec` code and stdin input against.
+	/// Current working directory is used by default.
+	#[clap(long)]
+	base_dir: Option<PathBuf>,
 }
 impl MiscOpts {
 	pub fn import_resolver(&self) -> FileImportResolver {
@@ -50,11 +62,19 @@ impl MiscOpts {
 			library_paths.extend(env::split_paths(path.as_os_str()));
 		}
 
-		FileImportResolver::new(library_paths)
+		let resolver = FileImportResolver::new(library_paths);
+		if let Some(base_dir) = &self.base_dir {
+			resolver.with_default_base(base_dir.clone())
+		} else {
+			resolver
+		}
 	}
 	pub fn stack_size_override(&self) -> StackDepthLimitOverrideGuard {
 		limit_stack_depth(self.max_stack)
 	}
+	pub fn thunk_budget_override(&self) -> ThunkBudgetOverrideGuard {
+		limit_thunks(self.max_thunks)
+	}
 }
 
 #[derive(Parser)]
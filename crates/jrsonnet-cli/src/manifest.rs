@@ -1,8 +1,12 @@
 use std::path::PathBuf;
 
 use clap::{Parser, ValueEnum};
-use jrsonnet_evaluator::manifest::{
-	JsonFormat, ManifestFormat, StringFormat, ToStringFormat, YamlStreamFormat,
+use jrsonnet_evaluator::{
+	bail,
+	manifest::{
+		CborFormat, JsonFormat, ManifestFormat, StringFormat, ToStringFormat, YamlStreamFormat,
+	},
+	Result,
 };
 use jrsonnet_stdlib::{IniFormat, TomlFormat, XmlJsonmlFormat, YamlFormat};
 
@@ -15,6 +19,8 @@ pub enum ManifestFormatName {
 	Toml,
 	XmlJsonml,
 	Ini,
+	/// Binary deterministic CBOR, best used together with `--output-file`
+	Cbor,
 }
 
 #[derive(Parser)]
@@ -33,18 +39,23 @@ pub struct ManifestOpts {
 	#[clap(long, short = 'y', conflicts_with = "string")]
 	yaml_stream: bool,
 	/// Number of spaces to pad output manifest with.
-	/// `0` for hard tabs, `-1` for single line output
+	/// `0` for single line json output
 	///
 	/// [default: 3 for json, 2 for yaml/toml]
 	#[clap(long)]
 	line_padding: Option<usize>,
+	/// String to indent output manifest with, instead of `--line-padding` spaces.
+	/// `\t` is replaced with tab character, i.e `--indent '\t'` indents json with tabs.
+	/// YAML doesn't allow tabs in indentation, so they are rejected for YAML output.
+	#[clap(long, conflicts_with = "line_padding")]
+	indent: Option<String>,
 	/// Preserve order in object manifestification
 	#[cfg(feature = "exp-preserve-order")]
 	#[clap(long)]
 	pub preserve_order: bool,
 }
 impl ManifestOpts {
-	pub fn manifest_format(&self) -> Box<dyn ManifestFormat> {
+	pub fn manifest_format(&self) -> Result<Box<dyn ManifestFormat>> {
 		let format: Box<dyn ManifestFormat> = if self.string {
 			Box::new(StringFormat)
 		} else {
@@ -55,20 +66,39 @@ impl ManifestOpts {
 				None if self.yaml_stream => ManifestFormatName::Yaml,
 				None => ManifestFormatName::Json,
 			};
+			let indent = self.indent.as_ref().map(|i| i.replace("\\t", "\t"));
+			let indent_or_padding =
+				|padding: usize| indent.clone().unwrap_or_else(|| " ".repeat(padding));
 			match format {
 				ManifestFormatName::String => Box::new(ToStringFormat),
-				ManifestFormatName::Json => Box::new(JsonFormat::cli(
-					self.line_padding.unwrap_or(3),
-					#[cfg(feature = "exp-preserve-order")]
-					preserve_order,
-				)),
-				ManifestFormatName::Yaml => Box::new(YamlFormat::cli(
-					self.line_padding.unwrap_or(2),
-					#[cfg(feature = "exp-preserve-order")]
-					preserve_order,
+				ManifestFormatName::Json => Box::new(indent.clone().map_or_else(
+					|| {
+						JsonFormat::cli(
+							self.line_padding.unwrap_or(3),
+							#[cfg(feature = "exp-preserve-order")]
+							preserve_order,
+						)
+					},
+					|indent| {
+						JsonFormat::cli_indent(
+							indent,
+							#[cfg(feature = "exp-preserve-order")]
+							preserve_order,
+						)
+					},
 				)),
-				ManifestFormatName::Toml => Box::new(TomlFormat::cli(
-					self.line_padding.unwrap_or(2),
+				ManifestFormatName::Yaml => {
+					if indent.as_ref().map_or(false, |i| i.contains('\t')) {
+						bail!("--indent can't contain tabs for YAML output, YAML doesn't allow tabs in indentation");
+					}
+					Box::new(YamlFormat::cli_indent(
+						indent_or_padding(self.line_padding.unwrap_or(2)),
+						#[cfg(feature = "exp-preserve-order")]
+						preserve_order,
+					))
+				}
+				ManifestFormatName::Toml => Box::new(TomlFormat::cli_indent(
+					indent_or_padding(self.line_padding.unwrap_or(2)),
 					#[cfg(feature = "exp-preserve-order")]
 					preserve_order,
 				)),
@@ -77,13 +107,14 @@ impl ManifestOpts {
 					#[cfg(feature = "exp-preserve-order")]
 					preserve_order,
 				)),
+				ManifestFormatName::Cbor => Box::new(CborFormat),
 			}
 		};
-		if self.yaml_stream {
+		Ok(if self.yaml_stream {
 			Box::new(YamlStreamFormat::cli(format))
 		} else {
 			format
-		}
+		})
 	}
 }
 
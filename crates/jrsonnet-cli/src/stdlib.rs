@@ -1,7 +1,12 @@
-use std::{fs::read_to_string, str::FromStr};
+use std::{
+	collections::HashSet,
+	fs::{read_dir, read_to_string},
+	path::{Path, PathBuf},
+	str::FromStr,
+};
 
 use clap::Parser;
-use jrsonnet_evaluator::{trace::PathResolver, Result};
+use jrsonnet_evaluator::{bail, trace::PathResolver, Error, Result, RuntimeError};
 use jrsonnet_stdlib::ContextInitializer;
 
 #[derive(Clone)]
@@ -76,6 +81,33 @@ impl FromStr for ExtFile {
 	}
 }
 
+fn io_error(path: &Path, e: &std::io::Error) -> Error {
+	RuntimeError(format!("{}: {e}", path.display()).into()).into()
+}
+
+/// Lists every file in `dir` as `(name, path)`, where name is the file stem.
+/// Subdirectories are skipped, entries are sorted by name.
+///
+/// Files themselves are only read once the variable is used.
+fn read_ext_str_dir(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+	let mut paths = Vec::new();
+	for entry in read_dir(dir).map_err(|e| io_error(dir, &e))? {
+		let path = entry.map_err(|e| io_error(dir, &e))?.path();
+		if !path.is_dir() {
+			paths.push(path);
+		}
+	}
+	paths.sort();
+	let mut out = Vec::with_capacity(paths.len());
+	for path in paths {
+		let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+			bail!("{}: file name is not valid utf-8", path.display());
+		};
+		out.push((name.to_owned(), path));
+	}
+	Ok(out)
+}
+
 #[derive(Parser)]
 #[clap(next_help_heading = "STANDARD LIBRARY")]
 pub struct StdOpts {
@@ -94,6 +126,12 @@ pub struct StdOpts {
 	/// See also `--ext-str`
 	#[clap(long, name = "name=var path", number_of_values = 1)]
 	ext_str_file: Vec<ExtFile>,
+	/// Read every file in directory as string external variable, named by file stem.
+	/// Names should not collide with any other external variable.
+	/// Files are only read once the variable is used.
+	/// See also `--ext-str-file`
+	#[clap(long, name = "var dir", number_of_values = 1)]
+	ext_str_file_dir: Vec<PathBuf>,
 	/// Add external variable from code.
 	/// See also `--ext-str`
 	#[clap(long, name = "name[=var source]", number_of_values = 1)]
@@ -121,6 +159,74 @@ impl StdOpts {
 		for ext in &self.ext_code_file {
 			ctx.add_ext_code(&ext.name as &str, &ext.value as &str)?;
 		}
+		let mut names = self
+			.ext_str
+			.iter()
+			.chain(&self.ext_code)
+			.map(|e| e.name.clone())
+			.chain(
+				self.ext_str_file
+					.iter()
+					.chain(&self.ext_code_file)
+					.map(|e| e.name.clone()),
+			)
+			.collect::<HashSet<_>>();
+		for dir in &self.ext_str_file_dir {
+			for (name, path) in read_ext_str_dir(dir)? {
+				if !names.insert(name.clone()) {
+					bail!(
+						"external variable {name:?} from {} is already defined",
+						dir.display()
+					);
+				}
+				ctx.add_ext_import_str(name.into(), path);
+			}
+		}
 		Ok(Some(ctx))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use clap::Parser;
+	use jrsonnet_evaluator::{FileImportResolver, State, Val};
+
+	use super::StdOpts;
+
+	#[test]
+	fn ext_str_file_dir() {
+		let temp = tempfile::tempdir().expect("temp dir is creatable");
+		let dir = temp.path();
+		fs::create_dir_all(dir.join("nested")).expect("temp dir is writable");
+		fs::write(dir.join("first.txt"), "hello").expect("temp dir is writable");
+		fs::write(dir.join("second"), "world").expect("temp dir is writable");
+		// Only read when used
+		fs::write(dir.join("binary"), [0xff]).expect("temp dir is writable");
+		let dir = dir.to_str().expect("temp dir is utf-8");
+
+		let opts = StdOpts::parse_from(["jrsonnet", "--ext-str-file-dir", dir]);
+		let mut s = State::builder();
+		s.context_initializer(
+			opts.context_initializer()
+				.unwrap()
+				.expect("stdlib is enabled"),
+		)
+		.import_resolver(FileImportResolver::default());
+		let s = s.build();
+		let v = s
+			.evaluate_snippet("<test>", "std.extVar('first') + ' ' + std.extVar('second')")
+			.unwrap();
+		assert!(matches!(v, Val::Str(s) if s.to_string() == "hello world"));
+		assert!(s
+			.evaluate_snippet("<test>", "std.extVar('binary')")
+			.is_err());
+
+		let opts = StdOpts::parse_from(["jrsonnet", "--ext-str-file-dir", dir, "-V", "first=x"]);
+		let Err(e) = opts.context_initializer() else {
+			panic!("collision should fail");
+		};
+		assert!(e.to_string().contains("\"first\""), "{e}");
+	}
+}
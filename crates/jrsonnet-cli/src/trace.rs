@@ -1,6 +1,6 @@
 use clap::{Parser, ValueEnum};
 use jrsonnet_evaluator::trace::{
-	CompactFormat, ExplainingFormat, HiDocFormat, PathResolver, TraceFormat,
+	CompactFormat, ExplainingFormat, HiDocFormat, PathResolver, TraceElision, TraceFormat,
 };
 
 #[derive(PartialEq, Eq, ValueEnum, Clone)]
@@ -23,11 +23,24 @@ pub struct TraceOpts {
 	/// If set to `0` then full stack trace will be displayed.
 	#[clap(long, short = 't', default_value = "20")]
 	max_trace: usize,
+	/// Number of frames to display from the top of stack trace, frames in the middle are elided.
+	/// If only `--trace-tail` is set, defaults to `0`.
+	#[clap(long)]
+	trace_head: Option<usize>,
+	/// Number of frames to display from the bottom of stack trace, frames in the middle are elided.
+	/// If only `--trace-head` is set, defaults to `0`.
+	#[clap(long)]
+	trace_tail: Option<usize>,
 }
 impl TraceOpts {
 	pub fn trace_format(&self) -> Box<dyn TraceFormat> {
 		let resolver = PathResolver::new_cwd_fallback();
 		let max_trace = self.max_trace;
+		let elision =
+			(self.trace_head.is_some() || self.trace_tail.is_some()).then(|| TraceElision {
+				head: self.trace_head.unwrap_or(0),
+				tail: self.trace_tail.unwrap_or(0),
+			});
 		let format: Box<dyn TraceFormat> = match self
 			.trace_format
 			.as_ref()
@@ -37,14 +50,17 @@ impl TraceOpts {
 				resolver,
 				padding: 4,
 				max_trace,
+				elision,
 			}),
 			TraceFormatName::Explaining => Box::new(ExplainingFormat {
 				resolver,
 				max_trace,
+				elision,
 			}),
 			TraceFormatName::HiDoc => Box::new(HiDocFormat {
 				resolver,
 				max_trace,
+				elision,
 			}),
 		};
 		format
@@ -0,0 +1,71 @@
+//! Measures memory retained by objects with a few fields, per field count
+
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	hint::black_box,
+	sync::atomic::{AtomicUsize, Ordering},
+	time::Instant,
+};
+
+use jrsonnet_evaluator::{ObjValue, ObjValueBuilder, Val};
+
+struct CountingAlloc;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+		unsafe { System.alloc(layout) }
+	}
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+		unsafe { System.dealloc(ptr, layout) };
+	}
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+		LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+		unsafe { System.realloc(ptr, layout, new_size) }
+	}
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const OBJECTS: usize = 10_000;
+const FIELD_NAMES: [&str; 4] = ["apiVersion", "kind", "name", "namespace"];
+
+fn build(fields: usize) -> Vec<ObjValue> {
+	(0..OBJECTS)
+		.map(|i| {
+			let mut builder = ObjValueBuilder::new();
+			for name in &FIELD_NAMES[..fields] {
+				builder.field(*name).value(Val::num(i as u32));
+			}
+			builder.build()
+		})
+		.collect()
+}
+
+fn main() {
+	// Warm up interner
+	black_box(build(FIELD_NAMES.len()));
+
+	for fields in 1..=FIELD_NAMES.len() {
+		let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+		let bytes_before = LIVE_BYTES.load(Ordering::Relaxed);
+		let start = Instant::now();
+		let objects = black_box(build(fields));
+		let elapsed = start.elapsed();
+		let bytes = LIVE_BYTES.load(Ordering::Relaxed) - bytes_before;
+		let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+		drop(objects);
+
+		println!(
+			"{OBJECTS} objects with {fields} field(s): {bytes:>9} bytes retained, {allocations:>6} allocations, {elapsed:?}",
+		);
+	}
+}
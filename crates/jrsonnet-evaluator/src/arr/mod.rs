@@ -1,4 +1,7 @@
-use std::{any::Any, num::NonZeroU32};
+use std::{
+	any::{Any, TypeId},
+	num::NonZeroU32,
+};
 
 use jrsonnet_gcmodule::{Cc, Trace};
 use jrsonnet_interner::IBytes;
@@ -62,6 +65,11 @@ impl ArrValue {
 		Self::new(<MappedArray<true>>::new(self, mapper))
 	}
 
+	/// Array of `[a[i], b[i], ...]` tuples, truncated to the shortest of `arrays`
+	pub fn zip(arrays: Vec<Self>) -> Self {
+		Self::new(ZipArray::new(arrays))
+	}
+
 	pub fn filter(self, filter: impl Fn(&Val) -> Result<bool>) -> Result<Self> {
 		// TODO: ArrValue::Picked(inner, indexes) for large arrays
 		let mut out = Vec::new();
@@ -107,7 +115,7 @@ impl ArrValue {
 	#[must_use]
 	pub fn slice(self, index: Option<i32>, end: Option<i32>, step: Option<NonZeroU32>) -> Self {
 		let get_idx = |pos: Option<i32>, len: usize, default| match pos {
-			Some(v) if v < 0 => len.saturating_sub((-v) as usize),
+			Some(v) if v < 0 => len.saturating_sub(v.unsigned_abs() as usize),
 			Some(v) => (v as usize).min(len),
 			None => default,
 		};
@@ -195,6 +203,16 @@ impl ArrValue {
 	pub fn as_any(&self) -> &dyn Any {
 		&self.0
 	}
+
+	/// Returns reference to underlying array implementation, if it is of type `T`
+	pub fn downcast_ref<T: ArrayLike>(&self) -> Option<&T> {
+		let inner: &dyn ArrayLike = &*self.0 .0;
+		if inner.type_id() != TypeId::of::<T>() {
+			return None;
+		}
+		// SAFETY: type id is checked above
+		Some(unsafe { &*std::ptr::from_ref(inner).cast::<T>() })
+	}
 }
 impl From<Vec<Val>> for ArrValue {
 	fn from(value: Vec<Val>) -> Self {
@@ -161,7 +161,7 @@ impl ArrayLike for ExprArray {
 		match &self.cached.borrow()[index] {
 			ArrayThunk::Computed(c) => return Ok(Some(c.clone())),
 			ArrayThunk::Errored(e) => return Err(e.clone()),
-			ArrayThunk::Pending => return Err(InfiniteRecursionDetected.into()),
+			ArrayThunk::Pending => return Err(InfiniteRecursionDetected(Vec::new()).into()),
 			ArrayThunk::Waiting(..) => {}
 		};
 
@@ -447,7 +447,7 @@ impl<const WITH_INDEX: bool> ArrayLike for MappedArray<WITH_INDEX> {
 		match &self.cached.borrow()[index] {
 			ArrayThunk::Computed(c) => return Ok(Some(c.clone())),
 			ArrayThunk::Errored(e) => return Err(e.clone()),
-			ArrayThunk::Pending => return Err(InfiniteRecursionDetected.into()),
+			ArrayThunk::Pending => return Err(InfiniteRecursionDetected(Vec::new()).into()),
 			ArrayThunk::Waiting(..) => {}
 		};
 
@@ -521,21 +521,21 @@ impl ArrayLike for RepeatedArray {
 	}
 
 	fn get(&self, index: usize) -> Result<Option<Val>> {
-		if index > self.total_len {
+		if index >= self.total_len {
 			return Ok(None);
 		}
 		self.data.get(index % self.data.len())
 	}
 
 	fn get_lazy(&self, index: usize) -> Option<Thunk<Val>> {
-		if index > self.total_len {
+		if index >= self.total_len {
 			return None;
 		}
 		self.data.get_lazy(index % self.data.len())
 	}
 
 	fn get_cheap(&self, index: usize) -> Option<Val> {
-		if index > self.total_len {
+		if index >= self.total_len {
 			return None;
 		}
 		self.data.get_cheap(index % self.data.len())
@@ -640,3 +640,47 @@ impl ArrayLike for PickObjectKeyValues {
 		false
 	}
 }
+
+#[derive(Trace, Debug)]
+pub struct ZipArray {
+	arrays: Vec<ArrValue>,
+	len: usize,
+}
+
+impl ZipArray {
+	pub fn new(arrays: Vec<ArrValue>) -> Self {
+		let len = arrays.iter().map(ArrValue::len).min().unwrap_or(0);
+		Self { arrays, len }
+	}
+	fn tuple(&self, index: usize) -> Val {
+		// Elements are picked lazily, zipping never forces input values
+		Val::Arr(ArrValue::lazy(
+			self.arrays
+				.iter()
+				.map(|arr| arr.get_lazy(index).expect("index checked"))
+				.collect(),
+		))
+	}
+}
+
+impl ArrayLike for ZipArray {
+	fn len(&self) -> usize {
+		self.len
+	}
+
+	fn get(&self, index: usize) -> Result<Option<Val>> {
+		Ok((index < self.len).then(|| self.tuple(index)))
+	}
+
+	fn get_lazy(&self, index: usize) -> Option<Thunk<Val>> {
+		(index < self.len).then(|| Thunk::evaluated(self.tuple(index)))
+	}
+
+	fn get_cheap(&self, _index: usize) -> Option<Val> {
+		None
+	}
+
+	fn is_cheap(&self) -> bool {
+		false
+	}
+}
@@ -0,0 +1,88 @@
+//! Limits total amount of work done by evaluator
+//!
+//! Every forced [`Thunk`](crate::Thunk) consumes one unit of budget, once the budget is spent,
+//! evaluation fails with [`ErrorKind::BudgetExceeded`]. Thunks are not bound to a [`State`](crate::State),
+//! so the budget is tracked per thread, same as the stack depth limit in [`crate::stack`].
+
+use std::cell::Cell;
+
+use crate::error::{ErrorKind, Result};
+
+struct ThunkBudget {
+	max_thunks: Cell<Option<usize>>,
+	evaluated_thunks: Cell<usize>,
+}
+
+#[cfg(feature = "nightly")]
+#[allow(clippy::thread_local_initializer_can_be_made_const)]
+#[thread_local]
+static THUNK_BUDGET: ThunkBudget = ThunkBudget {
+	max_thunks: Cell::new(None),
+	evaluated_thunks: Cell::new(0),
+};
+#[cfg(not(feature = "nightly"))]
+thread_local! {
+	static THUNK_BUDGET: ThunkBudget = const {
+		ThunkBudget {
+			max_thunks: Cell::new(None),
+			evaluated_thunks: Cell::new(0),
+		}
+	};
+}
+
+fn with_budget<T>(f: impl FnOnce(&ThunkBudget) -> T) -> T {
+	#[cfg(feature = "nightly")]
+	{
+		f(&THUNK_BUDGET)
+	}
+	#[cfg(not(feature = "nightly"))]
+	{
+		THUNK_BUDGET.with(f)
+	}
+}
+
+/// Called before thunk evaluation, fails if the budget is already spent
+pub(crate) fn consume_thunk() -> Result<()> {
+	with_budget(|budget| {
+		let evaluated = budget.evaluated_thunks.get();
+		if let Some(max) = budget.max_thunks.get() {
+			if evaluated >= max {
+				return Err(ErrorKind::BudgetExceeded(max).into());
+			}
+		}
+		budget.evaluated_thunks.set(evaluated + 1);
+		Ok(())
+	})
+}
+
+/// Returns number of thunks evaluated since the last [`limit_thunks`] call
+pub fn evaluated_thunks() -> usize {
+	with_budget(|budget| budget.evaluated_thunks.get())
+}
+
+pub struct ThunkBudgetOverrideGuard {
+	old_limit: Option<usize>,
+	old_evaluated: usize,
+}
+impl Drop for ThunkBudgetOverrideGuard {
+	fn drop(&mut self) {
+		with_budget(|budget| {
+			budget.max_thunks.set(self.old_limit);
+			budget.evaluated_thunks.set(self.old_evaluated);
+		});
+	}
+}
+
+/// Limits number of thunks which may be evaluated until returned guard is dropped,
+/// `None` means unlimited, which is the default
+pub fn limit_thunks(max_thunks: Option<usize>) -> ThunkBudgetOverrideGuard {
+	with_budget(|budget| {
+		let guard = ThunkBudgetOverrideGuard {
+			old_limit: budget.max_thunks.get(),
+			old_evaluated: budget.evaluated_thunks.get(),
+		};
+		budget.max_thunks.set(max_thunks);
+		budget.evaluated_thunks.set(0);
+		guard
+	})
+}
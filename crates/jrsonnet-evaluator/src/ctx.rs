@@ -68,7 +68,13 @@ impl Context {
 			}
 			heap.push((conf, k));
 		});
-		heap.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+		// Bindings are iterated in hash (address) order, tie-break by name to keep
+		// suggestions stable between runs
+		heap.sort_by(|a, b| {
+			b.0.partial_cmp(&a.0)
+				.unwrap_or(Ordering::Equal)
+				.then_with(|| a.1.cmp(&b.1))
+		});
 
 		bail!(VariableIsNotDefined(
 			name,
@@ -41,7 +41,7 @@ impl<T: Trace + Clone> ThunkValue for Pending<T> {
 
 	fn get(self: Box<Self>) -> Result<Self::Output> {
 		let Some(value) = self.0.get() else {
-			bail!(InfiniteRecursionDetected);
+			bail!(InfiniteRecursionDetected(Vec::new()));
 		};
 		Ok(value.clone())
 	}
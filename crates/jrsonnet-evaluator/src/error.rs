@@ -78,6 +78,11 @@ fn format_signature(sig: &FunctionSignature) -> String {
 	out
 }
 
+fn format_span_start(span: &Span) -> String {
+	let [start] = span.0.map_source_locations(&[span.1]);
+	format!("{}:{}", start.line, start.column.saturating_sub(1))
+}
+
 const fn format_empty_str(str: &str) -> &str {
 	if str.is_empty() {
 		"\"\" (empty string)"
@@ -132,6 +137,8 @@ pub enum ErrorKind {
 
 	#[error("array out of bounds: {0} is not within [0,{1})")]
 	ArrayBoundsError(isize, usize),
+	#[error("array of length {0} exceeds the limit of {1} elements")]
+	ArrayTooLong(usize, usize),
 	#[error("string out of bounds: {0} is not within [0,{1})")]
 	StringBoundsError(usize, usize),
 
@@ -148,6 +155,11 @@ pub enum ErrorKind {
 	#[error("no such field: {}{}", format_empty_str(.0), format_found(.1, "field"))]
 	NoSuchField(IStr, Vec<IStr>),
 
+	/// Value can't be written with the requested output mode, i.e `--multi` needs an object, and
+	/// yaml stream needs an array
+	#[error("manifest output should be {expected}, got {got}")]
+	ManifestTypeMismatch { expected: ValType, got: ValType },
+
 	#[error("only functions can be called, got {0}")]
 	OnlyFunctionsCanBeCalledGot(ValType),
 	#[error("parameter {0} is not defined")]
@@ -166,6 +178,13 @@ pub enum ErrorKind {
 	FieldMustBeStringGot(ValType),
 	#[error("duplicate field name: {}", format_empty_str(.0))]
 	DuplicateFieldName(IStr),
+	#[error(
+		"duplicate field name: {}, first defined at {}, redefined at {}",
+		format_empty_str(.0),
+		format_span_start(.1),
+		format_span_start(.2)
+	)]
+	DuplicateFieldNameDefinitions(IStr, Span, Span),
 
 	#[error("attempted to index array with string {}", format_empty_str(.0))]
 	AttemptedIndexAnArrayWithString(IStr),
@@ -189,6 +208,12 @@ pub enum ErrorKind {
 	ImportIsADirectory(SourcePath),
 	#[error("imported file is not valid utf-8: {0:?}")]
 	ImportBadFileUtf8(SourcePath),
+	#[error("can't import {0}: file is larger than {1} bytes")]
+	ImportFileTooLarge(SourcePath, u64),
+	#[error("can't import {}: resolved path is outside of root {}", .0.display(), .1.display())]
+	ImportOutsideRootJail(PathBuf, PathBuf),
+	#[error("can't import through symlink {}", .0.display())]
+	ImportThroughSymlink(PathBuf),
 	#[error("import io error: {0}")]
 	ImportIo(String),
 	#[error("tried to import {1} from {0}, but imports are not supported")]
@@ -219,20 +244,37 @@ pub enum ErrorKind {
 	RuntimeError(IStr),
 	#[error("stack overflow, try to reduce recursion, or set --max-stack to bigger value")]
 	StackOverflow,
+	#[error("evaluation budget exceeded: more than {0} thunks evaluated, try to set --max-thunks to bigger value")]
+	BudgetExceeded(usize),
+	/// Value depends on itself
+	///
+	/// For import cycles contains chain of imported files, starting and ending with the same file,
+	/// otherwise empty. Chain is not included in error message, it is rendered by trace formatters
 	#[error("infinite recursion detected")]
-	InfiniteRecursionDetected,
+	InfiniteRecursionDetected(Vec<SourcePath>),
+	/// Import chain is nested deeper than [`StateBuilder::max_import_depth`] allows
+	///
+	/// Contains chain of imported files, starting with the outermost one, rendered by trace formatters
+	///
+	/// [`StateBuilder::max_import_depth`]: crate::StateBuilder::max_import_depth
+	#[error("import depth limit exceeded: more than {0} nested imports")]
+	ImportTooDeep(usize, Vec<SourcePath>),
 	#[error("tried to index by fractional value")]
 	FractionalIndex,
 	#[error("attempted to divide by zero")]
 	DivisionByZero,
 
+	#[error("invalid json pointer {0:?}: should be empty, or start with /")]
+	InvalidJsonPointer(String),
+	/// Prefix is the longest part of json pointer, which was resolved successfully
+	#[error("json pointer {prefix:?} has no {segment:?} element")]
+	JsonPointerNotFound { prefix: String, segment: String },
+
 	#[error("string manifest output is not an string")]
 	StringManifestOutputIsNotAString,
-	#[error("stream manifest output is not an array")]
-	StreamManifestOutputIsNotAArray,
-	#[error("multi manifest output is not an object")]
-	MultiManifestOutputIsNotAObject,
 
+	#[error("failed to write manifest output")]
+	ManifestOutputWriteFailed,
 	#[error("cant recurse stream manifest")]
 	StreamManifestOutputCannotBeRecursed,
 	#[error("stream manifest output cannot consist of raw strings")]
@@ -269,6 +311,12 @@ impl From<ErrorKind> for Error {
 	}
 }
 
+impl From<std::fmt::Error> for Error {
+	fn from(_value: std::fmt::Error) -> Self {
+		Self::new(ErrorKind::ManifestOutputWriteFailed)
+	}
+}
+
 impl From<Infallible> for Error {
 	fn from(_value: Infallible) -> Self {
 		unreachable!()
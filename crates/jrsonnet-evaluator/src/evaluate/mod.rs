@@ -4,7 +4,7 @@ use jrsonnet_gcmodule::{Cc, Trace};
 use jrsonnet_interner::IStr;
 use jrsonnet_parser::{
 	ArgsDesc, AssertStmt, BinaryOpType, BindSpec, CompSpec, Expr, FieldMember, FieldName,
-	ForSpecData, IfSpecData, LiteralType, LocExpr, Member, ObjBody, ParamsDesc,
+	ForSpecData, IfSpecData, LiteralType, LocExpr, Member, ObjBody, ParamsDesc, Span,
 };
 use jrsonnet_types::ValType;
 
@@ -106,6 +106,10 @@ pub fn evaluate_field_name(ctx: Context, field_name: &FieldName) -> Result<Optio
 	})
 }
 
+/// Calls `callback` with the context of every comprehension iteration
+///
+/// Iteration contexts only add the loop variables on top of `ctx`, so locals defined outside of the
+/// comprehension are shared thunks, evaluated at most once regardless of the iteration count.
 pub fn evaluate_comp(
 	ctx: Context,
 	specs: &[CompSpec],
@@ -283,8 +287,83 @@ pub fn evaluate_field_member<B: Unbound<Bound = Context> + Clone>(
 	Ok(())
 }
 
+/// Collects names of `self` fields, statically referenced by assertion condition, i.e `self.x` or `self['x']`
+fn referenced_self_fields(expr: &LocExpr, out: &mut Vec<IStr>) {
+	match expr.expr() {
+		Expr::Index { indexable, parts } => {
+			if let (Expr::Literal(LiteralType::This), Some(first)) =
+				(indexable.expr(), parts.first())
+			{
+				if let Expr::Str(name) = first.value.expr() {
+					if !out.contains(name) {
+						out.push(name.clone());
+					}
+				}
+			}
+			referenced_self_fields(indexable, out);
+			for part in parts {
+				referenced_self_fields(&part.value, out);
+			}
+		}
+		Expr::BinaryOp(a, _, b) => {
+			referenced_self_fields(a, out);
+			referenced_self_fields(b, out);
+		}
+		Expr::UnaryOp(_, v) | Expr::Parened(v) => referenced_self_fields(v, out),
+		Expr::Apply(f, args, _) => {
+			referenced_self_fields(f, out);
+			for arg in args.unnamed.iter().chain(args.named.iter().map(|(_, v)| v)) {
+				referenced_self_fields(arg, out);
+			}
+		}
+		Expr::IfElse {
+			cond,
+			cond_then,
+			cond_else,
+		} => {
+			referenced_self_fields(&cond.0, out);
+			referenced_self_fields(cond_then, out);
+			if let Some(cond_else) = cond_else {
+				referenced_self_fields(cond_else, out);
+			}
+		}
+		_ => {}
+	}
+}
+
+/// Fails on fields with the same fixed name in a single object literal
+fn check_duplicate_fields(members: &[Member]) -> Result<()> {
+	let mut defined = GcHashMap::new();
+	for member in members {
+		let Member::Field(FieldMember {
+			name: FieldName::Fixed(name),
+			value,
+			..
+		}) = member
+		else {
+			continue;
+		};
+		if let Some(first) = defined.insert(name.clone(), value.span()) {
+			bail!(DuplicateFieldNameDefinitions(
+				name.clone(),
+				first,
+				value.span()
+			));
+		}
+	}
+	Ok(())
+}
+
 #[allow(clippy::too_many_lines)]
-pub fn evaluate_member_list_object(ctx: Context, members: &[Member]) -> Result<ObjValue> {
+pub fn evaluate_member_list_object(
+	ctx: Context,
+	members: &[Member],
+	span: &Span,
+) -> Result<ObjValue> {
+	if ctx.state().strict_duplicate_fields() {
+		check_duplicate_fields(members)?;
+	}
+
 	let mut builder = ObjValueBuilder::new();
 	let locals = Rc::new(
 		members
@@ -311,16 +390,35 @@ pub fn evaluate_member_list_object(ctx: Context, members: &[Member]) -> Result<O
 				struct ObjectAssert<B: Trace> {
 					uctx: B,
 					assert: AssertStmt,
+					span: Span,
 				}
 				impl<B: Unbound<Bound = Context>> ObjectAssertion for ObjectAssert<B> {
 					fn run(&self, sup: Option<ObjValue>, this: Option<ObjValue>) -> Result<()> {
 						let ctx = self.uctx.bind(sup, this)?;
-						evaluate_assert(ctx, &self.assert)
+						in_frame(
+							CallLocation::new(&self.span),
+							|| {
+								let mut fields = Vec::new();
+								referenced_self_fields(&self.assert.0, &mut fields);
+								if fields.is_empty() {
+									return "object assertion".to_owned();
+								}
+								let plural = if fields.len() == 1 { "" } else { "s" };
+								let fields = fields
+									.iter()
+									.map(|f| format!("<{f}>"))
+									.collect::<Vec<_>>()
+									.join(", ");
+								format!("object assertion on field{plural} {fields}")
+							},
+							|| evaluate_assert(ctx, &self.assert),
+						)
 					}
 				}
 				builder.assert(ObjectAssert {
 					uctx: uctx.clone(),
 					assert: stmt.clone(),
+					span: span.clone(),
 				});
 			}
 			Member::BindStmt(_) => {
@@ -333,9 +431,9 @@ pub fn evaluate_member_list_object(ctx: Context, members: &[Member]) -> Result<O
 	Ok(this)
 }
 
-pub fn evaluate_object(ctx: Context, object: &ObjBody) -> Result<ObjValue> {
+pub fn evaluate_object(ctx: Context, object: &ObjBody, span: &Span) -> Result<ObjValue> {
 	Ok(match object {
-		ObjBody::MemberList(members) => evaluate_member_list_object(ctx, members)?,
+		ObjBody::MemberList(members) => evaluate_member_list_object(ctx, members, span)?,
 		ObjBody::ObjComp(obj) => {
 			let mut builder = ObjValueBuilder::new();
 			let locals = Rc::new(
@@ -609,10 +707,10 @@ pub fn evaluate(ctx: Context, expr: &LocExpr) -> Result<Val> {
 			})?;
 			Val::Arr(ArrValue::lazy(out))
 		}
-		Obj(body) => Val::Obj(evaluate_object(ctx, body)?),
+		Obj(body) => Val::Obj(evaluate_object(ctx, body, &loc)?),
 		ObjExtend(a, b) => evaluate_add_op(
 			&evaluate(ctx.clone(), a)?,
-			&Val::Obj(evaluate_object(ctx, b)?),
+			&Val::Obj(evaluate_object(ctx, b, &loc)?),
 		)?,
 		Apply(value, args, tailstrict) => ensure_sufficient_stack(|| {
 			evaluate_apply(ctx, value, args, CallLocation::new(&loc), *tailstrict)
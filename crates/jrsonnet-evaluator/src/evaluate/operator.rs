@@ -25,8 +25,59 @@ pub fn evaluate_unary_op(op: UnaryOpType, b: &Val) -> Result<Val> {
 	})
 }
 
+/// Converts number operand of a bigint operation, only integral numbers can be mixed with bigints
+#[cfg(feature = "exp-bigint")]
+fn num_to_bigint(n: crate::val::NumValue) -> Result<num_bigint::BigInt> {
+	if let Some(v) = n.as_i64_exact() {
+		return Ok(v.into());
+	}
+	if !n.is_integral() {
+		bail!("can't mix bigint with non-integral number {n}")
+	}
+	// f64 Display never uses exponent notation, so every digit is kept here
+	Ok(n.to_string()
+		.parse()
+		.expect("integral float is a valid integer"))
+}
+
+#[cfg(feature = "exp-bigint")]
+fn evaluate_bigint_op(
+	a: &num_bigint::BigInt,
+	op: BinaryOpType,
+	b: &num_bigint::BigInt,
+) -> Result<Val> {
+	use BinaryOpType::*;
+	Ok(Val::BigInt(Box::new(match op {
+		Add => a + b,
+		Sub => a - b,
+		Mul => a * b,
+		Div | Mod if *b == num_bigint::BigInt::ZERO => bail!(DivisionByZero),
+		// Both are truncating, the same way as for numbers
+		Div => a / b,
+		Mod => a % b,
+		_ => unreachable!("not an arithmetic operator: {op}"),
+	})))
+}
+
+/// Handles arithmetic operators, where at least one of operands is bigint, and the other is bigint or
+/// integral number
+#[cfg(feature = "exp-bigint")]
+fn evaluate_bigint_arith_op(a: &Val, op: BinaryOpType, b: &Val) -> Option<Result<Val>> {
+	use Val::*;
+	Some(match (a, b) {
+		(BigInt(a), BigInt(b)) => evaluate_bigint_op(a, op, b),
+		(BigInt(a), Num(b)) => num_to_bigint(*b).and_then(|b| evaluate_bigint_op(a, op, &b)),
+		(Num(a), BigInt(b)) => num_to_bigint(*a).and_then(|a| evaluate_bigint_op(&a, op, b)),
+		_ => return None,
+	})
+}
+
 pub fn evaluate_add_op(a: &Val, b: &Val) -> Result<Val> {
 	use Val::*;
+	#[cfg(feature = "exp-bigint")]
+	if let Some(v) = evaluate_bigint_arith_op(a, BinaryOpType::Add, b) {
+		return v;
+	}
 	Ok(match (a, b) {
 		(Str(v1), Str(v2)) => Str(StrValue::concat(v1.clone(), v2.clone())),
 
@@ -41,8 +92,6 @@ pub fn evaluate_add_op(a: &Val, b: &Val) -> Result<Val> {
 		(Arr(a), Arr(b)) => Val::Arr(ArrValue::extended(a.clone(), b.clone())),
 
 		(Num(v1), Num(v2)) => Val::try_num(v1.get() + v2.get())?,
-		#[cfg(feature = "exp-bigint")]
-		(BigInt(a), BigInt(b)) => BigInt(Box::new(&**a + &**b)),
 		_ => bail!(BinaryOperatorDoesNotOperateOnValues(
 			BinaryOpType::Add,
 			a.value_type(),
@@ -53,6 +102,10 @@ pub fn evaluate_add_op(a: &Val, b: &Val) -> Result<Val> {
 
 pub fn evaluate_mod_op(a: &Val, b: &Val) -> Result<Val> {
 	use Val::*;
+	#[cfg(feature = "exp-bigint")]
+	if let Some(v) = evaluate_bigint_arith_op(a, BinaryOpType::Mod, b) {
+		return v;
+	}
 	match (a, b) {
 		(Num(a), Num(b)) => {
 			if b.get() == 0.0 {
@@ -120,6 +173,12 @@ pub fn evaluate_compare_op(a: &Val, b: &Val, op: BinaryOpType) -> Result<Orderin
 pub fn evaluate_binary_op_normal(a: &Val, op: BinaryOpType, b: &Val) -> Result<Val> {
 	use BinaryOpType::*;
 	use Val::*;
+	#[cfg(feature = "exp-bigint")]
+	if matches!(op, Sub | Mul | Div) {
+		if let Some(v) = evaluate_bigint_arith_op(a, op, b) {
+			return v;
+		}
+	}
 	Ok(match (a, op, b) {
 		(a, Add, b) => evaluate_add_op(a, b)?,
 
@@ -169,12 +228,6 @@ pub fn evaluate_binary_op_normal(a: &Val, op: BinaryOpType, b: &Val) -> Result<V
 			Val::try_num((v1.get() as i64).wrapping_shr(exp) as f64)?
 		}
 
-		// Bigint X Bigint
-		#[cfg(feature = "exp-bigint")]
-		(BigInt(a), Mul, BigInt(b)) => BigInt(Box::new(&**a * &**b)),
-		#[cfg(feature = "exp-bigint")]
-		(BigInt(a), Sub, BigInt(b)) => BigInt(Box::new(&**a - &**b)),
-
 		_ => bail!(BinaryOperatorDoesNotOperateOnValues(
 			op,
 			a.value_type(),
@@ -1,9 +1,11 @@
+use std::path::{Path, PathBuf};
+
 use hashbrown::HashMap;
 use jrsonnet_gcmodule::Trace;
 use jrsonnet_interner::IStr;
 use jrsonnet_parser::{ArgsDesc, LocExpr};
 
-use crate::{evaluate, gc::GcHashMap, typed::Typed, Context, Result, Thunk, Val};
+use crate::{evaluate, gc::GcHashMap, typed::Typed, Context, Result, State, Thunk, Val};
 
 /// Marker for arguments, which can be evaluated with context set to None
 pub trait OptionalContext {}
@@ -43,7 +45,14 @@ pub enum TlaArg {
 	Code(LocExpr),
 	Val(Val),
 	Lazy(Thunk<Val>),
+	/// Contents of the file, read with the import resolver once argument is evaluated, as `importstr` does
+	ImportStr(PathBuf),
+}
+fn import_str(state: &State, path: &Path) -> Result<Val> {
+	let resolved = state.resolve(path)?;
+	Ok(Val::string(state.import_resolved_str(resolved)?))
 }
+
 impl ArgLike for TlaArg {
 	fn evaluate_arg(&self, ctx: Context, tailstrict: bool) -> Result<Thunk<Val>> {
 		match self {
@@ -56,6 +65,13 @@ impl ArgLike for TlaArg {
 			}),
 			Self::Val(val) => Ok(Thunk::evaluated(val.clone())),
 			Self::Lazy(lazy) => Ok(lazy.clone()),
+			Self::ImportStr(path) => Ok(if tailstrict {
+				Thunk::evaluated(import_str(ctx.state(), path)?)
+			} else {
+				let state = ctx.state().clone();
+				let path = path.clone();
+				Thunk!(move || import_str(&state, &path))
+			}),
 		}
 	}
 }
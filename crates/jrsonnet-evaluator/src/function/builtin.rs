@@ -142,3 +142,51 @@ impl Builtin for NativeCallback {
 pub trait NativeCallbackHandler: Trace {
 	fn call(&self, args: &[Val]) -> Result<Val>;
 }
+
+/// Builtin backed by a plain Rust function, with anonymous positional parameters
+///
+/// Function pointer can't capture anything, values it needs are passed explicitly as `state`,
+/// which is traced by GC.
+///
+/// Use [`super::FuncVal::from_closure_1`] and co. to construct it
+#[derive(Trace)]
+pub struct ClosureBuiltin<T: Trace + 'static> {
+	params: Vec<BuiltinParam>,
+	state: T,
+	#[trace(skip)]
+	handler: fn(&T, &[Val]) -> Result<Val>,
+}
+impl<T: Trace + 'static> ClosureBuiltin<T> {
+	/// `handler` is always called with exactly `arity` arguments
+	pub fn new(arity: usize, state: T, handler: fn(&T, &[Val]) -> Result<Val>) -> Self {
+		Self {
+			params: vec![BuiltinParam::new(ParamName::ANONYMOUS, ParamDefault::None); arity],
+			state,
+			handler,
+		}
+	}
+}
+
+impl<T: Trace + 'static> Builtin for ClosureBuiltin<T> {
+	fn name(&self) -> &str {
+		"<closure>"
+	}
+
+	fn params(&self) -> &[BuiltinParam] {
+		&self.params
+	}
+
+	fn call(&self, ctx: Context, _loc: CallLocation<'_>, args: &dyn ArgsLike) -> Result<Val> {
+		let args = parse_builtin_call(ctx, &self.params, args, true)?;
+		let args = args
+			.into_iter()
+			.map(|a| a.expect("closure builtins have no default params"))
+			.map(|a| a.evaluate())
+			.collect::<Result<Vec<Val>>>()?;
+		(self.handler)(&self.state, &args)
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
@@ -8,7 +8,7 @@ use jrsonnet_parser::{Destruct, Expr, LocExpr, ParamsDesc, Span};
 
 use self::{
 	arglike::OptionalContext,
-	builtin::{Builtin, BuiltinParam, ParamDefault, ParamName, StaticBuiltin},
+	builtin::{Builtin, BuiltinParam, ClosureBuiltin, ParamDefault, ParamName, StaticBuiltin},
 	native::NativeDesc,
 	parse::{parse_default_function_call, parse_function_call},
 };
@@ -133,6 +133,54 @@ impl FuncVal {
 	pub fn static_builtin(static_builtin: &'static dyn StaticBuiltin) -> Self {
 		Self::StaticBuiltin(static_builtin)
 	}
+	/// Function of a single positional argument, implemented by Rust function
+	///
+	/// Function can't capture anything, see [`Self::from_closure_1_with`] for passing state to it.
+	/// For anything more complex, prefer `#[builtin]` macro.
+	pub fn from_closure_1(f: fn(Val) -> Result<Val>) -> Self {
+		Self::builtin(ClosureBuiltin::new(1, f, |f, args| f(args[0].clone())))
+	}
+	/// Same as [`Self::from_closure_1`], but for two arguments
+	pub fn from_closure_2(f: fn(Val, Val) -> Result<Val>) -> Self {
+		Self::builtin(ClosureBuiltin::new(2, f, |f, args| {
+			f(args[0].clone(), args[1].clone())
+		}))
+	}
+	/// Same as [`Self::from_closure_1`], but for three arguments
+	pub fn from_closure_3(f: fn(Val, Val, Val) -> Result<Val>) -> Self {
+		Self::builtin(ClosureBuiltin::new(3, f, |f, args| {
+			f(args[0].clone(), args[1].clone(), args[2].clone())
+		}))
+	}
+	/// Same as [`Self::from_closure_1`], but `f` is also called with `state`
+	///
+	/// State is traced by GC, so it may hold GC-managed values, i.e `Val`
+	pub fn from_closure_1_with<T: Trace + 'static>(
+		state: T,
+		f: fn(&T, Val) -> Result<Val>,
+	) -> Self {
+		Self::builtin(ClosureBuiltin::new(1, (state, f), |(state, f), args| {
+			f(state, args[0].clone())
+		}))
+	}
+	/// Same as [`Self::from_closure_1_with`], but for two arguments
+	pub fn from_closure_2_with<T: Trace + 'static>(
+		state: T,
+		f: fn(&T, Val, Val) -> Result<Val>,
+	) -> Self {
+		Self::builtin(ClosureBuiltin::new(2, (state, f), |(state, f), args| {
+			f(state, args[0].clone(), args[1].clone())
+		}))
+	}
+	/// Same as [`Self::from_closure_1_with`], but for three arguments
+	pub fn from_closure_3_with<T: Trace + 'static>(
+		state: T,
+		f: fn(&T, Val, Val, Val) -> Result<Val>,
+	) -> Self {
+		Self::builtin(ClosureBuiltin::new(3, (state, f), |(state, f), args| {
+			f(state, args[0].clone(), args[1].clone(), args[2].clone())
+		}))
+	}
 
 	pub fn params(&self) -> Vec<BuiltinParam> {
 		match self {
@@ -107,7 +107,11 @@ pub fn parse_function_call(
 
 		// Some args still weren't filled
 		if filled_named + filled_positionals != params.len() {
-			for param in params.iter().skip(args.unnamed_len()) {
+			for param in params
+				.iter()
+				.skip(args.unnamed_len())
+				.filter(|p| p.1.is_none())
+			{
 				let mut found = false;
 				args.named_names(&mut |name| {
 					if Some(name) == param.0.name().as_ref() {
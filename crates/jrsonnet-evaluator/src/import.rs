@@ -39,6 +39,19 @@ pub trait ImportResolver: Trace {
 	/// This should only be called with value returned from [`ImportResolver::resolve_file`]/[`ImportResolver::resolve`],
 	/// this cannot be resolved using associated type, as evaluator uses object instead of generic for [`ImportResolver`]
 	fn load_file_contents(&self, resolved: &SourcePath) -> Result<Vec<u8>>;
+	/// Post-processes contents returned by [`ImportResolver::load_file_contents`] before they are parsed,
+	/// or returned from `importstr`/`importbin`, e.g to decrypt or render stored files.
+	///
+	/// Default implementation returns contents as-is
+	fn transform_contents(&self, _resolved: &SourcePath, contents: Vec<u8>) -> Result<Vec<u8>> {
+		Ok(contents)
+	}
+	/// Loads file with [`ImportResolver::load_file_contents`], and applies [`ImportResolver::transform_contents`],
+	/// this is how evaluator reads every imported file
+	fn load_transformed_contents(&self, resolved: &SourcePath) -> Result<Vec<u8>> {
+		let data = self.load_file_contents(resolved)?;
+		self.transform_contents(resolved, data)
+	}
 
 	// For downcasts, will be removed after trait_upcasting_coercion
 	// stabilization.
@@ -74,15 +87,87 @@ pub struct FileImportResolver {
 	/// Library directories to search for file.
 	/// Referred to as `jpath` in original jsonnet implementation.
 	library_paths: Vec<PathBuf>,
+	/// Maximum size of imported file in bytes, unlimited if not set.
+	max_file_size: Option<u64>,
+	/// Canonicalized directory, outside of which files can't be imported.
+	root_jail: Option<PathBuf>,
+	/// Refuse to import files through symlinks.
+	reject_symlinks: bool,
+	/// Directory used to resolve imports from snippets, which have no file of their own.
+	/// Current working directory if not set.
+	default_base: Option<PathBuf>,
 }
 impl FileImportResolver {
 	pub fn new(library_paths: Vec<PathBuf>) -> Self {
-		Self { library_paths }
+		Self {
+			library_paths,
+			..Self::default()
+		}
+	}
+	/// Refuse to load files larger than `bytes`
+	#[must_use]
+	pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+		self.max_file_size = Some(bytes);
+		self
+	}
+	/// Refuse to import files, which are resolved (after following all symlinks and `..` components)
+	/// to a path outside of `root`
+	#[must_use]
+	pub fn with_root_jail(mut self, root: PathBuf) -> Self {
+		// Nonexisting root can't contain anything, canonicalization failure will be reported on import
+		self.root_jail = Some(root.canonicalize().unwrap_or(root));
+		self
+	}
+	/// Refuse to import files, if the imported path itself, or any directory introduced by the import
+	/// path (i.e not the directory of importing file/library path) is a symlink.
+	///
+	/// For entry files, only the file itself is checked, directories leading to it are trusted
+	#[must_use]
+	pub fn with_reject_symlinks(mut self, reject: bool) -> Self {
+		self.reject_symlinks = reject;
+		self
+	}
+	/// Resolve imports from snippets (i.e `-e` code or stdin) relative to `base`,
+	/// instead of the current working directory
+	#[must_use]
+	pub fn with_default_base(mut self, base: PathBuf) -> Self {
+		self.default_base = Some(base);
+		self
 	}
 	/// Dynamically add new jpath, used by bindings
 	pub fn add_jpath(&mut self, path: PathBuf) {
 		self.library_paths.push(path);
 	}
+
+	/// [`check_path`], which also enforces root jail and symlink policy before anything is read
+	fn check_path(&self, base: Option<&Path>, path: &Path) -> Result<Option<SourcePath>> {
+		if self.root_jail.is_none() && !self.reject_symlinks {
+			return check_path(path);
+		}
+		match fs::metadata(path) {
+			Ok(_) => {}
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+			Err(e) => bail!(ImportIo(e.to_string())),
+		}
+		if self.reject_symlinks {
+			for ancestor in path.ancestors() {
+				if ancestor.as_os_str().is_empty() || Some(ancestor) == base {
+					break;
+				}
+				let meta = fs::symlink_metadata(ancestor).map_err(|e| ImportIo(e.to_string()))?;
+				if meta.file_type().is_symlink() {
+					bail!(ImportThroughSymlink(ancestor.to_owned()));
+				}
+			}
+		}
+		if let Some(root) = &self.root_jail {
+			let resolved = path.canonicalize().map_err(|e| ImportIo(e.to_string()))?;
+			if !resolved.starts_with(root) {
+				bail!(ImportOutsideRootJail(resolved, root.clone()));
+			}
+		}
+		check_path(path)
+	}
 }
 
 /// Create `SourcePath` from path, handling directories/Fifo files (on unix)/etc
@@ -125,26 +210,31 @@ impl ImportResolver for FileImportResolver {
 		} else if let Some(d) = from.downcast_ref::<SourceDirectory>() {
 			d.path().to_owned()
 		} else if from.is_default() {
-			current_dir().map_err(|e| ImportIo(e.to_string()))?
+			if let Some(base) = &self.default_base {
+				base.clone()
+			} else {
+				current_dir().map_err(|e| ImportIo(e.to_string()))?
+			}
 		} else {
 			unreachable!("resolver can't return this path")
 		};
 
+		let base = direct.clone();
 		direct.push(path);
-		if let Some(direct) = check_path(&direct)? {
+		if let Some(direct) = self.check_path(Some(&base), &direct)? {
 			return Ok(direct);
 		}
 		for library_path in &self.library_paths {
 			let mut cloned = library_path.clone();
 			cloned.push(path);
-			if let Some(cloned) = check_path(&cloned)? {
+			if let Some(cloned) = self.check_path(Some(library_path), &cloned)? {
 				return Ok(cloned);
 			}
 		}
 		bail!(ImportFileNotFound(from.clone(), path.to_owned()))
 	}
 	fn resolve(&self, path: &Path) -> Result<SourcePath> {
-		let Some(source) = check_path(path)? else {
+		let Some(source) = self.check_path(path.parent(), path)? else {
 			bail!(AbsoluteImportFileNotFound(path.to_owned()))
 		};
 		Ok(source)
@@ -156,14 +246,34 @@ impl ImportResolver for FileImportResolver {
 		} else if id.downcast_ref::<SourceDirectory>().is_some() {
 			bail!(ImportIsADirectory(id.clone()))
 		} else if let Some(f) = id.downcast_ref::<SourceFifo>() {
+			if let Some(max) = self.max_file_size {
+				if f.1.len() as u64 > max {
+					bail!(ImportFileTooLarge(id.clone(), max));
+				}
+			}
 			return Ok(f.1.to_vec());
 		} else {
 			unreachable!("other types are not supported in resolve");
 		};
 		let mut file = File::open(path).map_err(|_e| ResolvedFileNotFound(id.clone()))?;
 		let mut out = Vec::new();
-		file.read_to_end(&mut out)
-			.map_err(|e| ImportIo(e.to_string()))?;
+		if let Some(max) = self.max_file_size {
+			let meta = file.metadata().map_err(|e| ImportIo(e.to_string()))?;
+			if meta.len() > max {
+				bail!(ImportFileTooLarge(id.clone(), max));
+			}
+			// File might grow between metadata check and read
+			file.by_ref()
+				.take(max + 1)
+				.read_to_end(&mut out)
+				.map_err(|e| ImportIo(e.to_string()))?;
+			if out.len() as u64 > max {
+				bail!(ImportFileTooLarge(id.clone(), max));
+			}
+		} else {
+			file.read_to_end(&mut out)
+				.map_err(|e| ImportIo(e.to_string()))?;
+		}
 		Ok(out)
 	}
 
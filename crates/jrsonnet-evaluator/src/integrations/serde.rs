@@ -159,12 +159,10 @@ impl Serialize for Val {
 			Self::Null => serializer.serialize_none(),
 			Self::Str(s) => serializer.serialize_str(&s.clone().into_flat()),
 			Self::Num(n) => {
-				let n = n.get();
-				if n.fract() == 0.0 {
-					let n = n as i64;
+				if let Some(n) = n.as_i64_exact() {
 					serializer.serialize_i64(n)
 				} else {
-					serializer.serialize_f64(n)
+					serializer.serialize_f64(n.get())
 				}
 			}
 			#[cfg(feature = "exp-bigint")]
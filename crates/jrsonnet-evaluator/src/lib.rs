@@ -7,6 +7,7 @@ extern crate self as jrsonnet_evaluator;
 mod arr;
 #[cfg(feature = "async-import")]
 pub mod async_import;
+pub mod budget;
 mod ctx;
 mod dynamic;
 pub mod error;
@@ -18,6 +19,7 @@ mod integrations;
 pub mod manifest;
 mod map;
 mod obj;
+pub mod profile;
 pub mod stack;
 pub mod stdlib;
 mod tla;
@@ -170,6 +172,25 @@ impl_context_initializer! {
 	A @ B C D E F G
 }
 
+/// Runs every initializer in order, for when the list of initializers is only known at runtime
+impl ContextInitializer for Vec<TraceBox<dyn ContextInitializer>> {
+	fn reserve_vars(&self) -> usize {
+		self.iter().map(|i| i.reserve_vars()).sum()
+	}
+	fn populate(&self, for_file: Source, builder: &mut ContextBuilder) {
+		for initializer in self {
+			initializer.populate(for_file.clone(), builder);
+		}
+	}
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+/// Cached file, loaded once per [`SourcePath`]
+///
+/// Both string and bytes representations are computed lazily and cached, so the same file
+/// imported with `importstr` and `importbin` is converted at most once.
 #[derive(Trace)]
 struct FileData {
 	string: Option<IStr>,
@@ -210,12 +231,58 @@ impl FileData {
 		}
 		Some(self.string.clone().expect("just set"))
 	}
+	pub(crate) fn get_bytes(&mut self) -> IBytes {
+		if self.bytes.is_none() {
+			self.bytes = Some(
+				self.string
+					.as_ref()
+					.expect("either string or bytes should be set")
+					.clone()
+					.cast_bytes(),
+			);
+		}
+		self.bytes.clone().expect("just set")
+	}
+}
+
+/// Non-fatal diagnostic, reported during evaluation
+///
+/// Collected on [`State`], use [`State::take_warnings`] to retrieve them
+#[derive(Debug, Clone, PartialEq, Eq, Trace)]
+#[trace(skip)]
+pub enum Warning {
+	/// Builtin is kept for compatibility, and may be removed in future
+	DeprecatedBuiltin {
+		name: &'static str,
+		replacement: Option<&'static str>,
+	},
+}
+impl fmt::Display for Warning {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::DeprecatedBuiltin { name, replacement } => {
+				write!(f, "std.{name} is deprecated")?;
+				if let Some(replacement) = replacement {
+					write!(f, ", use {replacement} instead")?;
+				}
+				Ok(())
+			}
+		}
+	}
 }
 
 #[derive(Trace)]
 pub struct EvaluationStateInternals {
 	/// Internal state
 	file_cache: RefCell<GcHashMap<SourcePath, FileData>>,
+	/// Files which are currently being evaluated, in import order, used to report import cycles
+	import_stack: RefCell<Vec<SourcePath>>,
+	/// Warnings reported since the last [`State::take_warnings`] call
+	warnings: RefCell<Vec<Warning>>,
+	/// See [`StateBuilder::strict_duplicate_fields`]
+	strict_duplicate_fields: bool,
+	/// See [`StateBuilder::max_import_depth`]
+	max_import_depth: usize,
 	/// Context initializer, which will be used for imports and everything
 	/// [`NoopContextInitializer`] is used by default, most likely you want to have `jrsonnet-stdlib`
 	context_initializer: TraceBox<dyn ContextInitializer>,
@@ -228,6 +295,10 @@ pub struct EvaluationStateInternals {
 pub struct State(Cc<EvaluationStateInternals>);
 
 impl State {
+	/// See [`ImportResolver::load_transformed_contents`]
+	fn load_file_contents(&self, path: &SourcePath) -> Result<Vec<u8>> {
+		self.import_resolver().load_transformed_contents(path)
+	}
 	/// Should only be called with path retrieved from [`resolve_path`], may panic otherwise
 	pub fn import_resolved_str(&self, path: SourcePath) -> Result<IStr> {
 		let mut file_cache = self.file_cache();
@@ -236,7 +307,7 @@ impl State {
 		let file = match file {
 			RawEntryMut::Occupied(ref mut d) => d.get_mut(),
 			RawEntryMut::Vacant(v) => {
-				let data = self.import_resolver().load_file_contents(&path)?;
+				let data = self.load_file_contents(&path)?;
 				v.insert(
 					path.clone(),
 					FileData::new_string(
@@ -260,24 +331,12 @@ impl State {
 		let file = match file {
 			RawEntryMut::Occupied(ref mut d) => d.get_mut(),
 			RawEntryMut::Vacant(v) => {
-				let data = self.import_resolver().load_file_contents(&path)?;
+				let data = self.load_file_contents(&path)?;
 				v.insert(path.clone(), FileData::new_bytes(data.as_slice().into()))
 					.1
 			}
 		};
-		if let Some(str) = &file.bytes {
-			return Ok(str.clone());
-		}
-		if file.bytes.is_none() {
-			file.bytes = Some(
-				file.string
-					.as_ref()
-					.expect("either string or bytes should be set")
-					.clone()
-					.cast_bytes(),
-			);
-		}
-		Ok(file.bytes.as_ref().expect("just set").clone())
+		Ok(file.get_bytes())
 	}
 	/// Should only be called with path retrieved from [`resolve_path`], may panic otherwise
 	pub fn import_resolved(&self, path: SourcePath) -> Result<Val> {
@@ -287,7 +346,7 @@ impl State {
 		let file = match file {
 			RawEntryMut::Occupied(ref mut d) => d.get_mut(),
 			RawEntryMut::Vacant(v) => {
-				let data = self.import_resolver().load_file_contents(&path)?;
+				let data = self.load_file_contents(&path)?;
 				v.insert(
 					path.clone(),
 					FileData::new_string(
@@ -322,12 +381,29 @@ impl State {
 		}
 		let parsed = file.parsed.as_ref().expect("just set").clone();
 		if file.evaluating {
-			bail!(InfiniteRecursionDetected)
+			let import_stack = self.0.import_stack.borrow();
+			let start = import_stack
+				.iter()
+				.position(|p| p == &path)
+				.expect("evaluating file is in import stack");
+			let mut cycle = import_stack[start..].to_vec();
+			cycle.push(path);
+			bail!(InfiniteRecursionDetected(cycle))
+		}
+		{
+			let import_stack = self.0.import_stack.borrow();
+			if import_stack.len() >= self.0.max_import_depth {
+				let mut chain = import_stack.clone();
+				chain.push(path);
+				bail!(ImportTooDeep(self.0.max_import_depth, chain))
+			}
 		}
 		file.evaluating = true;
 		// Dropping file cache guard here, as evaluation may use this map too
 		drop(file_cache);
+		self.0.import_stack.borrow_mut().push(path.clone());
 		let res = evaluate(self.create_default_context(file_name), &parsed);
+		self.0.import_stack.borrow_mut().pop();
 
 		let mut file_cache = self.file_cache();
 		let mut file = file_cache.raw_entry_mut().from_key(&path);
@@ -362,17 +438,15 @@ impl State {
 	}
 
 	/// Creates context with all passed global variables, calling custom modifier
+	///
+	/// Variables bound by `context_initializer` are layered over the default ones, and may shadow them.
 	pub fn create_default_context_with(
 		&self,
 		source: Source,
 		context_initializer: impl ContextInitializer,
 	) -> Context {
-		let default_initializer = self.context_initializer();
-		let mut builder = ContextBuilder::with_capacity(
-			self.clone(),
-			default_initializer.reserve_vars() + context_initializer.reserve_vars(),
-		);
-		default_initializer.populate(source.clone(), &mut builder);
+		let default_ctx = self.create_default_context(source.clone());
+		let mut builder = ContextBuilder::extend(default_ctx);
 		context_initializer.populate(source, &mut builder);
 
 		builder.build()
@@ -460,6 +534,139 @@ impl State {
 			&parsed,
 		)
 	}
+	/// Parses and evaluates the given snippet, and then returns value located at json pointer
+	/// (RFC 6901, e.g `/spec/containers/0/image`) in it.
+	///
+	/// Only values on the path are evaluated, errors in other fields/elements are not reported.
+	pub fn evaluate_path(
+		&self,
+		name: impl Into<IStr>,
+		code: impl Into<IStr>,
+		pointer: &str,
+	) -> Result<Val> {
+		let mut value = self.evaluate_snippet(name, code)?;
+		if pointer.is_empty() {
+			return Ok(value);
+		}
+		let Some(segments) = pointer.strip_prefix('/') else {
+			bail!(InvalidJsonPointer(pointer.to_owned()));
+		};
+		let mut prefix_len = 0;
+		for raw_segment in segments.split('/') {
+			let segment = raw_segment.replace("~1", "/").replace("~0", "~");
+			let next = match &value {
+				Val::Obj(obj) => obj.get(segment.as_str().into())?,
+				// Leading zeroes are not allowed by RFC
+				Val::Arr(arr) if segment == "0" || !segment.starts_with('0') => {
+					match segment.parse::<usize>() {
+						Ok(index) => arr.get(index)?,
+						Err(_) => None,
+					}
+				}
+				_ => None,
+			};
+			let Some(next) = next else {
+				bail!(JsonPointerNotFound {
+					prefix: pointer[..prefix_len].to_owned(),
+					segment,
+				});
+			};
+			value = next;
+			prefix_len += 1 + raw_segment.len();
+		}
+		Ok(value)
+	}
+}
+
+/// Default number of errors collected by [`State::check_snippet`]
+pub const DEFAULT_CHECK_ERROR_BUDGET: usize = 100;
+
+fn collect_errors(val: &Val, errors: &mut Vec<Error>, budget: usize) {
+	fn record(res: Result<()>, errors: &mut Vec<Error>) {
+		if let Err(e) = res {
+			errors.push(e);
+		}
+	}
+	if errors.len() >= budget {
+		return;
+	}
+	match val {
+		Val::Arr(arr) => {
+			for (i, item) in arr.iter().enumerate() {
+				if errors.len() >= budget {
+					return;
+				}
+				match item.with_description(|| format!("elem <{i}> evaluation")) {
+					Ok(item) => record(
+						in_description_frame(
+							|| format!("elem <{i}> checking"),
+							|| {
+								collect_errors(&item, errors, budget);
+								Ok(())
+							},
+						),
+						errors,
+					),
+					Err(e) => errors.push(e),
+				}
+			}
+		}
+		Val::Obj(obj) => {
+			record(obj.run_assertions(), errors);
+			for (key, value) in obj.iter(
+				#[cfg(feature = "exp-preserve-order")]
+				false,
+			) {
+				if errors.len() >= budget {
+					return;
+				}
+				match value.with_description(|| format!("field <{key}> evaluation")) {
+					Ok(value) => record(
+						in_description_frame(
+							|| format!("field <{key}> checking"),
+							|| {
+								collect_errors(&value, errors, budget);
+								Ok(())
+							},
+						),
+						errors,
+					),
+					Err(e) => errors.push(e),
+				}
+			}
+		}
+		_ => {}
+	}
+}
+
+/// Error-collecting evaluation, useful for editor diagnostics
+impl State {
+	/// Parses and evaluates the given snippet, then forces every visible field and array element,
+	/// collecting errors instead of stopping at the first one.
+	///
+	/// Functions are not called, and nothing is manifested. At most [`DEFAULT_CHECK_ERROR_BUDGET`]
+	/// errors are returned, see [`State::check_snippet_with_budget`] to configure that.
+	pub fn check_snippet(&self, name: impl Into<IStr>, code: impl Into<IStr>) -> Vec<Error> {
+		self.check_snippet_with_budget(name, code, DEFAULT_CHECK_ERROR_BUDGET)
+	}
+	/// Same as [`State::check_snippet`], but at most `budget` errors are collected
+	pub fn check_snippet_with_budget(
+		&self,
+		name: impl Into<IStr>,
+		code: impl Into<IStr>,
+		budget: usize,
+	) -> Vec<Error> {
+		let mut errors = Vec::new();
+		if budget == 0 {
+			return errors;
+		}
+		match self.evaluate_snippet(name, code) {
+			Ok(val) => collect_errors(&val, &mut errors, budget),
+			Err(e) => errors.push(e),
+		}
+		errors.truncate(budget);
+		errors
+	}
 }
 
 /// Settings utilities
@@ -483,6 +690,41 @@ impl State {
 	}
 }
 
+/// Warnings
+impl State {
+	/// Records a non-fatal warning, evaluation continues as usual
+	///
+	/// Warning is only recorded once until the next [`Self::take_warnings`] call, even if it is reported
+	/// repeatedly, i.e by the builtin called in a loop
+	pub fn warn(&self, warning: Warning) {
+		let mut warnings = self.0.warnings.borrow_mut();
+		if !warnings.contains(&warning) {
+			warnings.push(warning);
+		}
+	}
+	/// Returns warnings reported so far, clearing them
+	pub fn take_warnings(&self) -> Vec<Warning> {
+		std::mem::take(&mut *self.0.warnings.borrow_mut())
+	}
+	pub fn strict_duplicate_fields(&self) -> bool {
+		self.0.strict_duplicate_fields
+	}
+	/// Returns files successfully loaded by this state so far (through `import`, `importstr`,
+	/// `importbin`, or [`Self::import`]), with their contents, sorted by path
+	///
+	/// Files which failed to load are not cached, and thus not returned. Files only loaded as
+	/// bytes are skipped if they aren't valid UTF-8.
+	pub fn loaded_sources(&self) -> Vec<(SourcePath, IStr)> {
+		let mut out = self
+			.file_cache()
+			.iter_mut()
+			.filter_map(|(path, file)| Some((path.clone(), file.get_string()?)))
+			.collect::<Vec<_>>();
+		out.sort_by_cached_key(|(path, _)| path.to_string());
+		out
+	}
+}
+
 impl State {
 	pub fn builder() -> StateBuilder {
 		StateBuilder::default()
@@ -495,27 +737,78 @@ impl Default for State {
 	}
 }
 
-#[derive(Default)]
 pub struct StateBuilder {
 	import_resolver: Option<TraceBox<dyn ImportResolver>>,
-	context_initializer: Option<TraceBox<dyn ContextInitializer>>,
+	context_initializers: Vec<TraceBox<dyn ContextInitializer>>,
+	strict_duplicate_fields: bool,
+	max_import_depth: usize,
+}
+impl Default for StateBuilder {
+	fn default() -> Self {
+		Self {
+			import_resolver: None,
+			context_initializers: Vec::new(),
+			strict_duplicate_fields: false,
+			max_import_depth: 500,
+		}
+	}
 }
 impl StateBuilder {
 	pub fn import_resolver(&mut self, import_resolver: impl ImportResolver) -> &mut Self {
 		let _ = self.import_resolver.insert(tb!(import_resolver));
 		self
 	}
+	/// Sets context initializer, replacing all previously set/added ones
 	pub fn context_initializer(
 		&mut self,
 		context_initializer: impl ContextInitializer,
 	) -> &mut Self {
-		let _ = self.context_initializer.insert(tb!(context_initializer));
+		self.context_initializers.clear();
+		self.context_initializers.push(tb!(context_initializer));
+		self
+	}
+	/// Adds context initializer, which will populate context after all previously added ones.
+	///
+	/// Initializers shouldn't bind the same variables.
+	pub fn add_context_initializer(
+		&mut self,
+		context_initializer: impl ContextInitializer,
+	) -> &mut Self {
+		self.context_initializers.push(tb!(context_initializer));
+		self
+	}
+	/// Check object literals for fields with the same fixed name before evaluating them, and
+	/// report both definitions.
+	///
+	/// Duplicate fields are an error either way, but without this option only the last
+	/// definition is reported, and only once the object is constructed.
+	pub fn strict_duplicate_fields(&mut self, strict: bool) -> &mut Self {
+		self.strict_duplicate_fields = strict;
+		self
+	}
+	/// Maximum number of files being imported at the same time, i.e length of `a` imports `b`
+	/// imports `c`... chain, 500 by default.
+	///
+	/// Evaluation stack depth is limited separately, this limit protects against native stack
+	/// exhaustion when the stack depth limit is raised.
+	pub fn max_import_depth(&mut self, depth: usize) -> &mut Self {
+		self.max_import_depth = depth;
 		self
 	}
 	pub fn build(mut self) -> State {
+		let context_initializer: TraceBox<dyn ContextInitializer> =
+			match self.context_initializers.len() {
+				0 => tb!(()),
+				1 => self.context_initializers.pop().expect("len == 1"),
+				_ => tb!(self.context_initializers),
+			};
 		State(Cc::new(EvaluationStateInternals {
 			file_cache: RefCell::new(GcHashMap::new()),
-			context_initializer: self.context_initializer.take().unwrap_or_else(|| tb!(())),
+			import_stack: RefCell::new(Vec::new()),
+			warnings: RefCell::new(Vec::new()),
+			strict_duplicate_fields: self.strict_duplicate_fields,
+			max_import_depth: self.max_import_depth,
+			context_initializer,
 			import_resolver: self
 				.import_resolver
 				.take()
@@ -523,3 +816,16 @@ impl StateBuilder {
 		}))
 	}
 }
+
+#[cfg(all(test, feature = "exp-hex-literals"))]
+mod tests {
+	use crate::{State, Val};
+
+	#[test]
+	fn radix_literals() {
+		let s = State::default();
+		let eval = |code: &str| s.evaluate_snippet("<test>", code).expect("evaluated");
+		assert!(matches!(eval("0x10 == 16"), Val::Bool(true)));
+		assert!(matches!(eval("0xff + 0o7 + 0b1 == 263"), Val::Bool(true)));
+	}
+}
@@ -1,6 +1,13 @@
-use std::{borrow::Cow, fmt::Write, ptr};
+use std::{borrow::Cow, fmt::Write};
 
-use crate::{bail, in_description_frame, Result, ResultExt, Val};
+use jrsonnet_types::ValType;
+
+pub use self::cbor::CborFormat;
+use crate::{
+	bail, error::ErrorKind::ManifestTypeMismatch, in_description_frame, Result, ResultExt, Val,
+};
+
+mod cbor;
 
 pub trait ManifestFormat {
 	fn manifest_buf(&self, val: Val, buf: &mut String) -> Result<()>;
@@ -9,6 +16,33 @@ pub trait ManifestFormat {
 		self.manifest_buf(val, &mut out)?;
 		Ok(out)
 	}
+	/// Writes manifested value directly to `out`.
+	///
+	/// Default implementation manifests the whole value to intermediate string using
+	/// [`Self::manifest_buf`], formats which are able to produce output incrementally should override it.
+	fn manifest_to(&self, val: Val, out: &mut dyn Write) -> Result<()> {
+		out.write_str(&self.manifest(val)?)?;
+		Ok(())
+	}
+	/// Bytes-producing variant of [`Self::manifest_buf`], the only one supported by binary formats.
+	///
+	/// Default implementation writes utf-8 encoded output of [`Self::manifest_buf`]
+	fn manifest_bytes_buf(&self, val: Val, buf: &mut Vec<u8>) -> Result<()> {
+		buf.extend_from_slice(self.manifest(val)?.as_bytes());
+		Ok(())
+	}
+	fn manifest_bytes(&self, val: Val) -> Result<Vec<u8>> {
+		let mut out = Vec::new();
+		self.manifest_bytes_buf(val, &mut out)?;
+		Ok(out)
+	}
+	/// Does this format produce arbitrary bytes instead of text, in which case output should be
+	/// obtained with [`Self::manifest_bytes_buf`].
+	///
+	/// Default implementation returns `false`
+	fn is_binary(&self) -> bool {
+		false
+	}
 	/// When outputing to file, is it safe to append a trailing newline (I.e newline won't change
 	/// the meaning).
 	///
@@ -25,6 +59,18 @@ where
 		let inner = &**self;
 		inner.manifest_buf(val, buf)
 	}
+	fn manifest_to(&self, val: Val, out: &mut dyn Write) -> Result<()> {
+		let inner = &**self;
+		inner.manifest_to(val, out)
+	}
+	fn manifest_bytes_buf(&self, val: Val, buf: &mut Vec<u8>) -> Result<()> {
+		let inner = &**self;
+		inner.manifest_bytes_buf(val, buf)
+	}
+	fn is_binary(&self) -> bool {
+		let inner = &**self;
+		inner.is_binary()
+	}
 	fn file_trailing_newline(&self) -> bool {
 		let inner = &**self;
 		inner.file_trailing_newline()
@@ -38,6 +84,18 @@ where
 		let inner = &**self;
 		inner.manifest_buf(val, buf)
 	}
+	fn manifest_to(&self, val: Val, out: &mut dyn Write) -> Result<()> {
+		let inner = &**self;
+		inner.manifest_to(val, out)
+	}
+	fn manifest_bytes_buf(&self, val: Val, buf: &mut Vec<u8>) -> Result<()> {
+		let inner = &**self;
+		inner.manifest_bytes_buf(val, buf)
+	}
+	fn is_binary(&self) -> bool {
+		let inner = &**self;
+		inner.is_binary()
+	}
 	fn file_trailing_newline(&self) -> bool {
 		let inner = &**self;
 		inner.file_trailing_newline()
@@ -62,6 +120,9 @@ pub struct JsonFormat<'s> {
 	mtype: JsonFormatting,
 	newline: &'s str,
 	key_val_sep: &'s str,
+	/// If false - elements of arrays, which are values of object fields, are not indented
+	/// relative to the field key
+	indent_array_in_object: bool,
 	#[cfg(feature = "exp-preserve-order")]
 	preserve_order: bool,
 	#[cfg(feature = "exp-bigint")]
@@ -77,6 +138,7 @@ impl<'s> JsonFormat<'s> {
 			mtype: JsonFormatting::Minify,
 			newline: "\n",
 			key_val_sep: ":",
+			indent_array_in_object: true,
 			#[cfg(feature = "exp-preserve-order")]
 			preserve_order,
 			#[cfg(feature = "exp-bigint")]
@@ -92,6 +154,7 @@ impl<'s> JsonFormat<'s> {
 			mtype: JsonFormatting::ToString,
 			newline: "\n",
 			key_val_sep: ": ",
+			indent_array_in_object: true,
 			#[cfg(feature = "exp-preserve-order")]
 			preserve_order: false,
 			#[cfg(feature = "exp-bigint")]
@@ -110,6 +173,7 @@ impl<'s> JsonFormat<'s> {
 			mtype: JsonFormatting::Std,
 			newline,
 			key_val_sep,
+			indent_array_in_object: true,
 			#[cfg(feature = "exp-preserve-order")]
 			preserve_order,
 			#[cfg(feature = "exp-bigint")]
@@ -117,6 +181,14 @@ impl<'s> JsonFormat<'s> {
 			debug_truncate_strings: None,
 		}
 	}
+	/// Should arrays, which are values of object fields, be indented relative to the field key
+	///
+	/// Enabled by default, which matches go-jsonnet output
+	#[must_use]
+	pub fn with_indent_array_in_object(mut self, indent_array_in_object: bool) -> Self {
+		self.indent_array_in_object = indent_array_in_object;
+		self
+	}
 	// Same format as CLI manifestification
 	pub fn cli(
 		padding: usize,
@@ -128,11 +200,23 @@ impl<'s> JsonFormat<'s> {
 				preserve_order,
 			);
 		}
+		Self::cli_indent(
+			" ".repeat(padding),
+			#[cfg(feature = "exp-preserve-order")]
+			preserve_order,
+		)
+	}
+	/// Same as [`Self::cli`], but indents with arbitrary string, i.e `"\t"`
+	pub fn cli_indent(
+		indent: impl Into<Cow<'s, str>>,
+		#[cfg(feature = "exp-preserve-order")] preserve_order: bool,
+	) -> Self {
 		Self {
-			padding: Cow::Owned(" ".repeat(padding)),
+			padding: indent.into(),
 			mtype: JsonFormatting::Manifest,
 			newline: "\n",
 			key_val_sep: ": ",
+			indent_array_in_object: true,
 			#[cfg(feature = "exp-preserve-order")]
 			preserve_order,
 			#[cfg(feature = "exp-bigint")]
@@ -147,6 +231,7 @@ impl<'s> JsonFormat<'s> {
 			mtype: JsonFormatting::Manifest,
 			newline: "\n",
 			key_val_sep: ": ",
+			indent_array_in_object: true,
 			#[cfg(feature = "exp-preserve-order")]
 			preserve_order: true,
 			#[cfg(feature = "exp-bigint")]
@@ -162,6 +247,7 @@ impl Default for JsonFormat<'static> {
 			mtype: JsonFormatting::Manifest,
 			newline: "\n",
 			key_val_sep: ": ",
+			indent_array_in_object: true,
 			#[cfg(feature = "exp-preserve-order")]
 			preserve_order: false,
 			#[cfg(feature = "exp-bigint")]
@@ -173,16 +259,17 @@ impl Default for JsonFormat<'static> {
 
 pub fn manifest_json_ex(val: &Val, options: &JsonFormat<'_>) -> Result<String> {
 	let mut out = String::new();
-	manifest_json_ex_buf(val, &mut out, &mut String::new(), options)?;
+	manifest_json_ex_buf(val, &mut out, &mut String::new(), options, false)?;
 	Ok(out)
 }
 
 #[allow(clippy::too_many_lines)]
-fn manifest_json_ex_buf(
+fn manifest_json_ex_buf<W: Write + ?Sized>(
 	val: &Val,
-	buf: &mut String,
+	buf: &mut W,
 	cur_padding: &mut String,
 	options: &JsonFormat<'_>,
+	in_object: bool,
 ) -> Result<()> {
 	use JsonFormatting::*;
 
@@ -190,40 +277,44 @@ fn manifest_json_ex_buf(
 	match val {
 		Val::Bool(v) => {
 			if *v {
-				buf.push_str("true");
+				buf.write_str("true")?;
 			} else {
-				buf.push_str("false");
+				buf.write_str("false")?;
 			}
 		}
-		Val::Null => buf.push_str("null"),
+		Val::Null => buf.write_str("null")?,
 		Val::Str(s) => {
 			let flat = s.clone().into_flat();
 			if let Some(truncate) = options.debug_truncate_strings {
 				if flat.len() > truncate {
 					let (start, end) = flat.split_at(truncate / 2);
 					let (_, end) = end.split_at(end.len() - truncate / 2);
-					escape_string_json_buf(&format!("{start}..{end}"), buf);
+					escape_string_json_to(&format!("{start}..{end}"), buf)?;
 				} else {
-					escape_string_json_buf(&flat, buf);
+					escape_string_json_to(&flat, buf)?;
 				}
 			} else {
-				escape_string_json_buf(&flat, buf);
+				escape_string_json_to(&flat, buf)?;
 			}
 		}
-		Val::Num(n) => write!(buf, "{n}").unwrap(),
+		// NumValue is always finite, NaN/Infinity are rejected at the moment they are produced, so
+		// there is nothing to special-case here
+		Val::Num(n) => write!(buf, "{n}")?,
 		#[cfg(feature = "exp-bigint")]
 		Val::BigInt(n) => {
 			if options.preserve_bigints {
-				write!(buf, "{n}").unwrap();
+				write!(buf, "{n}")?;
 			} else {
-				write!(buf, "{:?}", n.to_string()).unwrap();
+				write!(buf, "{:?}", n.to_string())?;
 			}
 		}
 		Val::Arr(items) => {
-			buf.push('[');
+			buf.write_char('[')?;
 
 			let old_len = cur_padding.len();
-			cur_padding.push_str(&options.padding);
+			if !in_object || options.indent_array_in_object {
+				cur_padding.push_str(&options.padding);
+			}
 
 			let mut had_items = false;
 			for (i, item) in items.iter().enumerate() {
@@ -231,20 +322,20 @@ fn manifest_json_ex_buf(
 				let item = item.with_description(|| format!("elem <{i}> evaluation"))?;
 
 				if i != 0 {
-					buf.push(',');
+					buf.write_char(',')?;
 				}
 				match mtype {
 					Manifest | Std => {
-						buf.push_str(options.newline);
-						buf.push_str(cur_padding);
+						buf.write_str(options.newline)?;
+						buf.write_str(cur_padding)?;
 					}
-					ToString if i != 0 => buf.push(' '),
+					ToString if i != 0 => buf.write_char(' ')?,
 					Minify | ToString => {}
 				};
 
 				in_description_frame(
 					|| format!("elem <{i}> manifestification"),
-					|| manifest_json_ex_buf(&item, buf, cur_padding, options),
+					|| manifest_json_ex_buf(&item, buf, cur_padding, options, false),
 				)?;
 			}
 
@@ -253,28 +344,28 @@ fn manifest_json_ex_buf(
 			match mtype {
 				Manifest | ToString if !had_items => {
 					// Empty array as "[ ]"
-					buf.push(' ');
+					buf.write_char(' ')?;
 				}
 				Manifest => {
-					buf.push_str(options.newline);
-					buf.push_str(cur_padding);
+					buf.write_str(options.newline)?;
+					buf.write_str(cur_padding)?;
 				}
 				Std => {
 					if !had_items {
 						// Stdlib formats empty array as "[\n\n]"
-						buf.push_str(options.newline);
+						buf.write_str(options.newline)?;
 					}
-					buf.push_str(options.newline);
-					buf.push_str(cur_padding);
+					buf.write_str(options.newline)?;
+					buf.write_str(cur_padding)?;
 				}
 				Minify | ToString => {}
 			}
 
-			buf.push(']');
+			buf.write_char(']')?;
 		}
 		Val::Obj(obj) => {
 			obj.run_assertions()?;
-			buf.push('{');
+			buf.write_char('{')?;
 
 			let old_len = cur_padding.len();
 			cur_padding.push_str(&options.padding);
@@ -291,22 +382,22 @@ fn manifest_json_ex_buf(
 				let value = value.with_description(|| format!("field <{key}> evaluation"))?;
 
 				if i != 0 {
-					buf.push(',');
+					buf.write_char(',')?;
 				}
 				match mtype {
 					Manifest | Std => {
-						buf.push_str(options.newline);
-						buf.push_str(cur_padding);
+						buf.write_str(options.newline)?;
+						buf.write_str(cur_padding)?;
 					}
-					ToString if i != 0 => buf.push(' '),
+					ToString if i != 0 => buf.write_char(' ')?,
 					Minify | ToString => {}
 				}
 
-				escape_string_json_buf(&key, buf);
-				buf.push_str(options.key_val_sep);
+				escape_string_json_to(&key, buf)?;
+				buf.write_str(options.key_val_sep)?;
 				in_description_frame(
 					|| format!("field <{key}> manifestification"),
-					|| manifest_json_ex_buf(&value, buf, cur_padding, options),
+					|| manifest_json_ex_buf(&value, buf, cur_padding, options, true),
 				)?;
 			}
 
@@ -315,24 +406,24 @@ fn manifest_json_ex_buf(
 			match mtype {
 				Manifest | ToString if !had_fields => {
 					// Empty object as "{ }"
-					buf.push(' ');
+					buf.write_char(' ')?;
 				}
 				Manifest => {
-					buf.push_str(options.newline);
-					buf.push_str(cur_padding);
+					buf.write_str(options.newline)?;
+					buf.write_str(cur_padding)?;
 				}
 				Std => {
 					if !had_fields {
 						// Stdlib formats empty object as "{\n\n}"
-						buf.push_str(options.newline);
+						buf.write_str(options.newline)?;
 					}
-					buf.push_str(options.newline);
-					buf.push_str(cur_padding);
+					buf.write_str(options.newline)?;
+					buf.write_str(cur_padding)?;
 				}
 				Minify | ToString => {}
 			}
 
-			buf.push('}');
+			buf.write_char('}')?;
 		}
 		Val::Func(_) => bail!("tried to manifest function"),
 	};
@@ -341,7 +432,10 @@ fn manifest_json_ex_buf(
 
 impl ManifestFormat for JsonFormat<'_> {
 	fn manifest_buf(&self, val: Val, buf: &mut String) -> Result<()> {
-		manifest_json_ex_buf(&val, buf, &mut String::new(), self)
+		manifest_json_ex_buf(&val, buf, &mut String::new(), self, false)
+	}
+	fn manifest_to(&self, val: Val, out: &mut dyn Write) -> Result<()> {
+		manifest_json_ex_buf(&val, out, &mut String::new(), self, false)
 	}
 }
 
@@ -357,6 +451,14 @@ impl ManifestFormat for ToStringFormat {
 		}
 		JSON_TO_STRING.manifest_buf(val, out)
 	}
+	fn manifest_to(&self, val: Val, out: &mut dyn Write) -> Result<()> {
+		const JSON_TO_STRING: JsonFormat = JsonFormat::std_to_string_helper();
+		if let Some(str) = val.as_str() {
+			out.write_str(&str)?;
+			return Ok(());
+		}
+		JSON_TO_STRING.manifest_to(val, out)
+	}
 	fn file_trailing_newline(&self) -> bool {
 		false
 	}
@@ -373,6 +475,16 @@ impl ManifestFormat for StringFormat {
 		write!(out, "{s}").unwrap();
 		Ok(())
 	}
+	fn manifest_to(&self, val: Val, out: &mut dyn Write) -> Result<()> {
+		let Val::Str(s) = val else {
+			bail!(
+				"output should be string for string manifest format, got {}",
+				val.value_type()
+			)
+		};
+		write!(out, "{s}")?;
+		Ok(())
+	}
 	fn file_trailing_newline(&self) -> bool {
 		false
 	}
@@ -400,34 +512,49 @@ impl<I> YamlStreamFormat<I> {
 		}
 	}
 }
-impl<I: ManifestFormat> ManifestFormat for YamlStreamFormat<I> {
-	fn manifest_buf(&self, val: Val, out: &mut String) -> Result<()> {
+impl<I: ManifestFormat> YamlStreamFormat<I> {
+	fn manifest_stream<W: Write + ?Sized>(
+		&self,
+		val: Val,
+		out: &mut W,
+		manifest_item: impl Fn(&I, Val, &mut W) -> Result<()>,
+	) -> Result<()> {
 		let Val::Arr(arr) = val else {
-			bail!(
-				"output should be array for yaml stream format, got {}",
-				val.value_type()
-			)
+			bail!(ManifestTypeMismatch {
+				expected: ValType::Arr,
+				got: val.value_type(),
+			})
 		};
 		if !arr.is_empty() {
 			for (i, v) in arr.iter().enumerate() {
 				let v = v.with_description(|| format!("elem <{i}> evaluation"))?;
-				out.push_str("---\n");
+				out.write_str("---\n")?;
 				in_description_frame(
 					|| format!("elem <{i}> manifestification"),
-					|| self.inner.manifest_buf(v, out),
+					|| manifest_item(&self.inner, v, out),
 				)?;
-				out.push('\n');
+				out.write_char('\n')?;
 			}
 		}
 		if self.c_document_end {
-			out.push_str("...");
+			out.write_str("...")?;
 		}
 		if self.end_newline {
-			out.push('\n');
+			out.write_char('\n')?;
 		}
 		Ok(())
 	}
 }
+impl<I: ManifestFormat> ManifestFormat for YamlStreamFormat<I> {
+	fn manifest_buf(&self, val: Val, out: &mut String) -> Result<()> {
+		self.manifest_stream(val, out, I::manifest_buf)
+	}
+	// Method path is not general enough over `dyn Write` lifetime
+	#[allow(clippy::redundant_closure_for_method_calls)]
+	fn manifest_to(&self, val: Val, out: &mut dyn Write) -> Result<()> {
+		self.manifest_stream(val, out, |inner, v, out| inner.manifest_to(v, out))
+	}
+}
 
 pub fn escape_string_json(s: &str) -> String {
 	let mut buf = String::new();
@@ -469,15 +596,11 @@ static ESCAPE: [u8; 256] = [
 	__, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // F
 ];
 
-pub fn escape_string_json_buf(value: &str, buf: &mut String) {
-	// Safety: we only write correct utf-8 in this function
-	let buf: &mut Vec<u8> = unsafe { &mut *ptr::from_mut(buf).cast::<Vec<u8>>() };
+/// Same as [`escape_string_json_buf`], but for arbitrary writer
+pub fn escape_string_json_to<W: Write + ?Sized>(value: &str, out: &mut W) -> std::fmt::Result {
 	let bytes = value.as_bytes();
 
-	// Perfect for ascii strings, removes any reallocations
-	buf.reserve(value.len() + 2);
-
-	buf.push(b'"');
+	out.write_char('"')?;
 
 	let mut start = 0;
 
@@ -487,36 +610,122 @@ pub fn escape_string_json_buf(value: &str, buf: &mut String) {
 			continue;
 		}
 
+		// Only ascii bytes are escaped, so this is always a char boundary
 		if start < i {
-			buf.extend_from_slice(&bytes[start..i]);
+			out.write_str(&value[start..i])?;
 		}
 		start = i + 1;
 
 		match escape {
 			self::BB | self::TT | self::NN | self::FF | self::RR | self::QU | self::BS => {
-				buf.extend_from_slice(&[b'\\', escape]);
-			}
-			self::UU => {
-				static HEX_DIGITS: [u8; 16] = *b"0123456789abcdef";
-				let bytes = &[
-					b'\\',
-					b'u',
-					b'0',
-					b'0',
-					HEX_DIGITS[(byte >> 4) as usize],
-					HEX_DIGITS[(byte & 0xF) as usize],
-				];
-				buf.extend_from_slice(bytes);
+				out.write_char('\\')?;
+				out.write_char(escape as char)?;
 			}
+			self::UU => write!(out, "\\u{byte:04x}")?,
 			_ => unreachable!(),
 		}
 	}
 
-	if start == bytes.len() {
-		buf.push(b'"');
-		return;
+	out.write_str(&value[start..])?;
+	out.write_char('"')
+}
+
+pub fn escape_string_json_buf(value: &str, buf: &mut String) {
+	// Perfect for ascii strings, removes any reallocations
+	buf.reserve(value.len() + 2);
+	escape_string_json_to(value, buf).expect("writing to string never fails");
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fmt::{self, Write};
+
+	use super::{JsonFormat, ManifestFormat, ToStringFormat, YamlStreamFormat};
+	use crate::{error::ErrorKind, State, Val};
+
+	/// Records size of the largest single write, and fails after `limit` bytes
+	struct Recorder {
+		out: String,
+		largest_write: usize,
+		limit: usize,
+	}
+	impl Write for Recorder {
+		fn write_str(&mut self, s: &str) -> fmt::Result {
+			if self.out.len() + s.len() > self.limit {
+				return Err(fmt::Error);
+			}
+			self.largest_write = self.largest_write.max(s.len());
+			self.out.push_str(s);
+			Ok(())
+		}
+	}
+
+	fn eval(code: &str) -> Val {
+		State::default()
+			.evaluate_snippet("<test>", code)
+			.expect("evaluated")
+	}
+
+	#[test]
+	fn manifest_to_matches_manifest() {
+		let val = eval("[{a: [1, 'b\\n'], c: {}}, 'x', null]");
+		let formats: [Box<dyn ManifestFormat>; 4] = [
+			Box::new(JsonFormat::default()),
+			Box::new(JsonFormat::minify(
+				#[cfg(feature = "exp-preserve-order")]
+				false,
+			)),
+			Box::new(ToStringFormat),
+			Box::new(YamlStreamFormat::cli(JsonFormat::default())),
+		];
+		for format in formats {
+			let mut out = String::new();
+			val.manifest_to(&mut out, &format).expect("manifested");
+			assert_eq!(out, val.manifest(&format).expect("manifested"));
+		}
+	}
+
+	#[test]
+	fn json_is_written_incrementally() {
+		let val = eval("[{key: 'value', index: i} for i in [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]]");
+		let mut out = Recorder {
+			out: String::new(),
+			largest_write: 0,
+			limit: usize::MAX,
+		};
+		val.manifest_to(&mut out, JsonFormat::default())
+			.expect("manifested");
+		assert!(out.out.len() > 400);
+		assert!(out.largest_write < 32);
 	}
 
-	buf.extend_from_slice(&bytes[start..]);
-	buf.push(b'"');
+	#[test]
+	fn json_cli_indent() {
+		let val = eval("{a: [1, {}], b: {c: true}}");
+		let out = val
+			.manifest(JsonFormat::cli_indent(
+				"\t",
+				#[cfg(feature = "exp-preserve-order")]
+				false,
+			))
+			.expect("manifested");
+		assert_eq!(
+			out,
+			"{\n\t\"a\": [\n\t\t1,\n\t\t{ }\n\t],\n\t\"b\": {\n\t\t\"c\": true\n\t}\n}"
+		);
+	}
+
+	#[test]
+	fn write_errors_are_reported() {
+		let val = eval("[i * 1000 for i in [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]]");
+		let mut out = Recorder {
+			out: String::new(),
+			largest_write: 0,
+			limit: 50,
+		};
+		let err = val
+			.manifest_to(&mut out, JsonFormat::default())
+			.expect_err("output is limited");
+		assert!(matches!(err.error(), ErrorKind::ManifestOutputWriteFailed));
+	}
 }
@@ -0,0 +1,248 @@
+use super::ManifestFormat;
+use crate::{bail, in_description_frame, Result, ResultExt, Val};
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+#[cfg(feature = "exp-bigint")]
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+#[cfg(feature = "exp-bigint")]
+const MAJOR_TAG: u8 = 6;
+
+const FALSE: u8 = 0xf4;
+const TRUE: u8 = 0xf5;
+const NULL: u8 = 0xf6;
+const FLOAT16: u8 = 0xf9;
+const FLOAT32: u8 = 0xfa;
+const FLOAT64: u8 = 0xfb;
+
+/// Binary format, producing deterministically encoded CBOR ([RFC 8949, section 4.2](https://www.rfc-editor.org/rfc/rfc8949#section-4.2))
+///
+/// - Integral numbers, which fit into `i64`, are encoded as CBOR integers, other numbers are
+///   encoded as shortest float (half, single or double precision), which represents them exactly.
+///   `-0` is encoded as float, to preserve its sign.
+/// - Jsonnet numbers are always finite, so NaN and infinities are never produced.
+/// - Object fields are sorted by length first, and then lexicographically, as required for
+///   deterministic encoding, regardless of `exp-preserve-order`. Hidden fields are omitted.
+/// - Big integers (`exp-bigint`) are encoded as integers if they fit, and as bignums (tags 2 and 3)
+///   otherwise.
+pub struct CborFormat;
+
+impl ManifestFormat for CborFormat {
+	fn manifest_buf(&self, _val: Val, _buf: &mut String) -> Result<()> {
+		bail!("cbor is a binary format, and can't be manifested to string")
+	}
+	fn manifest_bytes_buf(&self, val: Val, buf: &mut Vec<u8>) -> Result<()> {
+		manifest_cbor_buf(&val, buf)
+	}
+	fn is_binary(&self) -> bool {
+		true
+	}
+	fn file_trailing_newline(&self) -> bool {
+		false
+	}
+}
+
+fn write_head(buf: &mut Vec<u8>, major: u8, value: u64) {
+	let major = major << 5;
+	if value < 24 {
+		buf.push(major | value as u8);
+	} else if let Ok(value) = u8::try_from(value) {
+		buf.extend_from_slice(&[major | 24, value]);
+	} else if let Ok(value) = u16::try_from(value) {
+		buf.push(major | 25);
+		buf.extend_from_slice(&value.to_be_bytes());
+	} else if let Ok(value) = u32::try_from(value) {
+		buf.push(major | 26);
+		buf.extend_from_slice(&value.to_be_bytes());
+	} else {
+		buf.push(major | 27);
+		buf.extend_from_slice(&value.to_be_bytes());
+	}
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+	write_head(buf, MAJOR_TEXT, s.len() as u64);
+	buf.extend_from_slice(s.as_bytes());
+}
+
+fn write_int(buf: &mut Vec<u8>, v: i64) {
+	if let Ok(v) = u64::try_from(v) {
+		write_head(buf, MAJOR_UNSIGNED, v);
+	} else {
+		// -1 - v, can't overflow for negative v
+		write_head(buf, MAJOR_NEGATIVE, !v as u64);
+	}
+}
+
+/// Half precision float bits, if conversion is lossless
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn f16_exact(v: f32) -> Option<u16> {
+	let bits = v.to_bits();
+	let sign = ((bits >> 16) & 0x8000) as u16;
+	let exp = ((bits >> 23) & 0xff) as i32;
+	let mantissa = bits & 0x7f_ffff;
+	if exp == 0 {
+		// Zero, f32 subnormals are too small for f16
+		return (mantissa == 0).then_some(sign);
+	}
+	let exp = exp - 127;
+	if (-14..=15).contains(&exp) {
+		if mantissa & 0x1fff != 0 {
+			return None;
+		}
+		return Some(sign | (((exp + 15) as u16) << 10) | (mantissa >> 13) as u16);
+	}
+	if (-24..-14).contains(&exp) {
+		// Subnormal f16: mantissa * 2^-24
+		let full = mantissa | 0x80_0000;
+		let shift = -(exp + 1);
+		if full & ((1 << shift) - 1) != 0 {
+			return None;
+		}
+		return Some(sign | (full >> shift) as u16);
+	}
+	None
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::float_cmp)]
+fn write_float(buf: &mut Vec<u8>, v: f64) {
+	let single = v as f32;
+	if f64::from(single) != v {
+		buf.push(FLOAT64);
+		buf.extend_from_slice(&v.to_be_bytes());
+	} else if let Some(half) = f16_exact(single) {
+		buf.push(FLOAT16);
+		buf.extend_from_slice(&half.to_be_bytes());
+	} else {
+		buf.push(FLOAT32);
+		buf.extend_from_slice(&single.to_be_bytes());
+	}
+}
+
+#[cfg(feature = "exp-bigint")]
+fn write_bigint(buf: &mut Vec<u8>, v: &num_bigint::BigInt) {
+	use num_bigint::{BigInt, Sign};
+
+	if let Ok(v) = i64::try_from(v) {
+		write_int(buf, v);
+		return;
+	}
+	let (major, tag, magnitude) = if v.sign() == Sign::Minus {
+		let magnitude: BigInt = -v - 1;
+		(MAJOR_NEGATIVE, 3, magnitude)
+	} else {
+		(MAJOR_UNSIGNED, 2, v.clone())
+	};
+	if let Ok(magnitude) = u64::try_from(&magnitude) {
+		write_head(buf, major, magnitude);
+		return;
+	}
+	let bytes = magnitude.magnitude().to_bytes_be();
+	write_head(buf, MAJOR_TAG, tag);
+	write_head(buf, MAJOR_BYTES, bytes.len() as u64);
+	buf.extend_from_slice(&bytes);
+}
+
+fn manifest_cbor_buf(val: &Val, buf: &mut Vec<u8>) -> Result<()> {
+	match val {
+		Val::Bool(false) => buf.push(FALSE),
+		Val::Bool(true) => buf.push(TRUE),
+		Val::Null => buf.push(NULL),
+		Val::Str(s) => write_str(buf, &s.clone().into_flat()),
+		Val::Num(n) => match n.as_i64_exact() {
+			Some(i) if i != 0 || n.is_sign_positive() => write_int(buf, i),
+			_ => write_float(buf, n.get()),
+		},
+		#[cfg(feature = "exp-bigint")]
+		Val::BigInt(n) => write_bigint(buf, n),
+		Val::Arr(items) => {
+			write_head(buf, MAJOR_ARRAY, items.len() as u64);
+			for (i, item) in items.iter().enumerate() {
+				let item = item.with_description(|| format!("elem <{i}> evaluation"))?;
+				in_description_frame(
+					|| format!("elem <{i}> manifestification"),
+					|| manifest_cbor_buf(&item, buf),
+				)?;
+			}
+		}
+		Val::Obj(obj) => {
+			obj.run_assertions()?;
+			let mut fields = obj.fields(
+				#[cfg(feature = "exp-preserve-order")]
+				false,
+			);
+			// Encoded keys are compared bytewise, which for text strings means shorter keys go first
+			fields.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+			write_head(buf, MAJOR_MAP, fields.len() as u64);
+			for key in fields {
+				let value = obj
+					.get(key.clone())
+					.with_description(|| format!("field <{key}> evaluation"))?
+					.expect("field exists");
+				write_str(buf, &key);
+				in_description_frame(
+					|| format!("field <{key}> manifestification"),
+					|| manifest_cbor_buf(&value, buf),
+				)?;
+			}
+		}
+		Val::Func(_) => bail!("tried to manifest function"),
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::CborFormat;
+	use crate::{manifest::ManifestFormat, State};
+
+	fn cbor(code: &str) -> String {
+		use std::fmt::Write;
+
+		let val = State::default()
+			.evaluate_snippet("<test>", code)
+			.expect("evaluated");
+		let bytes = CborFormat.manifest_bytes(val).expect("manifested");
+		let mut out = String::new();
+		for b in bytes {
+			write!(out, "{b:02x}").unwrap();
+		}
+		out
+	}
+
+	// Vectors are taken from RFC 8949 appendix A
+	#[test]
+	fn scalars() {
+		assert_eq!(cbor("0"), "00");
+		assert_eq!(cbor("23"), "17");
+		assert_eq!(cbor("24"), "1818");
+		assert_eq!(cbor("1000000"), "1a000f4240");
+		assert_eq!(cbor("1000000000000"), "1b000000e8d4a51000");
+		assert_eq!(cbor("-1"), "20");
+		assert_eq!(cbor("-1000"), "3903e7");
+		assert_eq!(cbor("-0"), "f98000");
+		assert_eq!(cbor("1.5"), "f93e00");
+		assert_eq!(cbor("65504.5"), "fa477fe080");
+		assert_eq!(cbor("0.00006103515625"), "f90400");
+		assert_eq!(cbor("5.960464477539063e-8"), "f90001");
+		assert_eq!(cbor("100000.5"), "fa47c35040");
+		assert_eq!(cbor("1.1"), "fb3ff199999999999a");
+		assert_eq!(cbor("1e300"), "fb7e37e43c8800759c");
+		assert_eq!(cbor("false"), "f4");
+		assert_eq!(cbor("true"), "f5");
+		assert_eq!(cbor("null"), "f6");
+		assert_eq!(cbor("'a'"), "6161");
+		assert_eq!(cbor("'\\u00fc'"), "62c3bc");
+	}
+
+	#[test]
+	fn containers() {
+		assert_eq!(cbor("[1, [2, 3]]"), "8201820203");
+		assert_eq!(cbor("{a: 1, b: [2, 3]}"), "a26161016162820203");
+		// Shorter keys go first, hidden fields are skipped
+		assert_eq!(cbor("{aa: 1, b: 2, c:: 3}"), "a261620262616101");
+	}
+}
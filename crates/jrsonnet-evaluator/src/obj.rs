@@ -8,6 +8,7 @@ use std::{
 
 use jrsonnet_gcmodule::{Cc, Trace, Weak};
 use jrsonnet_interner::IStr;
+pub use jrsonnet_macros::ObjectLayer;
 use jrsonnet_parser::{Span, Visibility};
 use rustc_hash::FxHashMap;
 
@@ -130,7 +131,7 @@ impl Debug for ObjFieldFlags {
 }
 
 #[allow(clippy::module_name_repetitions)]
-#[derive(Debug, Trace)]
+#[derive(Debug, Clone, Trace)]
 pub struct ObjMember {
 	#[trace(skip)]
 	flags: ObjFieldFlags,
@@ -139,6 +140,91 @@ pub struct ObjMember {
 	pub location: Option<Span>,
 }
 
+/// Objects with up to this amount of own fields store them in a plain vector
+const SMALL_OBJECT_FIELDS: usize = 8;
+
+/// Own fields of a single object layer
+///
+/// Most objects only have a few fields, and for them linear search over a vector is both faster
+/// and takes less memory than hash map, which also needs to keep spare capacity.
+#[derive(Debug, Trace)]
+enum ObjFields {
+	Small(Vec<(IStr, ObjMember)>),
+	Large(GcHashMap<IStr, ObjMember>),
+}
+impl ObjFields {
+	fn with_capacity(capacity: usize) -> Self {
+		if capacity > SMALL_OBJECT_FIELDS {
+			Self::Large(GcHashMap::with_capacity(capacity))
+		} else {
+			Self::Small(Vec::with_capacity(capacity))
+		}
+	}
+	fn len(&self) -> usize {
+		match self {
+			Self::Small(v) => v.len(),
+			Self::Large(m) => m.len(),
+		}
+	}
+	fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+	fn get(&self, name: &IStr) -> Option<&ObjMember> {
+		match self {
+			Self::Small(v) => v.iter().find(|(k, _)| k == name).map(|(_, m)| m),
+			Self::Large(m) => m.get(name),
+		}
+	}
+	fn contains_key(&self, name: &IStr) -> bool {
+		self.get(name).is_some()
+	}
+	fn iter(&self) -> impl Iterator<Item = (&IStr, &ObjMember)> {
+		let (small, large) = match self {
+			Self::Small(v) => (Some(v.iter().map(|(k, m)| (k, m))), None),
+			Self::Large(m) => (None, Some(m.iter())),
+		};
+		small
+			.into_iter()
+			.flatten()
+			.chain(large.into_iter().flatten())
+	}
+	/// Inserts field, replacing and returning the old one with the same name, if any
+	fn insert(&mut self, name: IStr, member: ObjMember) -> Option<ObjMember> {
+		match self {
+			Self::Small(v) => {
+				if let Some((_, old)) = v.iter_mut().find(|(k, _)| *k == name) {
+					return Some(std::mem::replace(old, member));
+				}
+				if v.len() < SMALL_OBJECT_FIELDS {
+					v.push((name, member));
+					return None;
+				}
+				let mut map = GcHashMap::with_capacity(v.len() + 1);
+				map.extend(v.drain(..));
+				map.insert(name, member);
+				*self = Self::Large(map);
+				None
+			}
+			Self::Large(m) => m.insert(name, member),
+		}
+	}
+	/// Drops spare capacity, as fields are not added after object is built
+	fn shrink_to_fit(&mut self) {
+		if let Self::Small(v) = self {
+			v.shrink_to_fit();
+		}
+	}
+}
+impl From<&GcHashMap<IStr, ObjMember>> for ObjFields {
+	fn from(map: &GcHashMap<IStr, ObjMember>) -> Self {
+		let mut out = Self::with_capacity(map.len());
+		for (name, member) in map.iter() {
+			out.insert(name.clone(), member.clone());
+		}
+		out
+	}
+}
+
 pub trait ObjectAssertion: Trace {
 	fn run(&self, super_obj: Option<ObjValue>, this: Option<ObjValue>) -> Result<()>;
 }
@@ -161,7 +247,7 @@ pub struct OopObject {
 	// this: Option<ObjValue>,
 	assertions: Cc<Vec<TraceBox<dyn ObjectAssertion>>>,
 	assertions_ran: RefCell<GcHashSet<ObjValue>>,
-	this_entries: Cc<GcHashMap<IStr, ObjMember>>,
+	this_entries: Cc<ObjFields>,
 	value_cache: RefCell<GcHashMap<(IStr, Option<WeakObjValue>), CacheValue>>,
 }
 impl Debug for OopObject {
@@ -343,6 +429,15 @@ impl ObjValue {
 	pub fn builder_with_capacity(capacity: usize) -> ObjValueBuilder {
 		ObjValueBuilder::with_capacity(capacity)
 	}
+	/// Same as [`ObjValue::from_iter`], but field values are lazy
+	pub fn from_thunk_iter(iter: impl IntoIterator<Item = (IStr, Thunk<Val>)>) -> Self {
+		let iter = iter.into_iter();
+		let mut out = ObjValueBuilder::with_capacity(iter.size_hint().0);
+		for (name, value) in iter {
+			out.field(name).thunk(value);
+		}
+		out.build()
+	}
 	pub(crate) fn extend_with_raw_member(self, key: IStr, value: ObjMember) -> Self {
 		let mut out = ObjValueBuilder::with_capacity(1);
 		out.with_super(self);
@@ -483,6 +578,10 @@ impl ObjValue {
 		);
 		out
 	}
+	/// Returns field names, either in the order of their definition (`preserve_order`), or sorted
+	/// by content.
+	///
+	/// Resulting order never depends on [`IStr`] addresses, so it is stable between runs.
 	pub fn fields_ex(
 		&self,
 		include_hidden: bool,
@@ -525,6 +624,38 @@ impl ObjValue {
 		fields.sort_unstable();
 		fields
 	}
+	/// Same as [`Self::fields_ex`], but returns only the first `limit` fields.
+	///
+	/// Every field still needs to be visited, as both visibility and position of a field depend on
+	/// all the object layers, but only the returned fields are sorted.
+	pub fn fields_ex_limited(
+		&self,
+		include_hidden: bool,
+		limit: usize,
+		#[cfg(feature = "exp-preserve-order")] preserve_order: bool,
+	) -> Vec<IStr> {
+		let mut fields: Vec<_> = self
+			.fields_visibility()
+			.into_iter()
+			.filter(|(_, (visible, _))| include_hidden || *visible)
+			.collect();
+		#[cfg(feature = "exp-preserve-order")]
+		if preserve_order {
+			if limit < fields.len() {
+				fields.select_nth_unstable_by_key(limit, |(_, (_, sk))| *sk);
+				fields.truncate(limit);
+			}
+			fields.sort_unstable_by_key(|(_, (_, sk))| *sk);
+			return fields.into_iter().map(|(k, _)| k).collect();
+		}
+
+		if limit < fields.len() {
+			fields.select_nth_unstable_by(limit, |(a, _), (b, _)| a.cmp(b));
+			fields.truncate(limit);
+		}
+		fields.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+		fields.into_iter().map(|(k, _)| k).collect()
+	}
 	pub fn fields(&self, #[cfg(feature = "exp-preserve-order")] preserve_order: bool) -> Vec<IStr> {
 		self.fields_ex(
 			false,
@@ -584,6 +715,13 @@ impl OopObject {
 		sup: Option<ObjValue>,
 		this_entries: Cc<GcHashMap<IStr, ObjMember>>,
 		assertions: Cc<Vec<TraceBox<dyn ObjectAssertion>>>,
+	) -> Self {
+		Self::with_fields(sup, Cc::new(ObjFields::from(&*this_entries)), assertions)
+	}
+	fn with_fields(
+		sup: Option<ObjValue>,
+		this_entries: Cc<ObjFields>,
+		assertions: Cc<Vec<TraceBox<dyn ObjectAssertion>>>,
 	) -> Self {
 		Self {
 			sup,
@@ -627,12 +765,12 @@ impl OopObject {
 impl ObjectLike for OopObject {
 	fn extend_from(&self, sup: ObjValue) -> ObjValue {
 		ObjValue::new(match &self.sup {
-			None => Self::new(
+			None => Self::with_fields(
 				Some(sup),
 				self.this_entries.clone(),
 				self.assertions.clone(),
 			),
-			Some(v) => Self::new(
+			Some(v) => Self::with_fields(
 				Some(v.extend_from(sup)),
 				self.this_entries.clone(),
 				self.assertions.clone(),
@@ -697,7 +835,7 @@ impl ObjectLike for OopObject {
 			return Ok(match v {
 				CacheValue::Cached(v) => Some(v.clone()),
 				CacheValue::NotFound => None,
-				CacheValue::Pending => bail!(InfiniteRecursionDetected),
+				CacheValue::Pending => bail!(InfiniteRecursionDetected(Vec::new())),
 				CacheValue::Errored(e) => return Err(e.clone()),
 			});
 		}
@@ -775,6 +913,141 @@ impl ObjectLike for OopObject {
 	}
 }
 
+/// Object layer, which fields are backed by Rust data, and are only converted to jsonnet values on access.
+///
+/// Can be derived for structs with named fields using [`macro@ObjectLayer`],
+/// and then converted to object with `ObjValue::new(NativeObject::new(layer))`.
+pub trait ObjectLayer: Trace + Debug + 'static {
+	/// Calls `handler` for every field of this layer, stops if `handler` returns true
+	///
+	/// Returns true if ended prematurely
+	fn enum_fields(&self, handler: &mut dyn FnMut(IStr, Visibility) -> bool) -> bool;
+	/// Visibility of field, `None` if there is no such field in this layer
+	fn field_visibility(&self, name: &str) -> Option<Visibility>;
+	/// Computes field value, `None` if there is no such field in this layer
+	fn get(&self, name: &str) -> Result<Option<Val>>;
+}
+
+/// Object, which consists of a single [`ObjectLayer`], optionally extending other object.
+///
+/// Computed field values are cached, but as native fields can't refer to `self`/`super`,
+/// unlike [`OopObject`] the cache is not bound to `this`.
+#[derive(Trace)]
+pub struct NativeObject<L: ObjectLayer> {
+	layer: Cc<L>,
+	sup: Option<ObjValue>,
+	value_cache: RefCell<GcHashMap<IStr, Val>>,
+}
+impl<L: ObjectLayer> NativeObject<L> {
+	pub fn new(layer: L) -> Self {
+		Self {
+			layer: Cc::new(layer),
+			sup: None,
+			value_cache: RefCell::new(GcHashMap::new()),
+		}
+	}
+}
+impl<L: ObjectLayer> Debug for NativeObject<L> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("NativeObject")
+			.field("layer", &*self.layer)
+			.field("sup", &self.sup)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<L: ObjectLayer> ObjectLike for NativeObject<L> {
+	fn extend_from(&self, sup: ObjValue) -> ObjValue {
+		ObjValue::new(Self {
+			layer: self.layer.clone(),
+			sup: Some(match &self.sup {
+				None => sup,
+				Some(v) => v.extend_from(sup),
+			}),
+			value_cache: RefCell::new(GcHashMap::new()),
+		})
+	}
+
+	fn len(&self) -> usize {
+		let mut visible = FxHashMap::default();
+		self.enum_fields(SuperDepth::default(), &mut |_, _, name, visibility| {
+			let entry = visible.entry(name).or_insert(true);
+			match visibility {
+				Visibility::Normal => {}
+				Visibility::Hidden => *entry = false,
+				Visibility::Unhide => *entry = true,
+			}
+			false
+		});
+		visible.into_values().filter(|v| *v).count()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	fn enum_fields(&self, depth: SuperDepth, handler: &mut EnumFieldsHandler<'_>) -> bool {
+		if let Some(s) = &self.sup {
+			if s.enum_fields(depth.deeper(), handler) {
+				return true;
+			}
+		}
+		let mut index = FieldIndex::default();
+		self.layer.enum_fields(&mut |name, visibility| {
+			let current = index;
+			index = index.next();
+			handler(depth, current, name, visibility)
+		})
+	}
+
+	fn has_field_include_hidden(&self, name: IStr) -> bool {
+		self.layer.field_visibility(&name).is_some()
+			|| self
+				.sup
+				.as_ref()
+				.map_or(false, |s| s.has_field_include_hidden(name))
+	}
+	fn has_field(&self, name: IStr) -> bool {
+		self.field_visibility(name)
+			.map_or(false, |v| v.is_visible())
+	}
+
+	fn get_for(&self, key: IStr, this: ObjValue) -> Result<Option<Val>> {
+		if let Some(v) = self.value_cache.borrow().get(&key) {
+			return Ok(Some(v.clone()));
+		}
+		let value = self.get_for_uncached(key.clone(), this)?;
+		if let Some(value) = &value {
+			self.value_cache.borrow_mut().insert(key, value.clone());
+		}
+		Ok(value)
+	}
+	fn get_for_uncached(&self, key: IStr, this: ObjValue) -> Result<Option<Val>> {
+		if let Some(value) = self.layer.get(&key)? {
+			return Ok(Some(value));
+		}
+		self.sup.as_ref().map_or(Ok(None), |s| s.get_raw(key, this))
+	}
+	fn field_visibility(&self, name: IStr) -> Option<Visibility> {
+		match self.layer.field_visibility(&name) {
+			Some(Visibility::Normal) => Some(
+				self.sup
+					.as_ref()
+					.and_then(|s| s.field_visibility(name))
+					.unwrap_or(Visibility::Normal),
+			),
+			Some(v) => Some(v),
+			None => self.sup.as_ref().and_then(|s| s.field_visibility(name)),
+		}
+	}
+
+	fn run_assertions_raw(&self, this: ObjValue) -> Result<()> {
+		self.sup
+			.as_ref()
+			.map_or(Ok(()), |s| s.run_assertions_raw(this))
+	}
+}
+
 impl PartialEq for ObjValue {
 	fn eq(&self, other: &Self) -> bool {
 		Cc::ptr_eq(&self.0, &other.0)
@@ -782,6 +1055,18 @@ impl PartialEq for ObjValue {
 }
 
 impl Eq for ObjValue {}
+/// Creates object with visible fields, ordered the same way as they were iterated.
+///
+/// If the same key is encountered multiple times, the last value wins, and the field is
+/// positioned as the last occurence.
+impl FromIterator<(IStr, Val)> for ObjValue {
+	fn from_iter<T: IntoIterator<Item = (IStr, Val)>>(iter: T) -> Self {
+		Self::from_thunk_iter(
+			iter.into_iter()
+				.map(|(name, value)| (name, Thunk::evaluated(value))),
+		)
+	}
+}
 impl Hash for ObjValue {
 	fn hash<H: Hasher>(&self, hasher: &mut H) {
 		hasher.write_usize(addr_of!(*self.0) as usize);
@@ -791,7 +1076,7 @@ impl Hash for ObjValue {
 #[allow(clippy::module_name_repetitions)]
 pub struct ObjValueBuilder {
 	sup: Option<ObjValue>,
-	map: GcHashMap<IStr, ObjMember>,
+	map: ObjFields,
 	assertions: Vec<TraceBox<dyn ObjectAssertion>>,
 	next_field_index: FieldIndex,
 }
@@ -802,7 +1087,7 @@ impl ObjValueBuilder {
 	pub fn with_capacity(capacity: usize) -> Self {
 		Self {
 			sup: None,
-			map: GcHashMap::with_capacity(capacity),
+			map: ObjFields::with_capacity(capacity),
 			assertions: Vec::new(),
 			next_field_index: FieldIndex::default(),
 		}
@@ -842,11 +1127,12 @@ impl ObjValueBuilder {
 		Ok(self)
 	}
 
-	pub fn build(self) -> ObjValue {
+	pub fn build(mut self) -> ObjValue {
 		if self.sup.is_none() && self.map.is_empty() && self.assertions.is_empty() {
 			return ObjValue::new_empty();
 		}
-		ObjValue::new(OopObject::new(
+		self.map.shrink_to_fit();
+		ObjValue::new(OopObject::with_fields(
 			self.sup,
 			Cc::new(self.map),
 			Cc::new(self.assertions),
@@ -921,8 +1207,13 @@ impl ObjMemberBuilder<ValueBuilder<'_>> {
 	pub fn value(self, value: impl Into<Val>) {
 		let (receiver, name, member) =
 			self.build_member(MaybeUnbound::Bound(Thunk::evaluated(value.into())));
-		let entry = receiver.0.map.entry(name);
-		entry.insert(member);
+		receiver.0.map.insert(name, member);
+	}
+
+	/// Inserts lazy value, replacing if it is already defined
+	pub fn thunk(self, value: impl Into<Thunk<Val>>) {
+		let (receiver, name, member) = self.build_member(MaybeUnbound::Bound(value.into()));
+		receiver.0.map.insert(name, member);
 	}
 
 	/// Tries to insert value, returns an error if it was already defined
@@ -0,0 +1,124 @@
+//! Builtin call statistics, collected when `exp-profile` feature is enabled.
+//!
+//! Every builtin implemented with [`macro@crate::function::builtin`] reports its calls here,
+//! statistics are kept per thread. Without the feature, reporting is no-op.
+
+#[cfg(feature = "exp-profile")]
+pub use enabled::*;
+
+/// Tracks single builtin call, recording its duration when dropped
+#[must_use]
+pub struct BuiltinCallGuard {
+	#[cfg(feature = "exp-profile")]
+	name: &'static str,
+	#[cfg(feature = "exp-profile")]
+	start: std::time::Instant,
+}
+
+/// Called by builtin macro on every builtin call
+#[doc(hidden)]
+#[inline]
+#[allow(unused_variables)]
+pub fn builtin_call(name: &'static str) -> BuiltinCallGuard {
+	BuiltinCallGuard {
+		#[cfg(feature = "exp-profile")]
+		name,
+		#[cfg(feature = "exp-profile")]
+		start: std::time::Instant::now(),
+	}
+}
+
+#[cfg(feature = "exp-profile")]
+mod enabled {
+	use std::{cell::RefCell, fmt, time::Duration};
+
+	use rustc_hash::FxHashMap;
+
+	use super::BuiltinCallGuard;
+
+	thread_local! {
+		static PROFILE: RefCell<FxHashMap<&'static str, BuiltinProfile>> = RefCell::default();
+	}
+
+	/// Accumulated statistics of a single builtin
+	#[derive(Clone, Debug)]
+	pub struct BuiltinProfile {
+		/// Name of the builtin implementation function, i.e `builtin_map` for `std.map`
+		pub name: &'static str,
+		pub calls: u64,
+		/// Total time spent in builtin, including nested calls (i.e callbacks passed to `std.map`)
+		pub total: Duration,
+	}
+	impl fmt::Display for BuiltinProfile {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			write!(
+				f,
+				"{}: {} calls, {}ms",
+				self.name,
+				self.calls,
+				self.total.as_millis()
+			)
+		}
+	}
+
+	impl Drop for BuiltinCallGuard {
+		fn drop(&mut self) {
+			let elapsed = self.start.elapsed();
+			PROFILE.with_borrow_mut(|profile| {
+				let entry = profile.entry(self.name).or_insert_with(|| BuiltinProfile {
+					name: self.name,
+					calls: 0,
+					total: Duration::ZERO,
+				});
+				entry.calls += 1;
+				entry.total += elapsed;
+			});
+		}
+	}
+
+	/// Statistics collected on the current thread, the most time-consuming builtins go first
+	pub fn dump() -> Vec<BuiltinProfile> {
+		let mut out: Vec<_> = PROFILE.with_borrow(|profile| profile.values().cloned().collect());
+		out.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(b.name)));
+		out
+	}
+
+	/// Forget statistics collected on the current thread
+	pub fn reset() {
+		PROFILE.with_borrow_mut(FxHashMap::clear);
+	}
+}
+
+#[cfg(all(test, feature = "exp-profile"))]
+mod tests {
+	use super::{dump, reset};
+	use crate::{
+		function::{builtin, FuncVal},
+		Val,
+	};
+
+	#[builtin]
+	fn profiled_double(x: f64) -> f64 {
+		x * 2.0
+	}
+
+	#[test]
+	fn builtin_calls_are_counted() {
+		reset();
+		let func = FuncVal::builtin(profiled_double {});
+		for i in 0..3 {
+			func.evaluate_simple(&(Val::num(i),), false)
+				.expect("called");
+		}
+		let profile = dump();
+		let entry = profile
+			.iter()
+			.find(|e| e.name == "profiled_double")
+			.expect("call recorded");
+		assert_eq!(entry.calls, 3);
+		assert!(entry.to_string().starts_with("profiled_double: 3 calls, "));
+
+		reset();
+		assert!(dump().is_empty());
+	}
+}
@@ -72,6 +72,21 @@ pub fn check_depth() -> Result<StackDepthGuard, StackOverflowError> {
 	}
 }
 
+/// Returns number of currently active stack frames
+///
+/// Intended for diagnostics, i.e builtins that want to report deep recursion before it becomes
+/// a [`ErrorKind::StackOverflow`] error
+pub fn current_depth() -> usize {
+	#[cfg(feature = "nightly")]
+	{
+		STACK_LIMIT.current_depth.get()
+	}
+	#[cfg(not(feature = "nightly"))]
+	{
+		STACK_LIMIT.with(|limit| limit.current_depth.get())
+	}
+}
+
 pub struct StackDepthLimitOverrideGuard {
 	old_limit: usize,
 }
@@ -7,7 +7,10 @@ use std::{
 use jrsonnet_gcmodule::Trace;
 use jrsonnet_parser::{CodeLocation, Source, Span};
 
-use crate::{error::ErrorKind, Error};
+use crate::{
+	error::{ErrorKind, StackTraceElement},
+	Error,
+};
 
 /// The way paths should be displayed
 #[derive(Clone, Trace)]
@@ -79,20 +82,98 @@ fn print_code_location(
 			out,
 			"{}:{}-{}:{}",
 			start.line,
-			end.column.saturating_sub(1),
-			start.line,
+			start.column.saturating_sub(1),
+			end.line,
 			end.column
 		)?;
 	}
 	Ok(())
 }
 
+/// Writes import chain of [`ErrorKind::InfiniteRecursionDetected`] or [`ErrorKind::ImportTooDeep`]
+/// on a separate line, if any
+fn write_import_cycle(
+	out: &mut dyn std::fmt::Write,
+	error: &Error,
+	resolver: Option<&PathResolver>,
+) -> Result<(), std::fmt::Error> {
+	let (title, cycle) = match error.error() {
+		ErrorKind::InfiniteRecursionDetected(cycle) => ("import cycle", cycle),
+		ErrorKind::ImportTooDeep(_, chain) => ("import chain", chain),
+		_ => return Ok(()),
+	};
+	if cycle.is_empty() {
+		return Ok(());
+	}
+	write!(out, "\n{title}: ")?;
+	for (i, path) in cycle.iter().enumerate() {
+		if i != 0 {
+			write!(out, " -> ")?;
+		}
+		match (path.path(), resolver) {
+			(Some(path), Some(resolver)) => write!(out, "{}", resolver.resolve(path))?,
+			(Some(path), None) => write!(out, "{}", path.display())?,
+			(None, _) => write!(out, "{path}")?,
+		}
+	}
+	Ok(())
+}
+
+/// Elides frames in the middle of long traces, keeping only `head` frames from the top, and
+/// `tail` frames from the bottom, similar to how rustc elides long backtraces
+#[derive(Trace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceElision {
+	pub head: usize,
+	pub tail: usize,
+}
+impl TraceElision {
+	/// Explicit `elision` takes priority, otherwise trace is limited to `max_trace` frames, split between
+	/// top and bottom of the trace. `max_trace == 0` means unlimited, as in libjsonnet
+	fn frames(
+		elision: Option<Self>,
+		max_trace: usize,
+		frames: &[StackTraceElement],
+	) -> Vec<TraceFrame<'_>> {
+		let elision = elision.or_else(|| {
+			(max_trace != 0).then(|| Self {
+				head: max_trace.div_ceil(2),
+				tail: max_trace / 2,
+			})
+		});
+		let Some(Self { head, tail }) = elision else {
+			return frames.iter().map(TraceFrame::Frame).collect();
+		};
+		if frames.len() <= head + tail {
+			return frames.iter().map(TraceFrame::Frame).collect();
+		}
+		let mut out = Vec::with_capacity(head + tail + 1);
+		out.extend(frames[..head].iter().map(TraceFrame::Frame));
+		out.push(TraceFrame::Omitted(frames.len() - head - tail));
+		out.extend(frames[frames.len() - tail..].iter().map(TraceFrame::Frame));
+		out
+	}
+}
+
+enum TraceFrame<'t> {
+	Frame(&'t StackTraceElement),
+	/// Divider in place of elided frames
+	Omitted(usize),
+}
+impl TraceFrame<'_> {
+	fn write_omitted(out: &mut dyn std::fmt::Write, count: usize) -> Result<(), std::fmt::Error> {
+		write!(out, "... ({count} frames omitted) ...")
+	}
+}
+
 /// vanilla-like jsonnet formatting
 #[derive(Trace)]
 pub struct CompactFormat {
 	pub resolver: PathResolver,
+	/// Maximum number of displayed frames, `0` to display all of them
 	pub max_trace: usize,
 	pub padding: usize,
+	/// If set, frames in the middle of the trace are elided
+	pub elision: Option<TraceElision>,
 }
 impl Default for CompactFormat {
 	fn default() -> Self {
@@ -100,6 +181,7 @@ impl Default for CompactFormat {
 			resolver: PathResolver::Absolute,
 			max_trace: 20,
 			padding: 4,
+			elision: None,
 		}
 	}
 }
@@ -111,6 +193,7 @@ impl TraceFormat for CompactFormat {
 		error: &Error,
 	) -> Result<(), std::fmt::Error> {
 		write!(out, "{}", error.error())?;
+		write_import_cycle(out, error, Some(&self.resolver))?;
 		if let ErrorKind::ImportSyntaxError { path, error } = error.error() {
 			use std::fmt::Write;
 
@@ -139,11 +222,13 @@ impl TraceFormat for CompactFormat {
 			print_code_location(&mut n, &location, &location).unwrap();
 			write!(out, "{:<p$}{n}", "", p = self.padding)?;
 		}
-		let file_names = error
-			.trace()
-			.0
+		let frames = TraceElision::frames(self.elision, self.max_trace, &error.trace().0);
+		let file_names = frames
 			.iter()
-			.map(|el| &el.location)
+			.map(|frame| match frame {
+				TraceFrame::Frame(el) => el.location.as_ref(),
+				TraceFrame::Omitted(_) => None,
+			})
 			.map(|location| {
 				use std::fmt::Write;
 				#[allow(clippy::option_if_let_else)]
@@ -169,8 +254,16 @@ impl TraceFormat for CompactFormat {
 			.map(String::len)
 			.max()
 			.unwrap_or(0);
-		for (el, file) in error.trace().0.iter().zip(file_names) {
+		for (frame, file) in frames.iter().zip(file_names) {
 			writeln!(out)?;
+			let el = match frame {
+				TraceFrame::Frame(el) => el,
+				TraceFrame::Omitted(count) => {
+					write!(out, "{:<p$}", "", p = self.padding)?;
+					TraceFrame::write_omitted(out, *count)?;
+					continue;
+				}
+			};
 			if let Some(file) = file {
 				write!(
 					out,
@@ -200,6 +293,8 @@ impl TraceFormat for CompactFormat {
 #[derive(Trace)]
 pub struct JsFormat {
 	pub max_trace: usize,
+	/// If set, frames in the middle of the trace are elided
+	pub elision: Option<TraceElision>,
 }
 impl TraceFormat for JsFormat {
 	fn write_trace(
@@ -208,8 +303,17 @@ impl TraceFormat for JsFormat {
 		error: &Error,
 	) -> Result<(), std::fmt::Error> {
 		write!(out, "{}", error.error())?;
-		for item in &error.trace().0 {
+		write_import_cycle(out, error, None)?;
+		for frame in TraceElision::frames(self.elision, self.max_trace, &error.trace().0) {
 			writeln!(out)?;
+			let item = match frame {
+				TraceFrame::Frame(item) => item,
+				TraceFrame::Omitted(count) => {
+					write!(out, "    ")?;
+					TraceFrame::write_omitted(out, count)?;
+					continue;
+				}
+			};
 			let desc = &item.desc;
 			if let Some(source) = &item.location {
 				let start_end = source.0.map_source_locations(&[source.1, source.2]);
@@ -245,6 +349,8 @@ impl TraceFormat for JsFormat {
 pub struct ExplainingFormat {
 	pub resolver: PathResolver,
 	pub max_trace: usize,
+	/// If set, frames in the middle of the trace are elided
+	pub elision: Option<TraceElision>,
 }
 #[cfg(feature = "explaining-traces")]
 impl TraceFormat for ExplainingFormat {
@@ -254,6 +360,7 @@ impl TraceFormat for ExplainingFormat {
 		error: &Error,
 	) -> Result<(), std::fmt::Error> {
 		write!(out, "{}", error.error())?;
+		write_import_cycle(out, error, Some(&self.resolver))?;
 		if let ErrorKind::ImportSyntaxError { path, error } = error.error() {
 			writeln!(out)?;
 			let offset = error.location.offset;
@@ -274,9 +381,15 @@ impl TraceFormat for ExplainingFormat {
 				"syntax error",
 			)?;
 		}
-		let trace = &error.trace();
-		for item in &trace.0 {
+		for frame in TraceElision::frames(self.elision, self.max_trace, &error.trace().0) {
 			writeln!(out)?;
+			let item = match frame {
+				TraceFrame::Frame(item) => item,
+				TraceFrame::Omitted(count) => {
+					TraceFrame::write_omitted(out, count)?;
+					continue;
+				}
+			};
 			let desc = &item.desc;
 			if let Some(source) = &item.location {
 				let start_end = source.0.map_source_locations(&[source.1, source.2]);
@@ -324,11 +437,23 @@ impl ExplainingFormat {
 			SourceAnnotation,
 		};
 
+		// Span might cross multiple lines, snippet should include all of them
+		let fragment_start = start.line_start_offset;
+		let fragment_end = end.line_end_offset.max(start.line_end_offset);
 		let source_fragment: String = source
 			.chars()
-			.skip(start.line_start_offset)
-			.take(end.line_end_offset - end.line_start_offset)
+			.skip(fragment_start)
+			.take(fragment_end.saturating_sub(fragment_start))
 			.collect();
+		let fragment_len = source_fragment.chars().count();
+		let range_start = start
+			.offset
+			.saturating_sub(fragment_start)
+			.min(fragment_len);
+		let range_end = end
+			.offset
+			.saturating_sub(fragment_start)
+			.clamp(range_start, fragment_len);
 
 		let origin = origin.source_path().path().map_or_else(
 			|| origin.source_path().to_string(),
@@ -349,11 +474,7 @@ impl ExplainingFormat {
 				annotations: vec![SourceAnnotation {
 					label: desc,
 					annotation_type: AnnotationType::Error,
-					range: (
-						start.offset - start.line_start_offset,
-						(end.offset.saturating_sub(start.line_start_offset))
-							.min(source_fragment.len()),
-					),
+					range: (range_start, range_end),
 				}],
 			}],
 		};
@@ -371,6 +492,8 @@ impl ExplainingFormat {
 pub struct HiDocFormat {
 	pub resolver: PathResolver,
 	pub max_trace: usize,
+	/// If set, frames in the middle of the trace are elided
+	pub elision: Option<TraceElision>,
 }
 #[cfg(feature = "explaining-traces")]
 impl TraceFormat for HiDocFormat {
@@ -385,6 +508,7 @@ impl TraceFormat for HiDocFormat {
 		use hi_doc::{source_to_ansi, Formatting, SnippetBuilder, Text};
 
 		write!(out, "{}", error.error())?;
+		write_import_cycle(out, error, Some(&self.resolver))?;
 		if let ErrorKind::ImportSyntaxError { path, error } = error.error() {
 			writeln!(out)?;
 			let offset = error.location.offset;
@@ -397,7 +521,6 @@ impl TraceFormat for HiDocFormat {
 			let ansi = source_to_ansi(&source);
 			write!(out, "{ansi}")?;
 		}
-		let trace = &error.trace();
 		let snippet_builder: RefCell<Option<SnippetBuilder>> = RefCell::new(None);
 		let mut last_location: Option<Span> = None;
 		let mut flush_builder = |data: Option<ResetData>| {
@@ -436,7 +559,20 @@ impl TraceFormat for HiDocFormat {
 			}
 			Some(out)
 		};
-		for item in &trace.0 {
+		for frame in TraceElision::frames(self.elision, self.max_trace, &error.trace().0) {
+			let item = match frame {
+				TraceFrame::Frame(item) => item,
+				TraceFrame::Omitted(count) => {
+					if let Some(flushed) = flush_builder(None) {
+						writeln!(out)?;
+						write!(out, "{flushed}")?;
+					}
+					writeln!(out)?;
+					write!(out, "   ")?;
+					TraceFrame::write_omitted(out, count)?;
+					continue;
+				}
+			};
 			let desc = &item.desc;
 			if let Some(source) = &item.location {
 				if let Some(flushed) = flush_builder(Some(ResetData {
@@ -476,3 +612,60 @@ impl TraceFormat for HiDocFormat {
 		self
 	}
 }
+
+#[cfg(all(test, feature = "explaining-traces"))]
+mod tests {
+	use super::{ExplainingFormat, PathResolver, TraceFormat};
+	use crate::State;
+
+	fn strip_ansi(s: &str) -> String {
+		let mut out = String::new();
+		let mut chars = s.chars();
+		while let Some(c) = chars.next() {
+			if c == '\x1b' {
+				// CSI sequence: ESC [ params final-byte
+				for c in chars.by_ref() {
+					if c.is_ascii_alphabetic() {
+						break;
+					}
+				}
+			} else {
+				out.push(c);
+			}
+		}
+		out
+	}
+
+	#[test]
+	fn explaining_multiline_span() {
+		let e = State::default()
+			.evaluate_snippet("snip", "local f(a, b) = error 'boom';\nf(\n  1,\n  2222)\n")
+			.expect_err("error is thrown");
+		let formatted = ExplainingFormat {
+			resolver: PathResolver::FileName,
+			max_trace: 20,
+			elision: None,
+		}
+		.format(&e)
+		.expect("formatted");
+		let formatted = strip_ansi(&formatted);
+		let lines = formatted
+			.lines()
+			.map(str::trim_end)
+			.skip_while(|l| !l.contains("snip:2:1"))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			lines,
+			[
+				" --> snip:2:1",
+				"  |",
+				"2 | / f(",
+				"3 | |   1,",
+				"4 | |   2222)",
+				"  | |_______^ function <f> call",
+				"  |",
+			],
+			"{formatted}"
+		);
+	}
+}
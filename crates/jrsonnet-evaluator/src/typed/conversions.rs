@@ -1,4 +1,9 @@
-use std::{collections::BTreeMap, marker::PhantomData, ops::Deref};
+use std::{
+	collections::{BTreeMap, HashMap},
+	hash::{BuildHasher, Hash},
+	marker::PhantomData,
+	ops::Deref,
+};
 
 use jrsonnet_gcmodule::{Cc, Trace};
 use jrsonnet_interner::{IBytes, IStr};
@@ -274,6 +279,30 @@ impl Typed for usize {
 	}
 }
 
+impl Typed for i64 {
+	const TYPE: &'static ComplexValType =
+		&ComplexValType::BoundedNumber(Some(MIN_SAFE_INTEGER), Some(MAX_SAFE_INTEGER));
+
+	fn into_untyped(value: Self) -> Result<Val> {
+		Ok(Val::try_num(value)?)
+	}
+
+	fn from_untyped(value: Val) -> Result<Self> {
+		<Self as Typed>::TYPE.check(&value)?;
+		match value {
+			Val::Num(n) => {
+				let n = n.get();
+				#[allow(clippy::float_cmp)]
+				if n.trunc() != n {
+					bail!("cannot convert number with fractional part to i64")
+				}
+				Ok(n as Self)
+			}
+			_ => unreachable!(),
+		}
+	}
+}
+
 impl Typed for IStr {
 	const TYPE: &'static ComplexValType = &ComplexValType::Simple(ValType::Str);
 
@@ -369,48 +398,73 @@ where
 	}
 }
 
+fn map_into_untyped<K: Typed, V: Typed>(
+	len: usize,
+	entries: impl IntoIterator<Item = (K, V)>,
+) -> Result<Val> {
+	let mut out = ObjValueBuilder::with_capacity(len);
+	for (k, v) in entries {
+		let Some(key) = K::into_untyped(k)?.as_str() else {
+			bail!("map key should serialize to string");
+		};
+		let value = V::into_untyped(v)?;
+		out.field(key).value(value);
+	}
+	Ok(Val::Obj(out.build()))
+}
+
+fn map_from_untyped<K: Typed, V: Typed, M: Default + Extend<(K, V)>>(value: Val) -> Result<M> {
+	ComplexValType::AttrsOf(V::TYPE).check(&value)?;
+	let obj = value.as_obj().expect("typecheck should fail");
+
+	let mut out = M::default();
+	if V::wants_lazy() {
+		for key in obj.fields_ex(
+			false,
+			#[cfg(feature = "exp-preserve-order")]
+			false,
+		) {
+			let value = obj.get_lazy(key.clone()).expect("field exists");
+			let value = V::from_lazy_untyped(value)?;
+			let key = K::from_untyped(Val::Str(key.into()))?;
+			out.extend([(key, value)]);
+		}
+	} else {
+		for (key, value) in obj.iter(
+			#[cfg(feature = "exp-preserve-order")]
+			false,
+		) {
+			let key = K::from_untyped(Val::Str(key.into()))?;
+			let value = V::from_untyped(value?)?;
+			out.extend([(key, value)]);
+		}
+	}
+	Ok(out)
+}
+
 impl<K: Typed + Ord, V: Typed> Typed for BTreeMap<K, V> {
 	const TYPE: &'static ComplexValType = &ComplexValType::AttrsOf(V::TYPE);
 
 	fn into_untyped(typed: Self) -> Result<Val> {
-		let mut out = ObjValueBuilder::with_capacity(typed.len());
-		for (k, v) in typed {
-			let Some(key) = K::into_untyped(k)?.as_str() else {
-				bail!("map key should serialize to string");
-			};
-			let value = V::into_untyped(v)?;
-			out.field(key).value(value);
-		}
-		Ok(Val::Obj(out.build()))
+		map_into_untyped(typed.len(), typed)
 	}
 
 	fn from_untyped(value: Val) -> Result<Self> {
-		Self::TYPE.check(&value)?;
-		let obj = value.as_obj().expect("typecheck should fail");
-
-		let mut out = Self::new();
-		if V::wants_lazy() {
-			for key in obj.fields_ex(
-				false,
-				#[cfg(feature = "exp-preserve-order")]
-				false,
-			) {
-				let value = obj.get_lazy(key.clone()).expect("field exists");
-				let value = V::from_lazy_untyped(value)?;
-				let key = K::from_untyped(Val::Str(key.into()))?;
-				let _ = out.insert(key, value);
-			}
-		} else {
-			for (key, value) in obj.iter(
-				#[cfg(feature = "exp-preserve-order")]
-				false,
-			) {
-				let key = K::from_untyped(Val::Str(key.into()))?;
-				let value = V::from_untyped(value?)?;
-				let _ = out.insert(key, value);
-			}
-		}
-		Ok(out)
+		map_from_untyped(value)
+	}
+}
+
+/// Object with arbitrary keys, unlike [`BTreeMap`], field order of the produced object is
+/// determined by the hasher, it only matters with `exp-preserve-order`
+impl<K: Typed + Eq + Hash, V: Typed, S: BuildHasher + Default> Typed for HashMap<K, V, S> {
+	const TYPE: &'static ComplexValType = &ComplexValType::AttrsOf(V::TYPE);
+
+	fn into_untyped(typed: Self) -> Result<Val> {
+		map_into_untyped(typed.len(), typed)
+	}
+
+	fn from_untyped(value: Val) -> Result<Self> {
+		map_from_untyped(value)
 	}
 }
 
@@ -460,8 +514,9 @@ impl Typed for IBytes {
 			<Self as Typed>::TYPE.check(&value)?;
 			unreachable!()
 		};
-		if let Some(bytes) = a.as_any().downcast_ref::<BytesArray>() {
-			return Ok(bytes.0.as_slice().into());
+		if let Some(bytes) = a.downcast_ref::<BytesArray>() {
+			// Already interned, no need to copy
+			return Ok(bytes.0.clone());
 		};
 		<Self as Typed>::TYPE.check(&value)?;
 		// Any::downcast_ref::<ByteArray>(&a);
@@ -0,0 +1,107 @@
+//! Conversions for [`chrono`] types.
+//!
+//! Date/time values are represented as RFC 3339 strings (i.e `2024-01-31T12:00:00Z`,
+//! `2024-01-31T12:00:00.5+03:00`), durations are represented as number of seconds, with fractional
+//! part used for sub-second precision (up to nanoseconds).
+
+use chrono::{DateTime, FixedOffset, SecondsFormat, TimeDelta, Utc};
+use jrsonnet_types::{ComplexValType, ValType};
+
+use super::{CheckType, TypeError, Typed};
+use crate::{Result, Val};
+
+fn parse_rfc3339(value: Val) -> Result<DateTime<FixedOffset>> {
+	<DateTime<Utc> as Typed>::TYPE.check(&value)?;
+	let Val::Str(s) = value else { unreachable!() };
+	let s = s.into_flat();
+	Ok(DateTime::parse_from_rfc3339(&s)
+		.map_err(|e| TypeError::InvalidValue("RFC 3339 datetime", format!("{s:?}: {e}").into()))?)
+}
+
+impl Typed for DateTime<Utc> {
+	const TYPE: &'static ComplexValType = &ComplexValType::Simple(ValType::Str);
+
+	fn into_untyped(typed: Self) -> Result<Val> {
+		Ok(Val::string(
+			typed.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+		))
+	}
+
+	fn from_untyped(untyped: Val) -> Result<Self> {
+		Ok(parse_rfc3339(untyped)?.with_timezone(&Utc))
+	}
+}
+
+impl Typed for DateTime<FixedOffset> {
+	const TYPE: &'static ComplexValType = &ComplexValType::Simple(ValType::Str);
+
+	fn into_untyped(typed: Self) -> Result<Val> {
+		Ok(Val::string(
+			typed.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+		))
+	}
+
+	fn from_untyped(untyped: Val) -> Result<Self> {
+		parse_rfc3339(untyped)
+	}
+}
+
+impl Typed for TimeDelta {
+	const TYPE: &'static ComplexValType = &ComplexValType::Simple(ValType::Num);
+
+	#[allow(clippy::cast_precision_loss)]
+	fn into_untyped(typed: Self) -> Result<Val> {
+		let secs = typed.num_seconds() as f64;
+		let nanos = f64::from(typed.subsec_nanos()) / 1e9;
+		Ok(Val::try_num(secs + nanos)?)
+	}
+
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	fn from_untyped(untyped: Val) -> Result<Self> {
+		let secs = f64::from_untyped(untyped)?;
+		let whole = secs.floor();
+		let mut nanos = ((secs - whole) * 1e9).round() as u32;
+		let mut whole = whole as i64;
+		if nanos >= 1_000_000_000 {
+			whole += 1;
+			nanos -= 1_000_000_000;
+		}
+		Ok(TimeDelta::new(whole, nanos).ok_or_else(|| {
+			TypeError::InvalidValue("duration", format!("{secs} seconds is out of range").into())
+		})?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::{DateTime, TimeDelta, Utc};
+
+	use crate::{error::ErrorKind, typed::Typed, Val};
+
+	#[test]
+	fn datetime_roundtrip() {
+		let parsed =
+			DateTime::<Utc>::from_untyped(Val::string("2024-01-31T15:00:00.5+03:00")).unwrap();
+		let Val::Str(s) = DateTime::<Utc>::into_untyped(parsed).unwrap() else {
+			unreachable!()
+		};
+		assert_eq!(s.to_string(), "2024-01-31T12:00:00.500Z");
+	}
+
+	#[test]
+	fn datetime_parse_error() {
+		let err = DateTime::<Utc>::from_untyped(Val::string("yesterday")).unwrap_err();
+		assert!(matches!(err.error(), ErrorKind::TypeError(_)));
+	}
+
+	#[test]
+	#[allow(clippy::float_cmp)]
+	fn duration_roundtrip() {
+		let duration = TimeDelta::from_untyped(Val::try_num(-1.25).unwrap()).unwrap();
+		assert_eq!(duration, TimeDelta::milliseconds(-1250));
+		let Val::Num(n) = TimeDelta::into_untyped(duration).unwrap() else {
+			unreachable!()
+		};
+		assert_eq!(n.get(), -1.25);
+	}
+}
@@ -1,12 +1,15 @@
 use std::{fmt::Display, rc::Rc};
 
 pub(crate) mod conversions;
+#[cfg(feature = "exp-chrono")]
+mod datetime;
 pub use conversions::*;
 use jrsonnet_gcmodule::Trace;
 pub use jrsonnet_types::{ComplexValType, ValType};
 use thiserror::Error;
 
 use crate::{
+	arr::{ArrValue, BytesArray},
 	error::{Error, ErrorKind, Result},
 	in_description_frame, Val,
 };
@@ -25,6 +28,8 @@ pub enum TypeError {
 		.2.map(|v|v.to_string()).unwrap_or_default(),
 	)]
 	BoundsFailed(f64, Option<f64>, Option<f64>),
+	#[error("invalid {0}: {1}")]
+	InvalidValue(&'static str, #[trace(skip)] Rc<str>),
 }
 impl From<TypeError> for Error {
 	fn from(e: TypeError) -> Self {
@@ -143,6 +148,19 @@ impl Display for ValuePathStack {
 	}
 }
 
+/// Byte arrays (i.e from `importbin`) are known to only contain numbers in `0..=255` range, there is
+/// no need to check every element when they are allowed by `elem_type`
+fn bytes_fit(elem_type: &ComplexValType, arr: &ArrValue) -> bool {
+	let fits = match elem_type {
+		ComplexValType::Any | ComplexValType::Simple(ValType::Num) => true,
+		ComplexValType::BoundedNumber(from, to) => {
+			from.map_or(true, |from| from <= 0.0) && to.map_or(true, |to| to >= 255.0)
+		}
+		_ => false,
+	};
+	fits && arr.downcast_ref::<BytesArray>().is_some()
+}
+
 impl CheckType for ComplexValType {
 	#[allow(clippy::too_many_lines)]
 	fn check(&self, value: &Val) -> Result<()> {
@@ -168,6 +186,9 @@ impl CheckType for ComplexValType {
 			}
 			Self::Array(elem_type) => match value {
 				Val::Arr(a) => {
+					if bytes_fit(elem_type, a) {
+						return Ok(());
+					}
 					for (i, item) in a.iter().enumerate() {
 						push_type_description(
 							|| format!("array index {i}"),
@@ -181,6 +202,9 @@ impl CheckType for ComplexValType {
 			},
 			Self::ArrayRef(elem_type) => match value {
 				Val::Arr(a) => {
+					if bytes_fit(elem_type, a) {
+						return Ok(());
+					}
 					for (i, item) in a.iter().enumerate() {
 						push_type_description(
 							|| format!("array index {i}"),
@@ -16,14 +16,12 @@ use thiserror::Error;
 
 pub use crate::arr::{ArrValue, ArrayLike};
 use crate::{
-	bail,
+	bail, budget,
 	error::{Error, ErrorKind::*},
 	function::FuncVal,
 	gc::{GcHashMap, TraceBox},
 	manifest::{ManifestFormat, ToStringFormat},
-	tb,
-	typed::BoundedUsize,
-	ObjValue, Result, Unbound, WeakObjValue,
+	tb, ObjValue, Result, Unbound, WeakObjValue,
 };
 
 pub trait ThunkValue: Trace {
@@ -98,13 +96,15 @@ where
 	///
 	/// - Lazy value evaluation returned error
 	/// - This method was called during inner value evaluation
+	/// - Thunk evaluation budget is exceeded, see [`budget::limit_thunks`]
 	pub fn evaluate(&self) -> Result<T> {
 		match &*self.0.borrow() {
 			ThunkInner::Computed(v) => return Ok(v.clone()),
 			ThunkInner::Errored(e) => return Err(e.clone()),
-			ThunkInner::Pending => return Err(InfiniteRecursionDetected.into()),
+			ThunkInner::Pending => return Err(InfiniteRecursionDetected(Vec::new()).into()),
 			ThunkInner::Waiting(..) => (),
 		};
+		budget::consume_thunk()?;
 		let ThunkInner::Waiting(value) = replace(&mut *self.0.borrow_mut(), ThunkInner::Pending)
 		else {
 			unreachable!();
@@ -243,17 +243,54 @@ impl IndexableVal {
 	}
 	/// Slice the value.
 	///
+	/// Negative `step` iterates in reverse, starting from `index` (defaults to the last element),
+	/// and stopping before `end` (defaults to going past the first element), same as in Python.
+	///
 	/// # Implementation
 	///
 	/// For strings, will create a copy of specified interval.
 	///
 	/// For arrays, nothing will be copied on this call, instead [`ArrValue::Slice`] view will be returned.
-	pub fn slice(
-		self,
-		index: Option<i32>,
-		end: Option<i32>,
-		step: Option<BoundedUsize<1, { i32::MAX as usize }>>,
-	) -> Result<Self> {
+	pub fn slice(self, index: Option<i32>, end: Option<i32>, step: Option<i32>) -> Result<Self> {
+		let step = step.unwrap_or(1);
+		let Some(step_abs) = NonZeroU32::new(step.unsigned_abs()) else {
+			bail!("slice step can't be zero");
+		};
+		if step < 0 {
+			// Reverse value, and slice it with positive step, with bounds mirrored
+			let (reversed, len) = match self {
+				Self::Str(s) => {
+					let reversed = s.chars().rev().collect::<String>();
+					let len = reversed.chars().count();
+					(Self::Str(reversed.into()), len)
+				}
+				Self::Arr(arr) => {
+					let len = arr.len();
+					(Self::Arr(arr.reversed()), len)
+				}
+			};
+			#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+			let len = len as i32;
+			let last = len - 1;
+			// -1 stands for "before the first element"
+			let resolve = |pos: i32| {
+				let pos = if pos < 0 { pos + len } else { pos };
+				pos.clamp(-1, last)
+			};
+			let index = index.map_or(last, resolve);
+			let end = end.map_or(-1, resolve);
+			if index <= end {
+				return Ok(match reversed {
+					Self::Str(_) => Self::Str("".into()),
+					Self::Arr(_) => Self::Arr(ArrValue::empty()),
+				});
+			}
+			return Ok(reversed.slice_forward(Some(last - index), Some(last - end), step_abs));
+		}
+		Ok(self.slice_forward(index, end, step_abs))
+	}
+
+	fn slice_forward(self, index: Option<i32>, end: Option<i32>, step: NonZeroU32) -> Self {
 		match &self {
 			Self::Str(s) => {
 				let mut computed_len = None;
@@ -269,7 +306,7 @@ impl IndexableVal {
 				};
 				let mut get_idx = |pos: Option<i32>, default| {
 					match pos {
-						Some(v) if v < 0 => get_len().saturating_sub((-v) as usize),
+						Some(v) if v < 0 => get_len().saturating_sub(v.unsigned_abs() as usize),
 						// No need to clamp, as iterator interface is used
 						Some(v) => v as usize,
 						None => default,
@@ -278,26 +315,21 @@ impl IndexableVal {
 
 				let index = get_idx(index, 0);
 				let end = get_idx(end, usize::MAX);
-				let step = step.as_deref().copied().unwrap_or(1);
 
 				if index >= end {
-					return Ok(Self::Str("".into()));
+					return Self::Str("".into());
 				}
 
-				Ok(Self::Str(
+				Self::Str(
 					(s.chars()
 						.skip(index)
 						.take(end - index)
-						.step_by(step)
+						.step_by(step.get() as usize)
 						.collect::<String>())
 					.into(),
-				))
+				)
 			}
-			Self::Arr(arr) => Ok(Self::Arr(arr.clone().slice(
-				index,
-				end,
-				step.map(|v| NonZeroU32::new(v.value() as u32).expect("bounded != 0")),
-			))),
+			Self::Arr(arr) => Self::Arr(arr.clone().slice(index, end, Some(step))),
 		}
 	}
 }
@@ -416,6 +448,28 @@ impl NumValue {
 	pub const fn get(&self) -> f64 {
 		self.0
 	}
+	/// Returns `true` if number has no fractional part, `-0` is integral too
+	///
+	/// Note that every finite f64 with magnitude above 2^52 is integral, even if it is
+	/// not exactly the number written in source code.
+	#[inline]
+	pub fn is_integral(&self) -> bool {
+		self.0.fract() == 0.0
+	}
+	/// Returns number as `i64`, if it is integral and fits into `i64` range
+	///
+	/// Unlike `as` cast, values outside of `i64` range are not saturated, and `None` is returned
+	/// for them, so callers might fall back to float representation instead.
+	pub fn as_i64_exact(&self) -> Option<i64> {
+		// i64::MIN is exactly representable as f64, i64::MAX is not, and gets rounded up to 2^63
+		const LOWER: f64 = i64::MIN as f64;
+		const UPPER: f64 = -(i64::MIN as f64);
+		if !self.is_integral() || self.0 < LOWER || self.0 >= UPPER {
+			return None;
+		}
+		#[allow(clippy::cast_possible_truncation)]
+		Some(self.0 as i64)
+	}
 }
 impl PartialEq for NumValue {
 	fn eq(&self, other: &Self) -> bool {
@@ -582,6 +636,17 @@ impl Val {
 			_ => None,
 		}
 	}
+	/// Returns value as `i64`, if it is an integral number, which fits into `i64` range
+	///
+	/// See [`NumValue::as_i64_exact`]
+	pub fn as_i64_exact(&self) -> Option<i64> {
+		match self {
+			Self::Num(n) => n.as_i64_exact(),
+			#[cfg(feature = "exp-bigint")]
+			Self::BigInt(n) => i64::try_from(&**n).ok(),
+			_ => None,
+		}
+	}
 	pub fn as_arr(&self) -> Option<ArrValue> {
 		match self {
 			Self::Arr(a) => Some(a.clone()),
@@ -621,6 +686,29 @@ impl Val {
 		}
 		manifest_dyn(self, &format)
 	}
+	/// Same as [`Self::manifest`], but writes output directly to `out`, without allocating the
+	/// whole manifested string for formats which support incremental output
+	pub fn manifest_to(
+		&self,
+		out: &mut dyn std::fmt::Write,
+		format: impl ManifestFormat,
+	) -> Result<()> {
+		fn manifest_dyn(
+			val: &Val,
+			out: &mut dyn std::fmt::Write,
+			manifest: &dyn ManifestFormat,
+		) -> Result<()> {
+			manifest.manifest_to(val.clone(), out)
+		}
+		manifest_dyn(self, out, &format)
+	}
+	/// Same as [`Self::manifest`], but also supports binary formats
+	pub fn manifest_bytes(&self, format: impl ManifestFormat) -> Result<Vec<u8>> {
+		fn manifest_dyn(val: &Val, manifest: &dyn ManifestFormat) -> Result<Vec<u8>> {
+			manifest.manifest_bytes(val.clone())
+		}
+		manifest_dyn(self, &format)
+	}
 
 	pub fn to_string(&self) -> Result<IStr> {
 		Ok(match self {
@@ -632,6 +720,13 @@ impl Val {
 		})
 	}
 
+	/// Structural equality, with the same semantics as jsonnet `==` operator:
+	/// values of different types are never equal, arrays and objects are compared recursively
+	/// (only visible fields are considered), comparing functions is an error
+	pub fn structural_eq(&self, other: &Self) -> Result<bool> {
+		equals(self, other)
+	}
+
 	pub fn into_indexable(self) -> Result<IndexableVal> {
 		Ok(match self {
 			Self::Str(s) => IndexableVal::Str(s.into_flat()),
@@ -752,3 +847,66 @@ pub fn equals(val_a: &Val, val_b: &Val) -> Result<bool> {
 		(a, b) => Ok(primitive_equals(a, b)?),
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::NumValue;
+	use crate::State;
+
+	fn num(v: f64) -> NumValue {
+		NumValue::new(v).expect("finite")
+	}
+
+	#[test]
+	fn integral() {
+		assert!(num(1.0).is_integral());
+		assert!(num(-0.0).is_integral());
+		assert!(num(1e300).is_integral());
+		assert!(!num(0.5).is_integral());
+	}
+
+	#[test]
+	fn i64_exact() {
+		assert_eq!(num(42.0).as_i64_exact(), Some(42));
+		assert_eq!(num(-0.0).as_i64_exact(), Some(0));
+		assert_eq!(num(1.5).as_i64_exact(), None);
+		assert_eq!(num(i64::MIN as f64).as_i64_exact(), Some(i64::MIN));
+		// 2^63, which is what i64::MAX gets rounded to
+		assert_eq!(num(i64::MAX as f64).as_i64_exact(), None);
+		assert_eq!(num(1e300).as_i64_exact(), None);
+		assert_eq!(num(-1e300).as_i64_exact(), None);
+	}
+
+	#[test]
+	fn structural_eq() {
+		let s = State::default();
+		let eval = |code: &str| s.evaluate_snippet("<test>", code).expect("evaluated");
+
+		let a = eval("{ a: [1, { b: 'c' }], h:: 1 }");
+		assert!(a
+			.structural_eq(&eval("{ a: [1, { b: 'c' }], h:: 2 }"))
+			.unwrap());
+		assert!(!a.structural_eq(&eval("{ a: [1, { b: 'd' }] }")).unwrap());
+		assert!(!a
+			.structural_eq(&eval("{ a: [1, { b: 'c' }], x: 1 }"))
+			.unwrap());
+		assert!(!eval("1").structural_eq(&eval("'1'")).unwrap());
+
+		let f = eval("function() 1");
+		let e = f
+			.structural_eq(&f)
+			.expect_err("functions are not comparable");
+		assert_eq!(
+			e.error().to_string(),
+			"runtime error: cannot test equality of functions"
+		);
+		// Same as `==`, function nested in object is compared too
+		let e = eval("{ f: function() 1 }")
+			.structural_eq(&eval("{ f: function() 1 }"))
+			.expect_err("functions are not comparable");
+		assert_eq!(
+			e.error().to_string(),
+			"runtime error: cannot test equality of functions"
+		);
+	}
+}
@@ -0,0 +1,574 @@
+//! Lints, working on top of lossless syntax tree produced by `jrsonnet-rowan-parser`
+
+use jrsonnet_rowan_parser::{
+	nodes::{
+		ArgsDesc, Assertion, Bind, BindDestruct, CompSpec, Destruct, DestructArrayPart,
+		DestructRest, Expr, ExprBase, FieldName, Member, MemberComp, Name, ObjBody, ObjLocal,
+		ParamsDesc, SourceFile, Stmt, Suffix,
+	},
+	rowan::TextRange,
+	AstNode,
+};
+
+/// Single lint finding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+	/// Range of the offending code
+	pub range: TextRange,
+	pub message: String,
+}
+
+/// Runs all lints over parsed file
+pub fn lint(file: &SourceFile) -> Vec<Lint> {
+	unused_locals(file)
+}
+
+/// Finds `local` bindings (both in expressions and in objects), which are never referenced
+///
+/// Function parameters and comprehension variables are not reported, they are often unused
+/// intentionally.
+pub fn unused_locals(file: &SourceFile) -> Vec<Lint> {
+	let mut walker = Walker::default();
+	if let Some(expr) = file.expr() {
+		walker.expr(&expr);
+	}
+	walker.lints.sort_by_key(|l| l.range.start());
+	walker.lints
+}
+
+struct Binding {
+	name: String,
+	range: TextRange,
+	used: bool,
+	/// Report this binding if it is unused
+	report: bool,
+}
+
+#[derive(Default)]
+struct Walker {
+	/// Innermost scope is the last one
+	scopes: Vec<Vec<Binding>>,
+	lints: Vec<Lint>,
+}
+
+impl Walker {
+	fn push_scope(&mut self) {
+		self.scopes.push(Vec::new());
+	}
+	fn pop_scope(&mut self) {
+		let scope = self.scopes.pop().expect("scope was pushed");
+		for binding in scope {
+			if binding.report && !binding.used {
+				self.lints.push(Lint {
+					range: binding.range,
+					message: format!("unused local: {}", binding.name),
+				});
+			}
+		}
+	}
+
+	fn define(&mut self, name: &Name, report: bool) {
+		let Some(ident) = name.ident_lit() else {
+			return;
+		};
+		let scope = self.scopes.last_mut().expect("scope was pushed");
+		scope.push(Binding {
+			name: ident.text().to_owned(),
+			range: name.syntax().text_range(),
+			used: false,
+			report,
+		});
+	}
+	fn define_destruct(&mut self, destruct: &Destruct, report: bool) {
+		match destruct {
+			Destruct::DestructFull(full) => {
+				if let Some(name) = full.name() {
+					self.define(&name, report);
+				}
+			}
+			Destruct::DestructSkip(_) => {}
+			Destruct::DestructArray(arr) => {
+				for part in arr.destruct_array_parts() {
+					match part {
+						DestructArrayPart::DestructArrayElement(elem) => {
+							if let Some(destruct) = elem.destruct() {
+								self.define_destruct(&destruct, report);
+							}
+						}
+						DestructArrayPart::DestructRest(rest) => {
+							if let Some(name) = DestructRest::into(&rest) {
+								self.define(&name, report);
+							}
+						}
+					}
+				}
+			}
+			Destruct::DestructObject(obj) => {
+				for field in obj.destruct_object_fields() {
+					if let Some(destruct) = field.destruct() {
+						self.define_destruct(&destruct, report);
+					} else if let Some(name) = field.field() {
+						self.define(&name, report);
+					}
+				}
+				if let Some(name) = obj.destruct_rest().and_then(|r| DestructRest::into(&r)) {
+					self.define(&name, report);
+				}
+			}
+		}
+	}
+	/// Visits default values of destructured object fields
+	fn destruct_defaults(&mut self, destruct: &Destruct) {
+		match destruct {
+			Destruct::DestructFull(_) | Destruct::DestructSkip(_) => {}
+			Destruct::DestructArray(arr) => {
+				for part in arr.destruct_array_parts() {
+					if let DestructArrayPart::DestructArrayElement(elem) = part {
+						if let Some(destruct) = elem.destruct() {
+							self.destruct_defaults(&destruct);
+						}
+					}
+				}
+			}
+			Destruct::DestructObject(obj) => {
+				for field in obj.destruct_object_fields() {
+					if let Some(destruct) = field.destruct() {
+						self.destruct_defaults(&destruct);
+					}
+					if let Some(default) = field.expr() {
+						self.expr(&default);
+					}
+				}
+			}
+		}
+	}
+
+	fn use_name(&mut self, name: &Name) {
+		let Some(ident) = name.ident_lit() else {
+			return;
+		};
+		let ident = ident.text();
+		for scope in self.scopes.iter_mut().rev() {
+			// Last definition in the scope wins, i.e `local a = 1, a = 2` is an error anyway
+			if let Some(binding) = scope.iter_mut().rev().find(|b| b.name == ident) {
+				binding.used = true;
+				return;
+			}
+		}
+	}
+
+	/// Defines bind name in current scope, without visiting its value
+	fn define_bind(&mut self, bind: &Bind) {
+		match bind {
+			Bind::BindDestruct(b) => {
+				if let Some(into) = BindDestruct::into(b) {
+					self.define_destruct(&into, true);
+				}
+			}
+			Bind::BindFunction(b) => {
+				if let Some(name) = b.name() {
+					self.define(&name, true);
+				}
+			}
+		}
+	}
+	fn bind_value(&mut self, bind: &Bind) {
+		match bind {
+			Bind::BindDestruct(b) => {
+				if let Some(into) = BindDestruct::into(b) {
+					self.destruct_defaults(&into);
+				}
+				if let Some(value) = b.value() {
+					self.expr(&value);
+				}
+			}
+			Bind::BindFunction(b) => self.function(b.params(), b.value()),
+		}
+	}
+
+	fn function(&mut self, params: Option<ParamsDesc>, body: Option<Expr>) {
+		self.push_scope();
+		let params = params.map(|p| p.params().collect::<Vec<_>>());
+		// All params are visible in every default value
+		for param in params.iter().flatten() {
+			if let Some(destruct) = param.destruct() {
+				self.define_destruct(&destruct, false);
+			}
+		}
+		for param in params.iter().flatten() {
+			if let Some(destruct) = param.destruct() {
+				self.destruct_defaults(&destruct);
+			}
+			if let Some(default) = param.expr() {
+				self.expr(&default);
+			}
+		}
+		if let Some(body) = body {
+			self.expr(&body);
+		}
+		self.pop_scope();
+	}
+
+	fn assertion(&mut self, assertion: &Assertion) {
+		if let Some(cond) = assertion.condition() {
+			self.expr(&cond);
+		}
+		if let Some(message) = assertion.message() {
+			self.expr(&message);
+		}
+	}
+
+	fn args(&mut self, args: &ArgsDesc) {
+		for arg in args.args() {
+			if let Some(expr) = arg.expr() {
+				self.expr(&expr);
+			}
+		}
+	}
+
+	fn expr(&mut self, expr: &Expr) {
+		// Every `local` statement opens a scope, which lasts until the end of expression
+		let mut opened = 0;
+		for stmt in expr.stmts() {
+			match stmt {
+				Stmt::StmtLocal(local) => {
+					self.push_scope();
+					opened += 1;
+					let binds = local.binds().collect::<Vec<_>>();
+					// Binds are mutually recursive
+					for bind in &binds {
+						self.define_bind(bind);
+					}
+					for bind in &binds {
+						self.bind_value(bind);
+					}
+				}
+				Stmt::StmtAssert(assert) => {
+					if let Some(assertion) = assert.assertion() {
+						self.assertion(&assertion);
+					}
+				}
+			}
+		}
+		if let Some(base) = expr.expr_base() {
+			self.expr_base(&base);
+		}
+		for suffix in expr.suffixs() {
+			match suffix {
+				Suffix::SuffixIndex(_) => {}
+				Suffix::SuffixIndexExpr(index) => {
+					if let Some(index) = index.index() {
+						self.expr(&index);
+					}
+				}
+				Suffix::SuffixSlice(slice) => {
+					let Some(desc) = slice.slice_desc() else {
+						continue;
+					};
+					if let Some(from) = desc.from() {
+						self.expr(&from);
+					}
+					if let Some(end) = desc.end().and_then(|e| e.expr()) {
+						self.expr(&end);
+					}
+					if let Some(step) = desc.step().and_then(|s| s.expr()) {
+						self.expr(&step);
+					}
+				}
+				Suffix::SuffixApply(apply) => {
+					if let Some(args) = apply.args_desc() {
+						self.args(&args);
+					}
+				}
+			}
+		}
+		for _ in 0..opened {
+			self.pop_scope();
+		}
+	}
+
+	fn expr_base(&mut self, base: &ExprBase) {
+		match base {
+			ExprBase::ExprBinary(b) => {
+				if let Some(lhs) = b.lhs_work() {
+					self.expr(&lhs);
+				}
+				if let Some(rhs) = b.rhs_work() {
+					self.expr(&rhs);
+				}
+			}
+			ExprBase::ExprObjExtend(e) => {
+				if let Some(lhs) = e.lhs_work() {
+					self.expr(&lhs);
+				}
+				if let Some(rhs) = e.rhs_work() {
+					self.expr(&rhs);
+				}
+			}
+			ExprBase::ExprUnary(u) => {
+				if let Some(rhs) = u.rhs() {
+					self.expr(&rhs);
+				}
+			}
+			ExprBase::ExprParened(p) => {
+				if let Some(expr) = p.expr() {
+					self.expr(&expr);
+				}
+			}
+			ExprBase::ExprString(_)
+			| ExprBase::ExprNumber(_)
+			| ExprBase::ExprLiteral(_)
+			| ExprBase::ExprImport(_) => {}
+			ExprBase::ExprArray(a) => {
+				for expr in a.exprs() {
+					self.expr(&expr);
+				}
+			}
+			ExprBase::ExprObject(o) => {
+				if let Some(body) = o.obj_body() {
+					self.obj_body(&body);
+				}
+			}
+			ExprBase::ExprArrayComp(c) => {
+				let opened = self.comp_specs(c.comp_specs());
+				if let Some(expr) = c.expr() {
+					self.expr(&expr);
+				}
+				for _ in 0..opened {
+					self.pop_scope();
+				}
+			}
+			ExprBase::ExprVar(v) => {
+				if let Some(name) = v.name() {
+					self.use_name(&name);
+				}
+			}
+			ExprBase::ExprIfThenElse(i) => {
+				if let Some(cond) = i.cond() {
+					self.expr(&cond);
+				}
+				if let Some(then) = i.then().and_then(|t| t.expr()) {
+					self.expr(&then);
+				}
+				if let Some(else_) = i.else_().and_then(|e| e.expr()) {
+					self.expr(&else_);
+				}
+			}
+			ExprBase::ExprFunction(f) => self.function(f.params_desc(), f.expr()),
+			ExprBase::ExprError(e) => {
+				if let Some(expr) = e.expr() {
+					self.expr(&expr);
+				}
+			}
+		}
+	}
+
+	/// Visits comprehension specs, leaving one scope opened per `for` spec, returns number of opened scopes
+	fn comp_specs(&mut self, specs: impl Iterator<Item = CompSpec>) -> usize {
+		let mut opened = 0;
+		for spec in specs {
+			match spec {
+				CompSpec::ForSpec(f) => {
+					if let Some(expr) = f.expr() {
+						self.expr(&expr);
+					}
+					self.push_scope();
+					opened += 1;
+					if let Some(bind) = f.bind() {
+						self.define_destruct(&bind, false);
+						self.destruct_defaults(&bind);
+					}
+				}
+				CompSpec::IfSpec(i) => {
+					if let Some(expr) = i.expr() {
+						self.expr(&expr);
+					}
+				}
+			}
+		}
+		opened
+	}
+
+	fn obj_locals<'a>(&mut self, locals: impl Iterator<Item = &'a ObjLocal>) -> Vec<Bind> {
+		let binds = locals.filter_map(ObjLocal::bind).collect::<Vec<_>>();
+		for bind in &binds {
+			self.define_bind(bind);
+		}
+		binds
+	}
+
+	fn field_name(&mut self, name: Option<FieldName>) {
+		if let Some(FieldName::FieldNameDynamic(d)) = name {
+			if let Some(expr) = d.expr() {
+				self.expr(&expr);
+			}
+		}
+	}
+
+	fn obj_body(&mut self, body: &ObjBody) {
+		match body {
+			ObjBody::ObjBodyMemberList(list) => {
+				let members = list.members().collect::<Vec<_>>();
+				// Field names are evaluated outside of object scope
+				for member in &members {
+					match member {
+						Member::MemberFieldNormal(f) => self.field_name(f.field_name()),
+						Member::MemberFieldMethod(f) => self.field_name(f.field_name()),
+						Member::MemberBindStmt(_) | Member::MemberAssertStmt(_) => {}
+					}
+				}
+				self.push_scope();
+				let locals = members
+					.iter()
+					.filter_map(|m| match m {
+						Member::MemberBindStmt(b) => b.obj_local(),
+						_ => None,
+					})
+					.collect::<Vec<_>>();
+				let binds = self.obj_locals(locals.iter());
+				for bind in &binds {
+					self.bind_value(bind);
+				}
+				for member in &members {
+					match member {
+						Member::MemberBindStmt(_) => {}
+						Member::MemberAssertStmt(a) => {
+							if let Some(assertion) = a.assertion() {
+								self.assertion(&assertion);
+							}
+						}
+						Member::MemberFieldNormal(f) => {
+							if let Some(expr) = f.expr() {
+								self.expr(&expr);
+							}
+						}
+						Member::MemberFieldMethod(f) => self.function(f.params_desc(), f.expr()),
+					}
+				}
+				self.pop_scope();
+			}
+			ObjBody::ObjBodyComp(comp) => {
+				let opened = self.comp_specs(comp.comp_specs());
+				let members = comp.member_comps().collect::<Vec<_>>();
+				for member in &members {
+					match member {
+						MemberComp::MemberFieldNormal(f) => self.field_name(f.field_name()),
+						MemberComp::MemberFieldMethod(f) => self.field_name(f.field_name()),
+						MemberComp::MemberBindStmt(_) => {}
+					}
+				}
+				self.push_scope();
+				let locals = members
+					.iter()
+					.filter_map(|m| match m {
+						MemberComp::MemberBindStmt(b) => b.obj_local(),
+						_ => None,
+					})
+					.collect::<Vec<_>>();
+				let binds = self.obj_locals(locals.iter());
+				for bind in &binds {
+					self.bind_value(bind);
+				}
+				for member in &members {
+					match member {
+						MemberComp::MemberBindStmt(_) => {}
+						MemberComp::MemberFieldNormal(f) => {
+							if let Some(expr) = f.expr() {
+								self.expr(&expr);
+							}
+						}
+						MemberComp::MemberFieldMethod(f) => {
+							self.function(f.params_desc(), f.expr());
+						}
+					}
+				}
+				self.pop_scope();
+				for _ in 0..opened {
+					self.pop_scope();
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use indoc::indoc;
+	use jrsonnet_rowan_parser::parse;
+
+	use super::unused_locals;
+
+	fn unused(code: &str) -> Vec<String> {
+		let (file, errors) = parse(code);
+		assert!(errors.is_empty(), "{errors:?}");
+		unused_locals(&file)
+			.into_iter()
+			.map(|l| {
+				let start = usize::from(l.range.start());
+				let end = usize::from(l.range.end());
+				format!("{}@{start}: {}", &code[start..end], l.message)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn simple() {
+		assert_eq!(unused("local a = 1; 2"), vec!["a@6: unused local: a"]);
+		assert!(unused("local a = 1; a").is_empty());
+	}
+
+	#[test]
+	fn mutually_recursive() {
+		assert!(unused("local a = b, b = 1; a").is_empty());
+		assert_eq!(
+			unused("local f(x) = f(x - 1); 1"),
+			Vec::<String>::new(),
+			"recursive use counts as use",
+		);
+	}
+
+	#[test]
+	fn shadowing() {
+		assert_eq!(
+			unused("local a = 1; local a = 2; a"),
+			vec!["a@6: unused local: a"]
+		);
+		assert_eq!(
+			unused("local a = 1; local f(a) = a; f(a)"),
+			Vec::<String>::new()
+		);
+		assert_eq!(
+			unused("local a = 1; [a for a in [2]]"),
+			vec!["a@6: unused local: a"]
+		);
+	}
+
+	#[test]
+	fn object_locals() {
+		assert_eq!(
+			unused(indoc!(
+				"
+				{
+					local used = 1,
+					local unused = 2,
+					a: used,
+				}"
+			)),
+			vec!["unused@26: unused local: unused"]
+		);
+		assert!(unused("{ local a = 1, assert a == 1 }").is_empty());
+		assert!(unused("{ local a = 1, f(x): a + x }").is_empty());
+		assert_eq!(
+			unused("local k = 'a'; { local k = 'b', [k]: 1 }"),
+			vec!["k@23: unused local: k"],
+			"field names don't see object locals",
+		);
+		assert!(unused("{ local v = 1, [k]: v for k in ['a'] }").is_empty());
+	}
+
+	#[test]
+	fn params_and_comprehensions_are_not_reported() {
+		assert!(unused("function(a, b) a").is_empty());
+		assert!(unused("[1 for x in [1, 2]]").is_empty());
+	}
+}
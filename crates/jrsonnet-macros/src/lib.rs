@@ -396,6 +396,7 @@ fn builtin_inner(attr: BuiltinAttrs, mut fun: ItemFn) -> syn::Result<TokenStream
 				}
 				#[allow(unused_variables)]
 				fn call(&self, ctx: Context, location: CallLocation, args: &dyn ArgsLike) -> Result<Val> {
+					let _profile = ::jrsonnet_evaluator::profile::builtin_call(stringify!(#name));
 					let parsed = parse_builtin_call(ctx.clone(), &PARAMS, args, false)?;
 
 					let result: #result = #name(#(#pass)*);
@@ -764,6 +765,144 @@ fn derive_typed_inner(input: DeriveInput) -> Result<TokenStream> {
 	})
 }
 
+impl TypedField {
+	fn check_layer_attrs(&self, span: proc_macro2::Span) -> Result<()> {
+		if self.attr.flatten || self.attr.add || !self.attr.aliases.is_empty() {
+			return Err(Error::new(
+				span,
+				"flatten, add and alias are not supported by ObjectLayer",
+			));
+		}
+		Ok(())
+	}
+	fn layer_visibility(&self) -> TokenStream {
+		if self.attr.hide {
+			quote! {Visibility::Hidden}
+		} else {
+			quote! {Visibility::Normal}
+		}
+	}
+	fn expand_layer_enum(&self) -> TokenStream {
+		let ident = &self.ident;
+		let name = self.name().expect("not flattened");
+		let visibility = self.layer_visibility();
+		let call = quote! {
+			if handler(#name.into(), #visibility) {
+				return true;
+			}
+		};
+		if self.is_option {
+			quote! {
+				if self.#ident.is_some() {
+					#call
+				}
+			}
+		} else {
+			call
+		}
+	}
+	fn expand_layer_visibility(&self) -> TokenStream {
+		let ident = &self.ident;
+		let name = self.name().expect("not flattened");
+		let visibility = self.layer_visibility();
+		if self.is_option {
+			quote! {
+				#name if self.#ident.is_some() => Some(#visibility),
+			}
+		} else {
+			quote! {
+				#name => Some(#visibility),
+			}
+		}
+	}
+	fn expand_layer_get(&self) -> TokenStream {
+		let ident = &self.ident;
+		let ty = &self.ty;
+		let name = self.name().expect("not flattened");
+		let convert = if self.is_lazy {
+			quote! {|value| <#ty as Typed>::into_lazy_untyped(value).evaluate()}
+		} else {
+			quote! {<#ty as Typed>::into_untyped}
+		};
+		if self.is_option {
+			quote! {
+				#name => self.#ident.clone().map(#convert).transpose(),
+			}
+		} else {
+			quote! {
+				#name => (#convert)(self.#ident.clone()).map(Some),
+			}
+		}
+	}
+}
+
+/// Implements `ObjectLayer` for struct, fields are converted to jsonnet values using `Typed`, only when accessed.
+///
+/// Field types should implement `Clone`. `#[typed(rename = "...")]` and `#[typed(hide)]` attributes are supported,
+/// `Option` fields are only present in object when set.
+#[proc_macro_derive(ObjectLayer, attributes(typed))]
+pub fn derive_object_layer(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = parse_macro_input!(item as DeriveInput);
+
+	match derive_object_layer_inner(input) {
+		Ok(v) => v.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+fn derive_object_layer_inner(input: DeriveInput) -> Result<TokenStream> {
+	let syn::Data::Struct(data) = &input.data else {
+		return Err(Error::new(input.span(), "only structs supported"));
+	};
+
+	let ident = &input.ident;
+	let fields = data
+		.fields
+		.iter()
+		.map(|field| {
+			let parsed = TypedField::parse(field)?;
+			parsed.check_layer_attrs(field.span())?;
+			Ok(parsed)
+		})
+		.collect::<Result<Vec<_>>>()?;
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let enum_fields = fields.iter().map(TypedField::expand_layer_enum);
+	let field_visibility = fields.iter().map(TypedField::expand_layer_visibility);
+	let get = fields.iter().map(TypedField::expand_layer_get);
+
+	Ok(quote! {
+		const _: () = {
+			use ::jrsonnet_evaluator::{
+				typed::Typed,
+				parser::Visibility,
+				error::Result as JrResult,
+				IStr, ObjectLayer, Val,
+			};
+
+			impl #impl_generics ObjectLayer for #ident #ty_generics #where_clause {
+				fn enum_fields(&self, handler: &mut dyn FnMut(IStr, Visibility) -> bool) -> bool {
+					#(#enum_fields)*
+					false
+				}
+				fn field_visibility(&self, name: &str) -> Option<Visibility> {
+					match name {
+						#(#field_visibility)*
+						_ => None,
+					}
+				}
+				fn get(&self, name: &str) -> JrResult<Option<Val>> {
+					match name {
+						#(#get)*
+						_ => Ok(None),
+					}
+				}
+			}
+		};
+	})
+}
+
 struct FormatInput {
 	formatting: LitStr,
 	arguments: Vec<Expr>,
@@ -0,0 +1,82 @@
+//! Measures heap allocations performed by the parser, useful when changing AST representation
+
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	fmt::Write,
+	hint::black_box,
+	sync::atomic::{AtomicUsize, Ordering},
+	time::Instant,
+};
+
+use jrsonnet_interner::IStr;
+use jrsonnet_parser::{parse, ParserSettings, Source};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+		System.alloc(layout)
+	}
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout);
+	}
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+		System.realloc(ptr, layout, new_size)
+	}
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Stdlib-like source, exercising most of the node kinds
+fn corpus(functions: usize) -> String {
+	let mut out = String::from("{\n");
+	for i in 0..functions {
+		writeln!(
+			out,
+			"  fn{i}(arr, keyF=function(x) x, onEmpty=error 'empty array')::
+    local len = std.length(arr);
+    assert std.isArray(arr) : 'expected array, got %s' % std.type(arr);
+    if len == 0 then onEmpty
+    else std.foldl(function(acc, e) acc + [keyF(e) * 2 + {i}], arr[1:len:1], []),
+  obj{i}:: {{ a: self.b + 1, b:: {i}, [std.toString({i})]+: super.c + null, 'str': \"s{i}\\n\" }},
+  comp{i}:: [x * y for x in std.range(0, 10) if x % 2 == 0 for y in [1, 2, 3]],
+  objComp{i}:: {{ [k]: v for k in ['a', 'b'] for v in [1] }},"
+		)
+		.unwrap();
+	}
+	out.push('}');
+	out
+}
+
+fn main() {
+	let code = corpus(500);
+	let settings = ParserSettings {
+		source: Source::new_virtual("<bench>".into(), IStr::empty()),
+	};
+	// Warm up interner and lazy statics
+	black_box(parse(&code, &settings).expect("corpus is valid"));
+
+	let runs = 20;
+	let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+	let bytes_before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+	let start = Instant::now();
+	for _ in 0..runs {
+		black_box(parse(black_box(&code), &settings).expect("corpus is valid"));
+	}
+	let elapsed = start.elapsed();
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+	let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes_before;
+
+	println!("source size:       {} bytes", code.len());
+	println!("time per parse:    {:?}", elapsed / runs);
+	println!("allocations/parse: {}", allocations / runs as usize);
+	println!("bytes/parse:       {}", bytes / runs as usize);
+}
@@ -304,6 +304,29 @@ pub struct SliceDesc {
 	pub step: Option<LocExpr>,
 }
 
+/// Quoting style of string literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringStyle {
+	/// `"hello"`
+	Double,
+	/// `'hello'`
+	Single,
+	/// `@"hello"`
+	VerbatimDouble,
+	/// `@'hello'`
+	VerbatimSingle,
+	/// `|||`, or `|||-` if `chomped`
+	Block { chomped: bool },
+}
+
+/// Original form of string literal, [`Expr::Str`] only contains its decoded value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringLiteral {
+	pub style: StringStyle,
+	/// Literal as written in the source code, including quotes
+	pub raw: IStr,
+}
+
 /// Syntax base
 #[derive(Debug, PartialEq, Trace)]
 pub enum Expr {
@@ -380,6 +403,19 @@ pub struct IndexPart {
 	#[cfg(feature = "exp-null-coaelse")]
 	pub null_coaelse: bool,
 }
+impl IndexPart {
+	/// Constructs index part regardless of enabled features, `null_coaelse` is ignored
+	/// unless `exp-null-coaelse` is enabled
+	pub fn new(value: LocExpr, null_coaelse: bool) -> Self {
+		#[cfg(not(feature = "exp-null-coaelse"))]
+		let _ = null_coaelse;
+		Self {
+			value,
+			#[cfg(feature = "exp-null-coaelse")]
+			null_coaelse,
+		}
+	}
+}
 
 /// file, begin offset, end offset
 #[derive(Clone, PartialEq, Eq, Trace)]
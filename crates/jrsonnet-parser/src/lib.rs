@@ -30,6 +30,13 @@ macro_rules! expr_un {
 	};
 }
 
+/// Element of array destructuring pattern, rest may appear between any items, but only once
+#[cfg_attr(not(feature = "exp-destruct"), allow(dead_code))]
+enum DestructArrayElem {
+	Item(expr::Destruct),
+	Rest(expr::DestructRest),
+}
+
 parser! {
 	grammar jsonnet_parser() for str {
 		use peg::ParseLiteral;
@@ -54,7 +61,16 @@ parser! {
 		/// Sequence of digits
 		rule uint_str() -> &'input str = a:$(digit()+) { a }
 		/// Number in scientific notation format
-		rule number() -> f64 = quiet!{a:$(uint_str() ("." uint_str())? (['e'|'E'] (s:['+'|'-'])? uint_str())?) {? a.parse().map_err(|_| "<number>") }} / expected!("<number>")
+		rule number() -> f64 = radix_number() / quiet!{a:$(uint_str() ("." uint_str())? (['e'|'E'] (s:['+'|'-'])? uint_str())?) {? a.parse().map_err(|_| "<number>") }} / expected!("<number>")
+		/// Integer in hexadecimal (`0x`), octal (`0o`) or binary (`0b`) notation
+		rule radix_number() -> f64
+			= v:quiet!{"0" radix:(['x'|'X'] { 16 } / ['o'|'O'] { 8 } / ['b'|'B'] { 2 }) digits:$((alpha() / digit())+) { (radix, digits) }} {?
+				let (radix, digits) = v;
+				#[cfg(feature = "exp-hex-literals")] return u128::from_str_radix(digits, radix)
+					.map(|v| v as f64)
+					.map_err(|_| "<number>");
+				#[cfg(not(feature = "exp-hex-literals"))] Err("!!!experimental hex literals were not enabled")
+			}
 
 		/// Reserved word followed by any non-alphanumberic
 		rule reserved() = ("assert" / "else" / "error" / "false" / "for" / "function" / "if" / "import" / "importstr" / "importbin" / "in" / "local" / "null" / "tailstrict" / "then" / "self" / "super" / "true") end_of_ident()
@@ -65,14 +81,14 @@ parser! {
 
 		pub rule param(s: &ParserSettings) -> expr::Param = name:destruct(s) expr:(_ "=" _ expr:expr(s){expr})? { expr::Param(name, expr) }
 		pub rule params(s: &ParserSettings) -> expr::ParamsDesc
-			= params:param(s) ** comma() comma()? { expr::ParamsDesc(Rc::new(params)) }
-			/ { expr::ParamsDesc(Rc::new(Vec::new())) }
+			= params:(p:(param(s) ++ comma()) comma()? {p})? { expr::ParamsDesc(Rc::new(params.unwrap_or_default())) }
 
 		pub rule arg(s: &ParserSettings) -> (Option<IStr>, LocExpr)
 			= name:(quiet! { (s:id() _ "=" !['='] _ {s})? } / expected!("<argument name>")) expr:expr(s) {(name, expr)}
 
 		pub rule args(s: &ParserSettings) -> expr::ArgsDesc
-			= args:arg(s)**comma() comma()? {?
+			= args:(a:(arg(s) ++ comma()) comma()? {a})? {?
+				let args = args.unwrap_or_default();
 				let unnamed_count = args.iter().take_while(|(n, _)| n.is_none()).count();
 				let mut unnamed = Vec::with_capacity(unnamed_count);
 				let mut named = Vec::with_capacity(args.len() - unnamed_count);
@@ -95,29 +111,37 @@ parser! {
 			= "..." into:(_ into:id() {into})? {if let Some(into) = into {
 				expr::DestructRest::Keep(into)
 			} else {expr::DestructRest::Drop}}
+		rule destruct_array_elem(s: &ParserSettings) -> DestructArrayElem
+			= rest:destruct_rest() {DestructArrayElem::Rest(rest)}
+			/ item:destruct(s) {DestructArrayElem::Item(item)}
 		pub rule destruct_array(s: &ParserSettings) -> expr::Destruct
-			= "[" _ start:destruct(s)**comma() rest:(
-				comma() _ rest:destruct_rest()? end:(
-					comma() end:destruct(s)**comma() (_ comma())? {end}
-					/ comma()? {Vec::new()}
-				) {(rest, end)}
-				/ comma()? {(None, Vec::new())}
-			) _ "]" {?
-				#[cfg(feature = "exp-destruct")] return Ok(expr::Destruct::Array {
-					start,
-					rest: rest.0,
-					end: rest.1,
-				});
+			= "[" _ elems:(e:(destruct_array_elem(s) ++ comma()) comma()? {e})? _ "]" {?
+				#[cfg(feature = "exp-destruct")] {
+					let mut start = Vec::new();
+					let mut rest = None;
+					let mut end = Vec::new();
+					for elem in elems.unwrap_or_default() {
+						match elem {
+							DestructArrayElem::Item(item) if rest.is_none() => start.push(item),
+							DestructArrayElem::Item(item) => end.push(item),
+							DestructArrayElem::Rest(_) if rest.is_some() => return Err("<single rest>"),
+							DestructArrayElem::Rest(r) => rest = Some(r),
+						}
+					}
+					return Ok(expr::Destruct::Array { start, rest, end });
+				}
 				#[cfg(not(feature = "exp-destruct"))] Err("!!!experimental destructuring was not enabled")
 			}
+		rule destruct_object_field(s: &ParserSettings) -> (IStr, Option<expr::Destruct>, Option<LocExpr>)
+			= name:id() into:(_ ":" _ into:destruct(s) {into})? default:(_ "=" _ v:expr(s) {v})? {(name, into, default)}
 		pub rule destruct_object(s: &ParserSettings) -> expr::Destruct
 			= "{" _
-				fields:(name:id() into:(_ ":" _ into:destruct(s) {into})? default:(_ "=" _ v:expr(s) {v})? {(name, into, default)})**comma()
-				rest:(
-					comma() rest:destruct_rest()? {rest}
-					/ comma()? {None}
-				)
+				body:(
+					fields:(destruct_object_field(s) ++ comma()) rest:(comma() rest:destruct_rest() {rest})? comma()? {(fields, rest)}
+					/ rest:destruct_rest() comma()? {(Vec::new(), Some(rest))}
+				)?
 			_ "}" {?
+				let (fields, rest) = body.unwrap_or_default();
 				#[cfg(feature = "exp-destruct")] return Ok(expr::Destruct::Object {
 					fields,
 					rest,
@@ -142,11 +166,15 @@ parser! {
 
 		pub rule whole_line() -> &'input str
 			= str:$((!['\n'][_])* "\n") {str}
+		/// Every non-empty line of the block should start with exactly the same whitespace, as the first one,
+		/// mixing tabs and spaces differently is not allowed
+		rule block_prefix(prefix: &str)
+			= p:$([' ' | '\t']*<{prefix.len()}>) {? if p == prefix { Ok(()) } else { Err("<block indentation>") } }
 		pub rule string_block() -> String
 			= "|||" chomped:"-"? (!['\n']single_whitespace())* "\n"
 			empty_lines:$(['\n']*)
-			prefix:[' ' | '\t']+ first_line:whole_line()
-			lines:("\n" {"\n"} / [' ' | '\t']*<{prefix.len()}> s:whole_line() {s})*
+			prefix:$([' ' | '\t']+) first_line:whole_line()
+			lines:("\n" {"\n"} / block_prefix(prefix) s:whole_line() {s})*
 			[' ' | '\t']*<, {prefix.len() - 1}> "|||"
 			{
 				let mut l = empty_lines.to_owned();
@@ -215,7 +243,7 @@ parser! {
 					compspecs,
 				})
 			}
-			/ members:(member(s) ** comma()) comma()? {expr::ObjBody::MemberList(members)}
+			/ members:(m:(member(s) ++ comma()) comma()? {m})? {expr::ObjBody::MemberList(members.unwrap_or_default())}
 		pub rule ifspec(s: &ParserSettings) -> IfSpecData
 			= keyword("if") _ expr:expr(s) {IfSpecData(expr)}
 		pub rule forspec(s: &ParserSettings) -> ForSpecData
@@ -223,13 +251,13 @@ parser! {
 		pub rule compspec(s: &ParserSettings) -> Vec<expr::CompSpec>
 			= s:(i:ifspec(s) { expr::CompSpec::IfSpec(i) } / f:forspec(s) {expr::CompSpec::ForSpec(f)} ) ** _ {s}
 		pub rule local_expr(s: &ParserSettings) -> Expr
-			= keyword("local") _ binds:bind(s) ** comma() (_ ",")? _ ";" _ expr:expr(s) { Expr::LocalExpr(binds, expr) }
+			= keyword("local") _ binds:bind(s) ++ comma() comma()? _ ";" _ expr:expr(s) { Expr::LocalExpr(binds, expr) }
 		pub rule string_expr(s: &ParserSettings) -> Expr
 			= s:string() {Expr::Str(s.into())}
 		pub rule obj_expr(s: &ParserSettings) -> Expr
 			= "{" _ body:objinside(s) _ "}" {Expr::Obj(body)}
 		pub rule array_expr(s: &ParserSettings) -> Expr
-			= "[" _ elems:(expr(s) ** comma()) _ comma()? "]" {Expr::Arr(elems)}
+			= "[" _ elems:(e:(expr(s) ++ comma()) comma()? {e})? _ "]" {Expr::Arr(elems.unwrap_or_default())}
 		pub rule array_comp_expr(s: &ParserSettings) -> Expr
 			= "[" _ expr:expr(s) _ comma()? _ forspec:forspec(s) _ others:(others: compspec(s) _ {others})? "]" {
 				let mut specs = vec![CompSpec::ForSpec(forspec)];
@@ -376,6 +404,41 @@ pub type ParseError = peg::error::ParseError<peg::str::LineCol>;
 pub fn parse(str: &str, settings: &ParserSettings) -> Result<LocExpr, ParseError> {
 	jsonnet_parser::jsonnet(str, settings)
 }
+/// Recovers original form of string literal expression from its span.
+///
+/// Spans always cover the whole literal, so parser doesn't need to keep raw strings around.
+/// Returns `None` for expressions, which are not string literals (field names in `a.b` are [`Expr::Str`] too),
+/// or if span source doesn't contain the literal (e.g for [`string_to_expr`] results).
+pub fn string_literal(expr: &LocExpr) -> Option<StringLiteral> {
+	let Expr::Str(value) = expr.expr() else {
+		return None;
+	};
+	let span = expr.span();
+	let raw = span.0.code().get(span.1 as usize..span.2 as usize)?;
+	let style = if raw.starts_with('"') {
+		StringStyle::Double
+	} else if raw.starts_with('\'') {
+		StringStyle::Single
+	} else if raw.starts_with("@\"") {
+		StringStyle::VerbatimDouble
+	} else if raw.starts_with("@'") {
+		StringStyle::VerbatimSingle
+	} else if raw.starts_with("|||") {
+		StringStyle::Block {
+			chomped: raw.starts_with("|||-"),
+		}
+	} else {
+		return None;
+	};
+	// Source might not be the one, which was parsed
+	if jsonnet_parser::string(raw).ok()? != **value {
+		return None;
+	}
+	Some(StringLiteral {
+		style,
+		raw: raw.into(),
+	})
+}
 /// Used for importstr values
 pub fn string_to_expr(str: IStr, settings: &ParserSettings) -> LocExpr {
 	let len = str.len();
@@ -387,7 +450,7 @@ pub mod tests {
 	use jrsonnet_interner::IStr;
 	use BinaryOpType::*;
 
-	use super::{expr::*, parse};
+	use super::{expr::*, parse, string_literal};
 	use crate::{source::Source, ParserSettings};
 
 	macro_rules! parse {
@@ -435,6 +498,32 @@ pub mod tests {
 		);
 	}
 
+	#[test]
+	fn multiline_string_leading_blank_lines() {
+		assert_eq!(
+			parse!("|||\n\n\n  a\n\n  b\n|||"),
+			el!(Expr::Str("\n\na\n\nb\n".into()), 0, 18),
+		);
+	}
+
+	#[test]
+	fn multiline_string_keeps_extra_indentation() {
+		assert_eq!(
+			parse!("|||\n  a\n      b\n   \tc\n|||"),
+			el!(Expr::Str("a\n    b\n \tc\n".into()), 0, 25),
+		);
+	}
+
+	#[test]
+	fn multiline_string_prefix_mismatch() {
+		let settings = ParserSettings {
+			source: Source::new_virtual("<test>".into(), IStr::empty()),
+		};
+		// Prefix is compared literally, tab is not the same as a space
+		assert!(parse("|||\n\t a\n \tb\n|||", &settings).is_err());
+		assert!(parse("|||\n  a\n\tb\n|||", &settings).is_err());
+	}
+
 	#[test]
 	fn slice() {
 		parse!("a[1:]");
@@ -444,6 +533,37 @@ pub mod tests {
 		parse!("str[:len - 1]");
 	}
 
+	#[test]
+	#[cfg(feature = "exp-hex-literals")]
+	fn radix_numbers() {
+		assert_eq!(parse!("0xFF"), el!(Expr::Num(255.0), 0, 4));
+		assert_eq!(parse!("0Xff"), el!(Expr::Num(255.0), 0, 4));
+		assert_eq!(parse!("0o17"), el!(Expr::Num(15.0), 0, 4));
+		assert_eq!(parse!("0b101"), el!(Expr::Num(5.0), 0, 5));
+		assert_eq!(parse!("0"), el!(Expr::Num(0.0), 0, 1));
+		assert_eq!(parse!("0.5"), el!(Expr::Num(0.5), 0, 3));
+
+		let settings = ParserSettings {
+			source: Source::new_virtual("<test>".into(), IStr::empty()),
+		};
+		assert!(parse("0xFG", &settings).is_err());
+		assert!(parse("0o8", &settings).is_err());
+		assert!(parse("0b", &settings).is_err());
+	}
+
+	#[test]
+	#[cfg(not(feature = "exp-hex-literals"))]
+	fn radix_numbers_disabled() {
+		let settings = ParserSettings {
+			source: Source::new_virtual("<test>".into(), IStr::empty()),
+		};
+		let err = parse("0x10", &settings).unwrap_err();
+		assert!(err
+			.expected
+			.tokens()
+			.any(|t| t == "!!!experimental hex literals were not enabled"));
+	}
+
 	#[test]
 	fn string_escaping() {
 		assert_eq!(
@@ -473,6 +593,37 @@ pub mod tests {
 		);
 	}
 
+	fn parse_with_source(code: &str) -> LocExpr {
+		parse(
+			code,
+			&ParserSettings {
+				source: Source::new_virtual("<test>".into(), code.into()),
+			},
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn string_literal_original_form() {
+		let code = r#"@"Hello\n""World""""#;
+		let literal = string_literal(&parse_with_source(code)).expect("string literal");
+		assert_eq!(literal.style, StringStyle::VerbatimDouble);
+		assert_eq!(&*literal.raw, code);
+
+		let code = "|||-\n  a\n|||";
+		let literal = string_literal(&parse_with_source(code)).expect("string literal");
+		assert_eq!(literal.style, StringStyle::Block { chomped: true });
+		assert_eq!(&*literal.raw, code);
+
+		let expr = parse_with_source("a.b");
+		let Expr::Index { parts, .. } = expr.expr() else {
+			panic!("index expected");
+		};
+		assert_eq!(string_literal(&parts[0].value), None);
+		// Source code is unknown
+		assert_eq!(string_literal(&parse!("'a'")), None);
+	}
+
 	#[test]
 	fn imports() {
 		assert_eq!(
@@ -738,6 +889,73 @@ pub mod tests {
 		parse!("local x(foo = 'foo', bar) = null; null");
 	}
 
+	fn parse_fails(code: &str) -> bool {
+		parse(
+			code,
+			&ParserSettings {
+				source: Source::new_virtual("<test>".into(), IStr::empty()),
+			},
+		)
+		.is_err()
+	}
+
+	#[test]
+	fn trailing_comma() {
+		parse!("f(a, b,)");
+		parse!("f(a, b=1,)");
+		parse!("local f(a, b=1,) = a; f");
+		parse!("function(a, b,) a");
+		parse!("[1, 2,]");
+		parse!("{a: 1, local b = 2, assert true,}");
+		parse!("{f(a,): a}");
+		parse!("local a = 1, b = 2,; a");
+	}
+
+	#[test]
+	fn double_or_lone_comma() {
+		for code in [
+			"f(a, b,,)",
+			"f(,)",
+			"local f(a,,) = a; f",
+			"function(,) 1",
+			"[1,,]",
+			"[1,,2]",
+			"[,]",
+			"{a: 1,,}",
+			"{,}",
+			"local a = 1,,; a",
+			"local ,; 1",
+		] {
+			assert!(parse_fails(code), "{code} should not parse");
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "exp-destruct")]
+	fn destruct_trailing_comma() {
+		parse!("local [a, b,] = c; a");
+		parse!("local [a, ...,] = c; a");
+		parse!("local [...r,] = c; r");
+		parse!("local [a, ...r, b,] = c; a");
+		parse!("local {a, b,} = c; a");
+		parse!("local {a, ...,} = c; a");
+		parse!("local {...r,} = c; r");
+		parse!("local f([a, b,],) = a; f");
+		for code in [
+			"local [a,,] = c; a",
+			"local [a,, b] = c; a",
+			"local [a, ...,,] = c; a",
+			"local [a, ..., ...] = c; a",
+			"local [,] = c; 1",
+			"local {a,,} = c; a",
+			"local {a, ...,,} = c; a",
+			"local {,} = c; 1",
+			"local {, ...} = c; 1",
+		] {
+			assert!(parse_fails(code), "{code} should not parse");
+		}
+	}
+
 	#[test]
 	fn add_location_info_to_all_sub_expressions() {
 		use Expr::*;
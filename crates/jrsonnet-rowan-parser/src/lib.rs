@@ -11,6 +11,7 @@ mod event;
 mod generated;
 mod language;
 mod lex;
+pub mod lower;
 mod marker;
 mod parser;
 mod precedence;
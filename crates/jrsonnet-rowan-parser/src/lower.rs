@@ -0,0 +1,575 @@
+//! Conversion of (possibly erroneous) lossless syntax tree to the `jrsonnet-parser` AST, which is used by evaluator
+//!
+//! Syntax tree produced by [`crate::parse`] is always complete, even for broken input: missing nodes are reported
+//! as `None` by accessors, and unparsed input is wrapped into `ERROR` nodes. Every missing expression is replaced
+//! with `error "syntax error: ..."` placeholder, so the resulting AST has the same shape, as the one of the fixed
+//! code would have.
+//!
+//! Evaluation of such AST will fail once any of the placeholders is reached, but scope analysis (i.e which locals
+//! are visible at some position, which is needed for completion) works for the whole file.
+
+use std::rc::Rc;
+
+use jrsonnet_parser::{
+	ArgsDesc, AssertStmt, BinaryOpType, BindSpec, CompSpec, Destruct, Expr, FieldMember, FieldName,
+	ForSpecData, IStr, IfSpecData, IndexPart, LiteralType, LocExpr, Member, ObjBody, ObjComp,
+	Param, ParamsDesc, ParserSettings, SliceDesc, Source, Span, UnaryOpType, Visibility,
+};
+
+use crate::{
+	nodes::{
+		self, BinaryOperatorKind, ImportKindKind, LiteralKind, UnaryOperatorKind, VisibilityKind,
+	},
+	AstNode, AstToken, SyntaxNode,
+};
+
+/// Name used in place of missing bindings and parameters, it can't be referenced from the code
+const MISSING_NAME: &str = "<missing>";
+
+/// Converts syntax tree to the evaluator AST, replacing missing and erroneous parts with placeholders,
+/// see [module docs](self) for details.
+///
+/// `source` should contain the same code, which was used to produce `file`.
+pub fn lower(file: &nodes::SourceFile, source: Source) -> LocExpr {
+	let lower = Lower { source };
+	lower.expr_opt(file.syntax(), file.expr(), "expression")
+}
+
+struct Lower {
+	source: Source,
+}
+
+impl Lower {
+	fn span(&self, node: &SyntaxNode) -> Span {
+		let range = node.text_range();
+		Span(
+			self.source.clone(),
+			range.start().into(),
+			range.end().into(),
+		)
+	}
+	fn missing(&self, node: &SyntaxNode, what: &str) -> LocExpr {
+		let span = self.span(node);
+		LocExpr::new(
+			Expr::ErrorStmt(LocExpr::new(
+				Expr::Str(format!("syntax error: missing {what}").into()),
+				span.clone(),
+			)),
+			span,
+		)
+	}
+	fn expr_opt(&self, parent: &SyntaxNode, expr: Option<nodes::Expr>, what: &str) -> LocExpr {
+		expr.map_or_else(|| self.missing(parent, what), |expr| self.expr(&expr))
+	}
+	fn name(name: Option<nodes::Name>) -> IStr {
+		name.and_then(|n| n.ident_lit())
+			.map_or_else(|| MISSING_NAME.into(), |n| n.text().into())
+	}
+
+	/// Reuses `jrsonnet-parser` to decode string and number literals, so escapes and text blocks are handled
+	/// exactly the same way
+	fn literal(&self, text: &str) -> Option<Expr> {
+		let parsed = jrsonnet_parser::parse(
+			text,
+			&ParserSettings {
+				source: self.source.clone(),
+			},
+		)
+		.ok()?;
+		match parsed.expr() {
+			Expr::Str(s) => Some(Expr::Str(s.clone())),
+			Expr::Num(n) => Some(Expr::Num(*n)),
+			_ => None,
+		}
+	}
+	fn text(&self, node: &SyntaxNode, text: Option<nodes::Text>) -> LocExpr {
+		text.and_then(|t| self.literal(t.text())).map_or_else(
+			|| self.missing(node, "string"),
+			|expr| LocExpr::new(expr, self.span(node)),
+		)
+	}
+
+	fn expr(&self, expr: &nodes::Expr) -> LocExpr {
+		let node = expr.syntax();
+		let end = node.text_range().end().into();
+		let mut out = expr.expr_base().map_or_else(
+			|| self.missing(node, "expression"),
+			|base| self.expr_base(&base),
+		);
+		let start = out.span().1;
+		for suffix in expr.suffixs() {
+			let span = Span(
+				self.source.clone(),
+				start,
+				suffix.syntax().text_range().end().into(),
+			);
+			out = LocExpr::new(self.suffix(out, &suffix), span);
+		}
+		let stmts = expr.stmts().collect::<Vec<_>>();
+		for stmt in stmts.into_iter().rev() {
+			let span = Span(
+				self.source.clone(),
+				stmt.syntax().text_range().start().into(),
+				end,
+			);
+			let expr = match stmt {
+				nodes::Stmt::StmtLocal(l) => {
+					Expr::LocalExpr(l.binds().map(|b| self.bind(&b)).collect(), out)
+				}
+				nodes::Stmt::StmtAssert(a) => {
+					Expr::AssertExpr(self.assertion(a.syntax(), a.assertion()), out)
+				}
+			};
+			out = LocExpr::new(expr, span);
+		}
+		out
+	}
+
+	fn suffix(&self, base: LocExpr, suffix: &nodes::Suffix) -> Expr {
+		match suffix {
+			nodes::Suffix::SuffixIndex(i) => Expr::Index {
+				indexable: base,
+				// Field may be cfg'd out even if this crate's feature is disabled, due to feature unification
+				parts: vec![IndexPart::new(
+					i.index().map_or_else(
+						|| self.missing(i.syntax(), "field name"),
+						|name| {
+							LocExpr::new(
+								Expr::Str(Self::name(Some(name.clone()))),
+								self.span(name.syntax()),
+							)
+						},
+					),
+					i.question_mark_token().is_some(),
+				)],
+			},
+			nodes::Suffix::SuffixIndexExpr(i) => Expr::Index {
+				indexable: base,
+				parts: vec![IndexPart::new(
+					self.expr_opt(i.syntax(), i.index(), "index"),
+					i.question_mark_token().is_some(),
+				)],
+			},
+			nodes::Suffix::SuffixSlice(s) => {
+				let desc = s.slice_desc();
+				Expr::Slice(
+					base,
+					SliceDesc {
+						start: desc
+							.as_ref()
+							.and_then(nodes::SliceDesc::from)
+							.map(|e| self.expr(&e)),
+						end: desc
+							.as_ref()
+							.and_then(nodes::SliceDesc::end)
+							.and_then(|e| e.expr())
+							.map(|e| self.expr(&e)),
+						step: desc
+							.as_ref()
+							.and_then(nodes::SliceDesc::step)
+							.and_then(|e| e.expr())
+							.map(|e| self.expr(&e)),
+					},
+				)
+			}
+			nodes::Suffix::SuffixApply(a) => {
+				let mut unnamed = Vec::new();
+				let mut named = Vec::new();
+				for arg in a.args_desc().iter().flat_map(nodes::ArgsDesc::args) {
+					let value = self.expr_opt(arg.syntax(), arg.expr(), "argument");
+					if let Some(name) = arg.name() {
+						named.push((Self::name(Some(name)), value));
+					} else {
+						unnamed.push(value);
+					}
+				}
+				Expr::Apply(
+					base,
+					ArgsDesc::new(unnamed, named),
+					a.tailstrict_kw_token().is_some(),
+				)
+			}
+		}
+	}
+
+	#[allow(clippy::too_many_lines)]
+	fn expr_base(&self, base: &nodes::ExprBase) -> LocExpr {
+		let node = base.syntax();
+		let expr = match base {
+			nodes::ExprBase::ExprBinary(b) => {
+				let op = match b.binary_operator().map(|o| o.kind()) {
+					Some(BinaryOperatorKind::Or) => BinaryOpType::Or,
+					#[cfg(feature = "exp-null-coaelse")]
+					Some(BinaryOperatorKind::NullCoaelse) => BinaryOpType::NullCoaelse,
+					Some(BinaryOperatorKind::And) => BinaryOpType::And,
+					Some(BinaryOperatorKind::BitOr) => BinaryOpType::BitOr,
+					Some(BinaryOperatorKind::BitXor) => BinaryOpType::BitXor,
+					Some(BinaryOperatorKind::BitAnd) => BinaryOpType::BitAnd,
+					Some(BinaryOperatorKind::Eq) => BinaryOpType::Eq,
+					Some(BinaryOperatorKind::Ne) => BinaryOpType::Neq,
+					Some(BinaryOperatorKind::Lt) => BinaryOpType::Lt,
+					Some(BinaryOperatorKind::Gt) => BinaryOpType::Gt,
+					Some(BinaryOperatorKind::Le) => BinaryOpType::Lte,
+					Some(BinaryOperatorKind::Ge) => BinaryOpType::Gte,
+					Some(BinaryOperatorKind::InKw) => BinaryOpType::In,
+					Some(BinaryOperatorKind::Lhs) => BinaryOpType::Lhs,
+					Some(BinaryOperatorKind::Rhs) => BinaryOpType::Rhs,
+					Some(BinaryOperatorKind::Plus) => BinaryOpType::Add,
+					Some(BinaryOperatorKind::Minus) => BinaryOpType::Sub,
+					Some(BinaryOperatorKind::Mul) => BinaryOpType::Mul,
+					Some(BinaryOperatorKind::Div) => BinaryOpType::Div,
+					Some(BinaryOperatorKind::Modulo) => BinaryOpType::Mod,
+					_ => return self.missing(node, "operator"),
+				};
+				Expr::BinaryOp(
+					self.expr_opt(node, b.lhs_work(), "expression"),
+					op,
+					self.expr_opt(node, b.rhs_work(), "expression"),
+				)
+			}
+			nodes::ExprBase::ExprUnary(u) => {
+				let op = match u.unary_operator().map(|o| o.kind()) {
+					Some(UnaryOperatorKind::Minus) => UnaryOpType::Minus,
+					Some(UnaryOperatorKind::Not) => UnaryOpType::Not,
+					Some(UnaryOperatorKind::BitNot) => UnaryOpType::BitNot,
+					None => return self.missing(node, "operator"),
+				};
+				Expr::UnaryOp(op, self.expr_opt(node, u.rhs(), "expression"))
+			}
+			nodes::ExprBase::ExprObjExtend(e) => {
+				let body = e
+					.rhs_work()
+					.filter(|rhs| rhs.stmts().next().is_none() && rhs.suffixs().next().is_none())
+					.and_then(|rhs| match rhs.expr_base()? {
+						nodes::ExprBase::ExprObject(o) => self.obj_body(o.syntax(), o.obj_body()),
+						_ => None,
+					});
+				let Some(body) = body else {
+					return self.missing(node, "object");
+				};
+				Expr::ObjExtend(self.expr_opt(node, e.lhs_work(), "expression"), body)
+			}
+			nodes::ExprBase::ExprParened(p) => {
+				Expr::Parened(self.expr_opt(node, p.expr(), "expression"))
+			}
+			nodes::ExprBase::ExprString(s) => return self.text(node, s.text()),
+			nodes::ExprBase::ExprNumber(n) => {
+				match n.number().and_then(|n| self.literal(n.text())) {
+					Some(num) => num,
+					None => return self.missing(node, "number"),
+				}
+			}
+			nodes::ExprBase::ExprLiteral(l) => Expr::Literal(match l.literal().map(|l| l.kind()) {
+				Some(LiteralKind::NullKw) => LiteralType::Null,
+				Some(LiteralKind::TrueKw) => LiteralType::True,
+				Some(LiteralKind::FalseKw) => LiteralType::False,
+				Some(LiteralKind::SelfKw) => LiteralType::This,
+				Some(LiteralKind::Dollar) => LiteralType::Dollar,
+				Some(LiteralKind::SuperKw) => LiteralType::Super,
+				None => return self.missing(node, "literal"),
+			}),
+			nodes::ExprBase::ExprArray(a) => Expr::Arr(a.exprs().map(|e| self.expr(&e)).collect()),
+			nodes::ExprBase::ExprObject(o) => match self.obj_body(node, o.obj_body()) {
+				Some(body) => Expr::Obj(body),
+				None => return self.missing(node, "object"),
+			},
+			nodes::ExprBase::ExprArrayComp(a) => Expr::ArrComp(
+				self.expr_opt(node, a.expr(), "expression"),
+				a.comp_specs().map(|s| self.comp_spec(&s)).collect(),
+			),
+			nodes::ExprBase::ExprImport(i) => {
+				let path = self.text(node, i.text());
+				match i.import_kind().map(|k| k.kind()) {
+					Some(ImportKindKind::ImportKw) => Expr::Import(path),
+					Some(ImportKindKind::ImportstrKw) => Expr::ImportStr(path),
+					Some(ImportKindKind::ImportbinKw) => Expr::ImportBin(path),
+					None => return self.missing(node, "import"),
+				}
+			}
+			nodes::ExprBase::ExprVar(v) => Expr::Var(Self::name(v.name())),
+			nodes::ExprBase::ExprIfThenElse(i) => Expr::IfElse {
+				cond: IfSpecData(self.expr_opt(node, i.cond(), "condition")),
+				cond_then: self.expr_opt(node, i.then().and_then(|t| t.expr()), "expression"),
+				cond_else: i
+					.else_()
+					.map(|e| self.expr_opt(e.syntax(), e.expr(), "expression")),
+			},
+			nodes::ExprBase::ExprFunction(f) => Expr::Function(
+				self.params(f.params_desc()),
+				self.expr_opt(node, f.expr(), "function body"),
+			),
+			nodes::ExprBase::ExprError(e) => {
+				Expr::ErrorStmt(self.expr_opt(node, e.expr(), "expression"))
+			}
+		};
+		LocExpr::new(expr, self.span(node))
+	}
+
+	fn assertion(&self, parent: &SyntaxNode, assertion: Option<nodes::Assertion>) -> AssertStmt {
+		let Some(assertion) = assertion else {
+			return AssertStmt(self.missing(parent, "assertion"), None);
+		};
+		AssertStmt(
+			self.expr_opt(assertion.syntax(), assertion.condition(), "condition"),
+			assertion.message().map(|m| self.expr(&m)),
+		)
+	}
+
+	fn params(&self, params: Option<nodes::ParamsDesc>) -> ParamsDesc {
+		ParamsDesc(Rc::new(
+			params
+				.iter()
+				.flat_map(nodes::ParamsDesc::params)
+				.map(|p| Param(self.destruct(p.destruct()), p.expr().map(|e| self.expr(&e))))
+				.collect(),
+		))
+	}
+
+	fn bind(&self, bind: &nodes::Bind) -> BindSpec {
+		match bind {
+			nodes::Bind::BindDestruct(d) => BindSpec::Field {
+				into: self.destruct(d.into()),
+				value: self.expr_opt(d.syntax(), d.value(), "value"),
+			},
+			nodes::Bind::BindFunction(f) => BindSpec::Function {
+				name: Self::name(f.name()),
+				params: self.params(f.params()),
+				value: self.expr_opt(f.syntax(), f.value(), "function body"),
+			},
+		}
+	}
+
+	#[cfg_attr(not(feature = "exp-destruct"), allow(clippy::unused_self))]
+	fn destruct(&self, destruct: Option<nodes::Destruct>) -> Destruct {
+		match destruct {
+			Some(nodes::Destruct::DestructFull(f)) => Destruct::Full(Self::name(f.name())),
+			#[cfg(feature = "exp-destruct")]
+			Some(nodes::Destruct::DestructSkip(_)) => Destruct::Skip,
+			#[cfg(feature = "exp-destruct")]
+			Some(nodes::Destruct::DestructArray(a)) => {
+				let mut start = Vec::new();
+				let mut rest = None;
+				let mut end = Vec::new();
+				for part in a.destruct_array_parts() {
+					match part {
+						nodes::DestructArrayPart::DestructArrayElement(e) => {
+							let item = self.destruct(e.destruct());
+							if rest.is_none() {
+								start.push(item);
+							} else {
+								end.push(item);
+							}
+						}
+						nodes::DestructArrayPart::DestructRest(r) => {
+							rest = Some(Self::destruct_rest(&r));
+						}
+					}
+				}
+				Destruct::Array { start, rest, end }
+			}
+			#[cfg(feature = "exp-destruct")]
+			Some(nodes::Destruct::DestructObject(o)) => Destruct::Object {
+				fields: o
+					.destruct_object_fields()
+					.map(|f| {
+						(
+							Self::name(f.field()),
+							f.destruct().map(|d| self.destruct(Some(d))),
+							f.expr().map(|e| self.expr(&e)),
+						)
+					})
+					.collect(),
+				rest: o.destruct_rest().map(|r| Self::destruct_rest(&r)),
+			},
+			// Destructuring is not enabled, or the binding is missing
+			#[allow(unreachable_patterns)]
+			_ => Destruct::Full(MISSING_NAME.into()),
+		}
+	}
+	#[cfg(feature = "exp-destruct")]
+	fn destruct_rest(rest: &nodes::DestructRest) -> jrsonnet_parser::DestructRest {
+		rest.into()
+			.map_or(jrsonnet_parser::DestructRest::Drop, |name| {
+				jrsonnet_parser::DestructRest::Keep(Self::name(Some(name)))
+			})
+	}
+
+	fn comp_spec(&self, spec: &nodes::CompSpec) -> CompSpec {
+		match spec {
+			nodes::CompSpec::ForSpec(f) => CompSpec::ForSpec(ForSpecData(
+				self.destruct(f.bind()),
+				self.expr_opt(f.syntax(), f.expr(), "expression"),
+			)),
+			nodes::CompSpec::IfSpec(i) => {
+				CompSpec::IfSpec(IfSpecData(self.expr_opt(i.syntax(), i.expr(), "condition")))
+			}
+		}
+	}
+
+	fn field_name(&self, parent: &SyntaxNode, name: Option<nodes::FieldName>) -> FieldName {
+		match name {
+			Some(nodes::FieldName::FieldNameFixed(f)) => {
+				if let Some(id) = f.id() {
+					return FieldName::Fixed(Self::name(Some(id)));
+				}
+				let text = self.text(f.syntax(), f.text());
+				match text.expr() {
+					Expr::Str(s) => FieldName::Fixed(s.clone()),
+					_ => FieldName::Dyn(text),
+				}
+			}
+			Some(nodes::FieldName::FieldNameDynamic(d)) => {
+				FieldName::Dyn(self.expr_opt(d.syntax(), d.expr(), "field name"))
+			}
+			None => FieldName::Dyn(self.missing(parent, "field name")),
+		}
+	}
+	fn visibility(visibility: Option<nodes::Visibility>) -> Visibility {
+		match visibility.map(|v| v.kind()) {
+			Some(VisibilityKind::Coloncoloncolon) => Visibility::Unhide,
+			Some(VisibilityKind::Coloncolon) => Visibility::Hidden,
+			Some(VisibilityKind::Colon) | None => Visibility::Normal,
+		}
+	}
+	fn field_normal(&self, f: &nodes::MemberFieldNormal) -> FieldMember {
+		FieldMember {
+			name: self.field_name(f.syntax(), f.field_name()),
+			plus: f.plus_token().is_some(),
+			params: None,
+			visibility: Self::visibility(f.visibility()),
+			value: self.expr_opt(f.syntax(), f.expr(), "field value"),
+		}
+	}
+	fn field_method(&self, f: &nodes::MemberFieldMethod) -> FieldMember {
+		FieldMember {
+			name: self.field_name(f.syntax(), f.field_name()),
+			plus: false,
+			params: Some(self.params(f.params_desc())),
+			visibility: Self::visibility(f.visibility()),
+			value: self.expr_opt(f.syntax(), f.expr(), "method body"),
+		}
+	}
+	fn obj_local(&self, parent: &SyntaxNode, local: Option<nodes::ObjLocal>) -> BindSpec {
+		local.and_then(|l| l.bind()).map_or_else(
+			|| BindSpec::Field {
+				into: Destruct::Full(MISSING_NAME.into()),
+				value: self.missing(parent, "local"),
+			},
+			|bind| self.bind(&bind),
+		)
+	}
+
+	/// Returns `None` for object comprehensions without a field
+	fn obj_body(&self, parent: &SyntaxNode, body: Option<nodes::ObjBody>) -> Option<ObjBody> {
+		Some(match body {
+			Some(nodes::ObjBody::ObjBodyMemberList(l)) => ObjBody::MemberList(
+				l.members()
+					.map(|m| match m {
+						nodes::Member::MemberBindStmt(b) => {
+							Member::BindStmt(self.obj_local(b.syntax(), b.obj_local()))
+						}
+						nodes::Member::MemberAssertStmt(a) => {
+							Member::AssertStmt(self.assertion(a.syntax(), a.assertion()))
+						}
+						nodes::Member::MemberFieldNormal(f) => Member::Field(self.field_normal(&f)),
+						nodes::Member::MemberFieldMethod(f) => Member::Field(self.field_method(&f)),
+					})
+					.collect(),
+			),
+			Some(nodes::ObjBody::ObjBodyComp(c)) => {
+				let mut pre_locals = Vec::new();
+				let mut field = None;
+				let mut post_locals = Vec::new();
+				for member in c.member_comps() {
+					match member {
+						nodes::MemberComp::MemberBindStmt(b) => {
+							let local = self.obj_local(b.syntax(), b.obj_local());
+							if field.is_none() {
+								pre_locals.push(local);
+							} else {
+								post_locals.push(local);
+							}
+						}
+						// Only a single field is allowed, others are reported by parser
+						nodes::MemberComp::MemberFieldNormal(f) if field.is_none() => {
+							field = Some(self.field_normal(&f));
+						}
+						nodes::MemberComp::MemberFieldMethod(f) if field.is_none() => {
+							field = Some(self.field_method(&f));
+						}
+						nodes::MemberComp::MemberFieldNormal(_)
+						| nodes::MemberComp::MemberFieldMethod(_) => {}
+					}
+				}
+				ObjBody::ObjComp(ObjComp {
+					pre_locals,
+					field: field?,
+					post_locals,
+					compspecs: c.comp_specs().map(|s| self.comp_spec(&s)).collect(),
+				})
+			}
+			None => ObjBody::MemberList(vec![Member::AssertStmt(AssertStmt(
+				self.missing(parent, "object body"),
+				None,
+			))]),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use jrsonnet_parser::{
+		Expr, FieldName, LocExpr, Member, ObjBody, Source, SourcePath, SourceVirtual,
+	};
+
+	use super::lower;
+	use crate::parse;
+
+	fn lower_code(code: &str) -> (LocExpr, usize) {
+		let (file, errors) = parse(code);
+		let source = Source::new(SourcePath::new(SourceVirtual("<test>".into())), code.into());
+		(lower(&file, source), errors.len())
+	}
+
+	#[test]
+	fn lower_valid() {
+		let code = "local a = 1; { b: a + 2, 'c': [x for x in [a]], d(y):: y }.b";
+		let (lowered, errors) = lower_code(code);
+		assert_eq!(errors, 0);
+		let source = lowered.span().0;
+		let parsed = jrsonnet_parser::parse(code, &jrsonnet_parser::ParserSettings { source })
+			.expect("code is valid");
+		// Spans of intermediate nodes differ, compare only the structure
+		assert_eq!(
+			format!("{:?}", lowered.expr()).replace(char::is_numeric, ""),
+			format!("{:?}", parsed.expr()).replace(char::is_numeric, ""),
+		);
+	}
+
+	#[test]
+	fn lower_with_error_node() {
+		let (lowered, errors) = lower_code("local a = 1; { b: a, c: }");
+		assert_eq!(errors, 1);
+
+		let Expr::LocalExpr(binds, body) = lowered.expr() else {
+			panic!("expected local, got {lowered:?}");
+		};
+		assert_eq!(binds.len(), 1);
+		let Expr::Obj(ObjBody::MemberList(members)) = body.expr() else {
+			panic!("expected object, got {body:?}");
+		};
+		let [Member::Field(b), Member::Field(c)] = members.as_slice() else {
+			panic!("expected two fields, got {members:?}");
+		};
+		assert_eq!(b.name, FieldName::Fixed("b".into()));
+		assert_eq!(*b.value.expr(), Expr::Var("a".into()));
+		assert_eq!(c.name, FieldName::Fixed("c".into()));
+		let Expr::ErrorStmt(message) = c.value.expr() else {
+			panic!("expected placeholder, got {:?}", c.value);
+		};
+		assert_eq!(
+			*message.expr(),
+			Expr::Str("syntax error: missing expression".into())
+		);
+	}
+}
@@ -0,0 +1,33 @@
+//! Compares `std.setMember` (binary search) with `std.member` (linear scan) on a large sorted array
+
+use std::{hint::black_box, time::Instant};
+
+use jrsonnet_evaluator::{trace::PathResolver, State};
+use jrsonnet_stdlib::ContextInitializer;
+
+const ELEMENTS: usize = 100_000;
+const LOOKUPS: usize = 2_000;
+
+fn main() {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::Absolute));
+	let s = s.build();
+
+	for func in ["std.setMember(x, arr)", "std.member(arr, x)"] {
+		// Every other lookup misses
+		let code = format!(
+			"local arr = std.range(0, {ELEMENTS} - 1);
+			std.length([
+				i
+				for i in std.range(0, {LOOKUPS} - 1)
+				if (local x = i * {step} + (i % 2) / 2; {func})
+			])",
+			step = ELEMENTS / LOOKUPS,
+		);
+		let start = Instant::now();
+		let found = black_box(s.evaluate_snippet("<bench>", code.as_str()))
+			.expect("benchmark snippet is valid");
+		let elapsed = start.elapsed();
+		println!("{func}: {LOOKUPS} lookups in {ELEMENTS} elements, {elapsed:?} ({found:?} found)");
+	}
+}
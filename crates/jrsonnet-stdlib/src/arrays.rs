@@ -1,14 +1,19 @@
 #![allow(non_snake_case)]
 
+use std::{cell::RefCell, rc::Rc};
+
 use jrsonnet_evaluator::{
 	bail,
+	error::ErrorKind::ArrayTooLong,
 	function::{builtin, FuncVal},
 	runtime_error,
-	typed::{BoundedI32, BoundedUsize, Either2, NativeFn, Typed},
+	typed::{BoundedI32, Either2, NativeFn, Typed},
 	val::{equals, ArrValue, IndexableVal},
 	Either, IStr, ObjValue, ObjValueBuilder, Result, ResultExt, Thunk, Val,
 };
 
+use crate::Settings;
+
 pub fn eval_on_empty(on_empty: Option<Thunk<Val>>) -> Result<Val> {
 	if let Some(on_empty) = on_empty {
 		on_empty.evaluate()
@@ -34,23 +39,60 @@ pub fn builtin_make_array(sz: BoundedI32<0, { i32::MAX }>, func: FuncVal) -> Res
 	)
 }
 
-#[builtin]
-pub fn builtin_repeat(what: Either![IStr, ArrValue], count: usize) -> Result<Val> {
+fn check_array_len(settings: &RefCell<Settings>, len: usize) -> Result<()> {
+	if let Some(max) = settings.borrow().max_array_len {
+		if len > max {
+			bail!(ArrayTooLong(len, max));
+		}
+	}
+	Ok(())
+}
+
+#[builtin(fields(
+	settings: Rc<RefCell<Settings>>,
+))]
+pub fn builtin_repeat(
+	this: &builtin_repeat,
+	what: Either![IStr, ArrValue],
+	count: usize,
+) -> Result<Val> {
 	Ok(match what {
-		Either2::A(s) => Val::string(s.repeat(count)),
-		Either2::B(arr) => Val::Arr(
-			ArrValue::repeated(arr, count)
-				.ok_or_else(|| runtime_error!("repeated length overflow"))?,
-		),
+		Either2::A(s) => {
+			if s.len().checked_mul(count).is_none() {
+				bail!("repeated length overflow");
+			}
+			Val::string(s.repeat(count))
+		}
+		Either2::B(arr) => {
+			let len = arr
+				.len()
+				.checked_mul(count)
+				.ok_or_else(|| runtime_error!("repeated length overflow"))?;
+			check_array_len(&this.settings, len)?;
+			Val::Arr(
+				ArrValue::repeated(arr, count)
+					.ok_or_else(|| runtime_error!("repeated length overflow"))?,
+			)
+		}
 	})
 }
 
+/// Unlike `std.makeArray(n, function(_) value)`, value is evaluated at most once, and shared by
+/// every element, so the array takes constant memory
+#[builtin(fields(
+	settings: Rc<RefCell<Settings>>,
+))]
+pub fn builtin_fill(this: &builtin_fill, value: Thunk<Val>, n: usize) -> Result<ArrValue> {
+	check_array_len(&this.settings, n)?;
+	Ok(ArrValue::repeated(ArrValue::lazy(vec![value]), n).expect("single element can't overflow"))
+}
+
 #[builtin]
 pub fn builtin_slice(
 	indexable: IndexableVal,
 	index: Option<Option<i32>>,
 	end: Option<Option<i32>>,
-	step: Option<Option<BoundedUsize<1, { i32::MAX as usize }>>>,
+	step: Option<Option<i32>>,
 ) -> Result<Val> {
 	indexable
 		.slice(index.flatten(), end.flatten(), step.flatten())
@@ -69,6 +111,24 @@ pub fn builtin_map_with_index(func: FuncVal, arr: IndexableVal) -> ArrValue {
 	arr.map_with_index(func)
 }
 
+/// Either `std.zip(a, b)`, or `std.zip([arr1, arr2, ...])` for any number of arrays
+#[builtin]
+pub fn builtin_zip(a: ArrValue, b: Option<ArrValue>) -> Result<ArrValue> {
+	let arrays = if let Some(b) = b {
+		vec![a, b]
+	} else {
+		Vec::<ArrValue>::from_untyped(Val::Arr(a))?
+	};
+	Ok(ArrValue::zip(arrays))
+}
+
+#[builtin]
+pub fn builtin_enumerate(arr: IndexableVal) -> Result<ArrValue> {
+	let arr = arr.to_array();
+	let len = i32::try_from(arr.len()).map_err(|_| runtime_error!("array is too long"))?;
+	Ok(ArrValue::zip(vec![ArrValue::range_exclusive(0, len), arr]))
+}
+
 #[builtin]
 pub fn builtin_map_with_key(func: FuncVal, obj: ObjValue) -> Result<ObjValue> {
 	let mut out = ObjValueBuilder::new();
@@ -155,6 +215,24 @@ pub fn builtin_foldr(func: FuncVal, arr: ArrValue, init: Val) -> Result<Val> {
 	Ok(acc)
 }
 
+/// Same as `std.foldl`, but the first element is used as initial value
+#[builtin]
+pub fn builtin_foldl1(func: FuncVal, arr: ArrValue) -> Result<Val> {
+	let Some(init) = arr.get(0)? else {
+		bail!("foldl1 expects non-empty array");
+	};
+	builtin_foldl(func, arr.slice(Some(1), None, None), init)
+}
+
+/// Same as `std.foldr`, but the last element is used as initial value
+#[builtin]
+pub fn builtin_foldr1(func: FuncVal, arr: ArrValue) -> Result<Val> {
+	let Some(init) = arr.get(arr.len().saturating_sub(1))? else {
+		bail!("foldr1 expects non-empty array");
+	};
+	builtin_foldr(func, arr.slice(None, Some(-1), None), init)
+}
+
 #[builtin]
 pub fn builtin_range(from: i32, to: i32) -> Result<ArrValue> {
 	if to < from {
@@ -163,33 +241,44 @@ pub fn builtin_range(from: i32, to: i32) -> Result<ArrValue> {
 	Ok(ArrValue::range_inclusive(from, to))
 }
 
-#[builtin]
-pub fn builtin_join(sep: IndexableVal, arr: ArrValue) -> Result<IndexableVal> {
+#[builtin(fields(
+	settings: Rc<RefCell<Settings>>,
+))]
+pub fn builtin_join(this: &builtin_join, sep: IndexableVal, arr: ArrValue) -> Result<IndexableVal> {
 	use std::fmt::Write;
 	Ok(match sep {
 		IndexableVal::Arr(joiner_items) => {
-			let mut out = Vec::new();
-
-			let mut first = true;
+			let mut parts = Vec::new();
 			for item in arr.iter() {
-				let item = item?.clone();
-				if let Val::Arr(items) = item {
-					if !first {
-						out.reserve(joiner_items.len());
-						// TODO: extend
-						for item in joiner_items.iter() {
-							out.push(item?);
-						}
-					}
-					first = false;
-					out.reserve(items.len());
-					for item in items.iter() {
+				match item? {
+					Val::Arr(items) => parts.push(items),
+					Val::Null => {}
+					_ => bail!("in std.join all items should be arrays"),
+				}
+			}
+			let len = parts
+				.iter()
+				.try_fold(0usize, |acc, items| acc.checked_add(items.len()))
+				.and_then(|len| {
+					len.checked_add(
+						joiner_items
+							.len()
+							.checked_mul(parts.len().saturating_sub(1))?,
+					)
+				})
+				.ok_or_else(|| runtime_error!("joined length overflow"))?;
+			check_array_len(&this.settings, len)?;
+
+			let mut out = Vec::with_capacity(len);
+			for (i, items) in parts.into_iter().enumerate() {
+				if i != 0 {
+					// TODO: extend
+					for item in joiner_items.iter() {
 						out.push(item?);
 					}
-				} else if matches!(item, Val::Null) {
-					continue;
-				} else {
-					bail!("in std.join all items should be arrays");
+				}
+				for item in items.iter() {
+					out.push(item?);
 				}
 			}
 
@@ -220,11 +309,23 @@ pub fn builtin_join(sep: IndexableVal, arr: ArrValue) -> Result<IndexableVal> {
 }
 
 #[builtin]
-pub fn builtin_lines(arr: ArrValue) -> Result<IndexableVal> {
-	builtin_join(
-		IndexableVal::Str("\n".into()),
-		ArrValue::extended(arr, ArrValue::eager(vec![Val::string("")])),
-	)
+pub fn builtin_lines(arr: ArrValue, #[default("\n".into())] sep: IStr) -> Result<String> {
+	use std::fmt::Write;
+	let mut out = String::new();
+	for (i, item) in arr.iter().enumerate() {
+		match item? {
+			Val::Str(line) => {
+				write!(out, "{line}").unwrap();
+				out.push_str(&sep);
+			}
+			Val::Null => continue,
+			v => bail!(
+				"in std.lines all items should be strings, got {} at index {i}",
+				v.value_type()
+			),
+		}
+	}
+	Ok(out)
 }
 
 #[builtin]
@@ -240,8 +341,9 @@ pub fn deep_join_inner(out: &mut String, arr: IndexableVal) -> Result<()> {
 	match arr {
 		IndexableVal::Str(s) => write!(out, "{s}").expect("no error"),
 		IndexableVal::Arr(arr) => {
-			for ele in arr.iter() {
-				let indexable = IndexableVal::from_untyped(ele?)?;
+			for (i, ele) in arr.iter().enumerate() {
+				let indexable = IndexableVal::from_untyped(ele?)
+					.with_description(|| format!("elem <{i}> joining"))?;
 				deep_join_inner(out, indexable)?;
 			}
 		}
@@ -256,9 +358,14 @@ pub fn builtin_deep_join(arr: IndexableVal) -> Result<String> {
 	Ok(out)
 }
 
+/// Strings are reversed by Unicode scalar values, so multibyte characters are kept intact,
+/// but combining characters will end up attached to a different base character.
 #[builtin]
-pub fn builtin_reverse(arr: ArrValue) -> ArrValue {
-	arr.reversed()
+pub fn builtin_reverse(arr: Either![IStr, ArrValue]) -> Val {
+	match arr {
+		Either2::A(s) => Val::string(s.chars().rev().collect::<String>()),
+		Either2::B(arr) => Val::Arr(arr.reversed()),
+	}
 }
 
 #[builtin]
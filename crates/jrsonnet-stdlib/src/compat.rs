@@ -1,14 +1,19 @@
 use std::cmp::Ordering;
 
 use jrsonnet_evaluator::{
-	function::builtin, operator::evaluate_compare_op, val::ArrValue, Result, Val,
+	function::builtin, operator::evaluate_compare_op, val::ArrValue, Context, Result, Val, Warning,
 };
 
-#[builtin]
-#[allow(non_snake_case)]
-pub fn builtin___compare(v1: Val, v2: Val) -> Result<i32> {
+fn warn_deprecated(ctx: &Context, name: &'static str) {
+	ctx.state().warn(Warning::DeprecatedBuiltin {
+		name,
+		replacement: Some("comparison operators"),
+	});
+}
+
+fn compare(v1: &Val, v2: &Val) -> Result<i32> {
 	Ok(
-		match evaluate_compare_op(&v1, &v2, jrsonnet_parser::BinaryOpType::Lt)? {
+		match evaluate_compare_op(v1, v2, jrsonnet_parser::BinaryOpType::Lt)? {
 			Ordering::Less => -1,
 			Ordering::Equal => 0,
 			Ordering::Greater => 1,
@@ -18,15 +23,24 @@ pub fn builtin___compare(v1: Val, v2: Val) -> Result<i32> {
 
 #[builtin]
 #[allow(non_snake_case)]
-pub fn builtin___compare_array(arr1: ArrValue, arr2: ArrValue) -> Result<i32> {
-	builtin___compare(Val::Arr(arr1), Val::Arr(arr2))
+pub fn builtin___compare(ctx: Context, v1: Val, v2: Val) -> Result<i32> {
+	warn_deprecated(&ctx, "__compare");
+	compare(&v1, &v2)
+}
+
+#[builtin]
+#[allow(non_snake_case)]
+pub fn builtin___compare_array(ctx: Context, arr1: ArrValue, arr2: ArrValue) -> Result<i32> {
+	warn_deprecated(&ctx, "__compare_array");
+	compare(&Val::Arr(arr1), &Val::Arr(arr2))
 }
 
 macro_rules! arr_comp {
 	($name:ident, $operator:expr) => {
 		#[builtin]
 		#[allow(non_snake_case)]
-		pub fn $name(arr1: ArrValue, arr2: ArrValue) -> Result<bool> {
+		pub fn $name(ctx: Context, arr1: ArrValue, arr2: ArrValue) -> Result<bool> {
+			warn_deprecated(&ctx, &stringify!($name)["builtin_".len()..]);
 			let ordering = evaluate_compare_op(
 				&Val::Arr(arr1),
 				&Val::Arr(arr2),
@@ -16,12 +16,23 @@ pub fn builtin_decode_utf8(arr: IBytes) -> Result<IStr> {
 	arr.cast_str().ok_or_else(|| runtime_error!("bad utf8"))
 }
 
+/// Encodes input into buffer allocated once with the exact output length, input is borrowed as is,
+/// so encoding `importbin` blobs doesn't copy them
+fn encode_base64(input: &[u8]) -> Result<String> {
+	let len = base64::encoded_len(input.len(), true)
+		.ok_or_else(|| runtime_error!("base64 output length overflow"))?;
+	let mut out = String::with_capacity(len);
+	STANDARD.encode_string(input, &mut out);
+	debug_assert_eq!(out.len(), len);
+	Ok(out)
+}
+
 #[builtin]
-pub fn builtin_base64(input: Either![IStr, IBytes]) -> String {
+pub fn builtin_base64(input: Either![IStr, IBytes]) -> Result<String> {
 	use Either2::*;
 	match input {
-		A(l) => STANDARD.encode(l.as_bytes()),
-		B(a) => STANDARD.encode(a.as_slice()),
+		A(l) => encode_base64(l.as_bytes()),
+		B(a) => encode_base64(a.as_slice()),
 	}
 }
 
@@ -3,6 +3,7 @@
 use std::{
 	cell::{Ref, RefCell, RefMut},
 	collections::HashMap,
+	path::PathBuf,
 	rc::Rc,
 };
 
@@ -12,7 +13,10 @@ pub use encoding::*;
 pub use hash::*;
 use jrsonnet_evaluator::{
 	error::{ErrorKind::*, Result},
-	function::{CallLocation, FuncVal, TlaArg},
+	function::{
+		builtin::{BuiltinParam, StaticBuiltin},
+		CallLocation, FuncVal, TlaArg,
+	},
 	trace::PathResolver,
 	ContextBuilder, IStr, ObjValue, ObjValueBuilder, Thunk, Val,
 };
@@ -49,233 +53,335 @@ mod sort;
 mod strings;
 mod types;
 
-#[allow(clippy::too_many_lines)]
-pub fn stdlib_uncached(settings: Rc<RefCell<Settings>>) -> ObjValue {
-	let mut builder = ObjValueBuilder::new();
+/// Builtins, which have no state, and are shared between all stdlib instances
+// FIXME: Use PHF
+const STATIC_BUILTINS: &[(&str, &dyn StaticBuiltin)] = &[
+	// Types
+	("type", builtin_type::INST),
+	("types", builtin_types::INST),
+	("typesObject", builtin_types_object::INST),
+	("assertShape", builtin_assert_shape::INST),
+	("isType", builtin_is_type::INST),
+	("isString", builtin_is_string::INST),
+	("isNumber", builtin_is_number::INST),
+	("isBoolean", builtin_is_boolean::INST),
+	("isObject", builtin_is_object::INST),
+	("isArray", builtin_is_array::INST),
+	("isFunction", builtin_is_function::INST),
+	// Arrays
+	("makeArray", builtin_make_array::INST),
+	("slice", builtin_slice::INST),
+	("map", builtin_map::INST),
+	("mapWithIndex", builtin_map_with_index::INST),
+	("zip", builtin_zip::INST),
+	("enumerate", builtin_enumerate::INST),
+	("mapWithKey", builtin_map_with_key::INST),
+	("flatMap", builtin_flatmap::INST),
+	("filter", builtin_filter::INST),
+	("foldl", builtin_foldl::INST),
+	("foldr", builtin_foldr::INST),
+	("foldl1", builtin_foldl1::INST),
+	("foldr1", builtin_foldr1::INST),
+	("range", builtin_range::INST),
+	("lines", builtin_lines::INST),
+	("resolvePath", builtin_resolve_path::INST),
+	("deepJoin", builtin_deep_join::INST),
+	("reverse", builtin_reverse::INST),
+	("any", builtin_any::INST),
+	("all", builtin_all::INST),
+	("member", builtin_member::INST),
+	("find", builtin_find::INST),
+	("contains", builtin_contains::INST),
+	("count", builtin_count::INST),
+	("avg", builtin_avg::INST),
+	("removeAt", builtin_remove_at::INST),
+	("remove", builtin_remove::INST),
+	("flattenArrays", builtin_flatten_arrays::INST),
+	("flattenDeepArray", builtin_flatten_deep_array::INST),
+	("prune", builtin_prune::INST),
+	("filterMap", builtin_filter_map::INST),
+	// Math
+	("abs", builtin_abs::INST),
+	("sign", builtin_sign::INST),
+	("max", builtin_max::INST),
+	("min", builtin_min::INST),
+	("clamp", builtin_clamp::INST),
+	("sum", builtin_sum::INST),
+	("modulo", builtin_modulo::INST),
+	("floor", builtin_floor::INST),
+	("ceil", builtin_ceil::INST),
+	("log", builtin_log::INST),
+	("pow", builtin_pow::INST),
+	("sqrt", builtin_sqrt::INST),
+	("sin", builtin_sin::INST),
+	("cos", builtin_cos::INST),
+	("tan", builtin_tan::INST),
+	("asin", builtin_asin::INST),
+	("acos", builtin_acos::INST),
+	("atan", builtin_atan::INST),
+	("atan2", builtin_atan2::INST),
+	("exp", builtin_exp::INST),
+	("mantissa", builtin_mantissa::INST),
+	("exponent", builtin_exponent::INST),
+	("round", builtin_round::INST),
+	("isEven", builtin_is_even::INST),
+	("isOdd", builtin_is_odd::INST),
+	("isInteger", builtin_is_integer::INST),
+	("isDecimal", builtin_is_decimal::INST),
+	// Operator
+	("mod", builtin_mod::INST),
+	("primitiveEquals", builtin_primitive_equals::INST),
+	("equals", builtin_equals::INST),
+	("deepEqual", builtin_deep_equal::INST),
+	("xor", builtin_xor::INST),
+	("xnor", builtin_xnor::INST),
+	("format", builtin_format::INST),
+	// Sort
+	("sort", builtin_sort::INST),
+	("uniq", builtin_uniq::INST),
+	("set", builtin_set::INST),
+	("minArray", builtin_min_array::INST),
+	("maxArray", builtin_max_array::INST),
+	// Hash
+	("md5", builtin_md5::INST),
+	("sha1", builtin_sha1::INST),
+	("sha256", builtin_sha256::INST),
+	("sha512", builtin_sha512::INST),
+	("sha3", builtin_sha3::INST),
+	// Encoding
+	("encodeUTF8", builtin_encode_utf8::INST),
+	("decodeUTF8", builtin_decode_utf8::INST),
+	("base64", builtin_base64::INST),
+	("base64Decode", builtin_base64_decode::INST),
+	("base64DecodeBytes", builtin_base64_decode_bytes::INST),
+	// Objects
+	("objectFieldsEx", builtin_object_fields_ex::INST),
+	("objectFields", builtin_object_fields::INST),
+	("objectFieldsAll", builtin_object_fields_all::INST),
+	("objectFieldsN", builtin_object_fields_n::INST),
+	("objectFieldsOrder", builtin_object_fields_order::INST),
+	("objectFieldsFilter", builtin_object_fields_filter::INST),
+	("objectValues", builtin_object_values::INST),
+	("objectValuesAll", builtin_object_values_all::INST),
+	("mapObjectValues", builtin_map_object_values::INST),
+	("objectKeysValues", builtin_object_keys_values::INST),
+	("objectKeysValuesAll", builtin_object_keys_values_all::INST),
+	("objectEntriesEx", builtin_object_entries_ex::INST),
+	("objectHasEx", builtin_object_has_ex::INST),
+	("objectHas", builtin_object_has::INST),
+	("objectHasAll", builtin_object_has_all::INST),
+	("objectHasPath", builtin_object_has_path::INST),
+	("objectRemoveKey", builtin_object_remove_key::INST),
+	("objectIntersect", builtin_object_intersect::INST),
+	("objectDiff", builtin_object_diff::INST),
+	// Manifest
+	("escapeStringJson", builtin_escape_string_json::INST),
+	("escapeStringJsonRaw", builtin_escape_string_json_raw::INST),
+	("escapeStringPython", builtin_escape_string_python::INST),
+	("escapeStringXML", builtin_escape_string_xml::INST),
+	("manifestJsonEx", builtin_manifest_json_ex::INST),
+	("manifestJson", builtin_manifest_json::INST),
+	("manifestJsonMinified", builtin_manifest_json_minified::INST),
+	("manifestJsonPretty", builtin_manifest_json_pretty::INST),
+	("manifestSize", builtin_manifest_size::INST),
+	("canonicalJson", builtin_canonical_json::INST),
+	("manifestYamlDoc", builtin_manifest_yaml_doc::INST),
+	("manifestYamlStream", builtin_manifest_yaml_stream::INST),
+	("manifestTomlEx", builtin_manifest_toml_ex::INST),
+	("manifestToml", builtin_manifest_toml::INST),
+	("toString", builtin_to_string::INST),
+	("manifestPython", builtin_manifest_python::INST),
+	("manifestPythonVars", builtin_manifest_python_vars::INST),
+	("manifestXmlJsonml", builtin_manifest_xml_jsonml::INST),
+	("manifestIni", builtin_manifest_ini::INST),
+	// Parse
+	("parseJson", builtin_parse_json::INST),
+	("parseYaml", builtin_parse_yaml::INST),
+	#[cfg(feature = "exp-json5")]
+	("parseJson5", builtin_parse_json5::INST),
+	// Strings
+	("codepoint", builtin_codepoint::INST),
+	("substr", builtin_substr::INST),
+	("char", builtin_char::INST),
+	("strReplace", builtin_str_replace::INST),
+	("escapeStringBash", builtin_escape_string_bash::INST),
+	("escapeStringDollars", builtin_escape_string_dollars::INST),
+	(
+		"escapeStringShellArgv",
+		builtin_escape_string_shell_argv::INST,
+	),
+	("isEmpty", builtin_is_empty::INST),
+	("equalsIgnoreCase", builtin_equals_ignore_case::INST),
+	("splitLimit", builtin_splitlimit::INST),
+	("splitLimitR", builtin_splitlimitr::INST),
+	("split", builtin_split::INST),
+	("splitOnce", builtin_split_once::INST),
+	("asciiUpper", builtin_ascii_upper::INST),
+	("asciiLower", builtin_ascii_lower::INST),
+	("findSubstr", builtin_find_substr::INST),
+	("parseInt", builtin_parse_int::INST),
+	#[cfg(feature = "exp-bigint")]
+	("bigint", builtin_bigint::INST),
+	("parseOctal", builtin_parse_octal::INST),
+	("parseHex", builtin_parse_hex::INST),
+	("stringChars", builtin_string_chars::INST),
+	#[cfg(feature = "exp-unicode")]
+	("stringGraphemes", builtin_string_graphemes::INST),
+	("lstripChars", builtin_lstrip_chars::INST),
+	("rstripChars", builtin_rstrip_chars::INST),
+	("stripChars", builtin_strip_chars::INST),
+	// Misc
+	("length", builtin_length::INST),
+	("functionParams", builtin_function_params::INST),
+	("get", builtin_get::INST),
+	("getPath", builtin_get_path::INST),
+	("startsWith", builtin_starts_with::INST),
+	("endsWith", builtin_ends_with::INST),
+	("assertEqual", builtin_assert_equal::INST),
+	("mergePatch", builtin_merge_patch::INST),
+	// Sets
+	("setMember", builtin_set_member::INST),
+	("setInter", builtin_set_inter::INST),
+	("setDiff", builtin_set_diff::INST),
+	("setUnion", builtin_set_union::INST),
+	// Regex
+	#[cfg(feature = "exp-regex")]
+	("regexQuoteMeta", builtin_regex_quote_meta::INST),
+	// Compat
+	("__compare", builtin___compare::INST),
+	("__compare_array", builtin___compare_array::INST),
+	("__array_less", builtin___array_less::INST),
+	("__array_greater", builtin___array_greater::INST),
+	("__array_less_or_equal", builtin___array_less_or_equal::INST),
+	(
+		"__array_greater_or_equal",
+		builtin___array_greater_or_equal::INST,
+	),
+];
 
-	// FIXME: Use PHF
-	for (name, builtin) in [
-		// Types
-		("type", builtin_type::INST),
-		("isString", builtin_is_string::INST),
-		("isNumber", builtin_is_number::INST),
-		("isBoolean", builtin_is_boolean::INST),
-		("isObject", builtin_is_object::INST),
-		("isArray", builtin_is_array::INST),
-		("isFunction", builtin_is_function::INST),
-		// Arrays
-		("makeArray", builtin_make_array::INST),
-		("repeat", builtin_repeat::INST),
-		("slice", builtin_slice::INST),
-		("map", builtin_map::INST),
-		("mapWithIndex", builtin_map_with_index::INST),
-		("mapWithKey", builtin_map_with_key::INST),
-		("flatMap", builtin_flatmap::INST),
-		("filter", builtin_filter::INST),
-		("foldl", builtin_foldl::INST),
-		("foldr", builtin_foldr::INST),
-		("range", builtin_range::INST),
-		("join", builtin_join::INST),
-		("lines", builtin_lines::INST),
-		("resolvePath", builtin_resolve_path::INST),
-		("deepJoin", builtin_deep_join::INST),
-		("reverse", builtin_reverse::INST),
-		("any", builtin_any::INST),
-		("all", builtin_all::INST),
-		("member", builtin_member::INST),
-		("find", builtin_find::INST),
-		("contains", builtin_contains::INST),
-		("count", builtin_count::INST),
-		("avg", builtin_avg::INST),
-		("removeAt", builtin_remove_at::INST),
-		("remove", builtin_remove::INST),
-		("flattenArrays", builtin_flatten_arrays::INST),
-		("flattenDeepArray", builtin_flatten_deep_array::INST),
-		("prune", builtin_prune::INST),
-		("filterMap", builtin_filter_map::INST),
-		// Math
-		("abs", builtin_abs::INST),
-		("sign", builtin_sign::INST),
-		("max", builtin_max::INST),
-		("min", builtin_min::INST),
-		("clamp", builtin_clamp::INST),
-		("sum", builtin_sum::INST),
-		("modulo", builtin_modulo::INST),
-		("floor", builtin_floor::INST),
-		("ceil", builtin_ceil::INST),
-		("log", builtin_log::INST),
-		("pow", builtin_pow::INST),
-		("sqrt", builtin_sqrt::INST),
-		("sin", builtin_sin::INST),
-		("cos", builtin_cos::INST),
-		("tan", builtin_tan::INST),
-		("asin", builtin_asin::INST),
-		("acos", builtin_acos::INST),
-		("atan", builtin_atan::INST),
-		("atan2", builtin_atan2::INST),
-		("exp", builtin_exp::INST),
-		("mantissa", builtin_mantissa::INST),
-		("exponent", builtin_exponent::INST),
-		("round", builtin_round::INST),
-		("isEven", builtin_is_even::INST),
-		("isOdd", builtin_is_odd::INST),
-		("isInteger", builtin_is_integer::INST),
-		("isDecimal", builtin_is_decimal::INST),
-		// Operator
-		("mod", builtin_mod::INST),
-		("primitiveEquals", builtin_primitive_equals::INST),
-		("equals", builtin_equals::INST),
-		("xor", builtin_xor::INST),
-		("xnor", builtin_xnor::INST),
-		("format", builtin_format::INST),
-		// Sort
-		("sort", builtin_sort::INST),
-		("uniq", builtin_uniq::INST),
-		("set", builtin_set::INST),
-		("minArray", builtin_min_array::INST),
-		("maxArray", builtin_max_array::INST),
-		// Hash
-		("md5", builtin_md5::INST),
-		("sha1", builtin_sha1::INST),
-		("sha256", builtin_sha256::INST),
-		("sha512", builtin_sha512::INST),
-		("sha3", builtin_sha3::INST),
-		// Encoding
-		("encodeUTF8", builtin_encode_utf8::INST),
-		("decodeUTF8", builtin_decode_utf8::INST),
-		("base64", builtin_base64::INST),
-		("base64Decode", builtin_base64_decode::INST),
-		("base64DecodeBytes", builtin_base64_decode_bytes::INST),
-		// Objects
-		("objectFieldsEx", builtin_object_fields_ex::INST),
-		("objectFields", builtin_object_fields::INST),
-		("objectFieldsAll", builtin_object_fields_all::INST),
-		("objectValues", builtin_object_values::INST),
-		("objectValuesAll", builtin_object_values_all::INST),
-		("objectKeysValues", builtin_object_keys_values::INST),
-		("objectKeysValuesAll", builtin_object_keys_values_all::INST),
-		("objectHasEx", builtin_object_has_ex::INST),
-		("objectHas", builtin_object_has::INST),
-		("objectHasAll", builtin_object_has_all::INST),
-		("objectRemoveKey", builtin_object_remove_key::INST),
-		// Manifest
-		("escapeStringJson", builtin_escape_string_json::INST),
-		("escapeStringPython", builtin_escape_string_python::INST),
-		("escapeStringXML", builtin_escape_string_xml::INST),
-		("manifestJsonEx", builtin_manifest_json_ex::INST),
-		("manifestJson", builtin_manifest_json::INST),
-		("manifestJsonMinified", builtin_manifest_json_minified::INST),
-		("manifestYamlDoc", builtin_manifest_yaml_doc::INST),
-		("manifestYamlStream", builtin_manifest_yaml_stream::INST),
-		("manifestTomlEx", builtin_manifest_toml_ex::INST),
-		("manifestToml", builtin_manifest_toml::INST),
-		("toString", builtin_to_string::INST),
-		("manifestPython", builtin_manifest_python::INST),
-		("manifestPythonVars", builtin_manifest_python_vars::INST),
-		("manifestXmlJsonml", builtin_manifest_xml_jsonml::INST),
-		("manifestIni", builtin_manifest_ini::INST),
-		// Parse
-		("parseJson", builtin_parse_json::INST),
-		("parseYaml", builtin_parse_yaml::INST),
-		// Strings
-		("codepoint", builtin_codepoint::INST),
-		("substr", builtin_substr::INST),
-		("char", builtin_char::INST),
-		("strReplace", builtin_str_replace::INST),
-		("escapeStringBash", builtin_escape_string_bash::INST),
-		("escapeStringDollars", builtin_escape_string_dollars::INST),
-		("isEmpty", builtin_is_empty::INST),
-		("equalsIgnoreCase", builtin_equals_ignore_case::INST),
-		("splitLimit", builtin_splitlimit::INST),
-		("splitLimitR", builtin_splitlimitr::INST),
-		("split", builtin_split::INST),
-		("asciiUpper", builtin_ascii_upper::INST),
-		("asciiLower", builtin_ascii_lower::INST),
-		("findSubstr", builtin_find_substr::INST),
-		("parseInt", builtin_parse_int::INST),
-		#[cfg(feature = "exp-bigint")]
-		("bigint", builtin_bigint::INST),
-		("parseOctal", builtin_parse_octal::INST),
-		("parseHex", builtin_parse_hex::INST),
-		("stringChars", builtin_string_chars::INST),
-		("lstripChars", builtin_lstrip_chars::INST),
-		("rstripChars", builtin_rstrip_chars::INST),
-		("stripChars", builtin_strip_chars::INST),
-		// Misc
-		("length", builtin_length::INST),
-		("get", builtin_get::INST),
-		("startsWith", builtin_starts_with::INST),
-		("endsWith", builtin_ends_with::INST),
-		("assertEqual", builtin_assert_equal::INST),
-		("mergePatch", builtin_merge_patch::INST),
-		// Sets
-		("setMember", builtin_set_member::INST),
-		("setInter", builtin_set_inter::INST),
-		("setDiff", builtin_set_diff::INST),
-		("setUnion", builtin_set_union::INST),
-		// Regex
-		#[cfg(feature = "exp-regex")]
-		("regexQuoteMeta", builtin_regex_quote_meta::INST),
-		// Compat
-		("__compare", builtin___compare::INST),
-		("__compare_array", builtin___compare_array::INST),
-		("__array_less", builtin___array_less::INST),
-		("__array_greater", builtin___array_greater::INST),
-		("__array_less_or_equal", builtin___array_less_or_equal::INST),
+/// Description of standard library function
+#[derive(Clone)]
+pub struct BuiltinInfo {
+	/// Name of the field in `std` object
+	pub name: &'static str,
+	pub params: Vec<BuiltinParam>,
+}
+
+/// Lists every builtin function of the standard library, with their parameters.
+///
+/// `std.thisFile` is not a function, and is not included.
+pub fn builtin_catalog() -> Vec<BuiltinInfo> {
+	let settings = ContextInitializer::new(PathResolver::Absolute).settings;
+	STATIC_BUILTINS
+		.iter()
+		.map(|&(name, builtin)| BuiltinInfo {
+			name,
+			params: builtin.params().to_vec(),
+		})
+		.chain(
+			stateful_builtins(&settings)
+				.into_iter()
+				.map(|(name, builtin)| BuiltinInfo {
+					name,
+					params: builtin.params(),
+				}),
+		)
+		.collect()
+}
+
+/// Builtins, which depend on [`Settings`] or other per-instance state
+fn stateful_builtins(settings: &Rc<RefCell<Settings>>) -> Vec<(&'static str, FuncVal)> {
+	#[cfg_attr(not(feature = "exp-regex"), allow(unused_mut))]
+	let mut out = vec![
 		(
-			"__array_greater_or_equal",
-			builtin___array_greater_or_equal::INST,
+			"extVar",
+			FuncVal::builtin(builtin_ext_var {
+				settings: settings.clone(),
+				overrides: Rc::default(),
+			}),
 		),
-	]
-	.iter()
-	.copied()
-	{
-		builder.method(name, builtin);
-	}
-
-	builder.method(
-		"extVar",
-		builtin_ext_var {
-			settings: settings.clone(),
-		},
-	);
-	builder.method(
-		"native",
-		builtin_native {
-			settings: settings.clone(),
-		},
-	);
-	builder.method("trace", builtin_trace { settings });
-	builder.method("id", FuncVal::Id);
+		(
+			"native",
+			FuncVal::builtin(builtin_native {
+				settings: settings.clone(),
+			}),
+		),
+		(
+			"repeat",
+			FuncVal::builtin(builtin_repeat {
+				settings: settings.clone(),
+			}),
+		),
+		(
+			"fill",
+			FuncVal::builtin(builtin_fill {
+				settings: settings.clone(),
+			}),
+		),
+		(
+			"join",
+			FuncVal::builtin(builtin_join {
+				settings: settings.clone(),
+			}),
+		),
+		(
+			"trace",
+			FuncVal::builtin(builtin_trace {
+				settings: settings.clone(),
+			}),
+		),
+		(
+			"traceLabeled",
+			FuncVal::builtin(builtin_trace_labeled {
+				settings: settings.clone(),
+			}),
+		),
+		("id", FuncVal::Id),
+	];
 
 	#[cfg(feature = "exp-regex")]
 	{
 		// Regex
 		let regex_cache = RegexCache::default();
-		builder.method(
-			"regexFullMatch",
-			builtin_regex_full_match {
-				cache: regex_cache.clone(),
-			},
-		);
-		builder.method(
-			"regexPartialMatch",
-			builtin_regex_partial_match {
-				cache: regex_cache.clone(),
-			},
-		);
-		builder.method(
-			"regexReplace",
-			builtin_regex_replace {
-				cache: regex_cache.clone(),
-			},
-		);
-		builder.method(
-			"regexGlobalReplace",
-			builtin_regex_global_replace { cache: regex_cache },
-		);
+		out.extend([
+			(
+				"regexFullMatch",
+				FuncVal::builtin(builtin_regex_full_match {
+					cache: regex_cache.clone(),
+				}),
+			),
+			(
+				"regexPartialMatch",
+				FuncVal::builtin(builtin_regex_partial_match {
+					cache: regex_cache.clone(),
+				}),
+			),
+			(
+				"regexReplace",
+				FuncVal::builtin(builtin_regex_replace {
+					cache: regex_cache.clone(),
+				}),
+			),
+			(
+				"regexGlobalReplace",
+				FuncVal::builtin(builtin_regex_global_replace { cache: regex_cache }),
+			),
+		]);
 	};
 
+	out
+}
+
+pub fn stdlib_uncached(settings: Rc<RefCell<Settings>>) -> ObjValue {
+	let mut builder = ObjValueBuilder::new();
+
+	for &(name, builtin) in STATIC_BUILTINS {
+		builder.method(name, builtin);
+	}
+	for (name, builtin) in stateful_builtins(&settings) {
+		builder.method(name, builtin);
+	}
+
 	builder.build()
 }
 
@@ -309,6 +415,21 @@ impl TracePrinter for StdTracePrinter {
 	}
 }
 
+/// Collects traces in memory instead of printing them, clones share the same buffer
+#[derive(Default, Clone)]
+pub struct BufferedTracePrinter(Rc<RefCell<Vec<IStr>>>);
+impl BufferedTracePrinter {
+	/// Returns collected traces, clearing the buffer
+	pub fn take(&self) -> Vec<IStr> {
+		std::mem::take(&mut *self.0.borrow_mut())
+	}
+}
+impl TracePrinter for BufferedTracePrinter {
+	fn print_trace(&self, _loc: CallLocation, value: IStr) {
+		self.0.borrow_mut().push(value);
+	}
+}
+
 pub struct Settings {
 	/// Used for `std.extVar`
 	pub ext_vars: HashMap<IStr, TlaArg>,
@@ -318,6 +439,15 @@ pub struct Settings {
 	pub trace_printer: Box<dyn TracePrinter>,
 	/// Used for `std.thisFile`
 	pub path_resolver: PathResolver,
+	/// Should `std.thisFile` be defined, enabled by default
+	///
+	/// When enabled, every file gets its own `std` object, extending shared stdlib with `thisFile`
+	/// field, path itself is only resolved when `std.thisFile` is accessed. When disabled, every
+	/// file receives the same `std` object, which is slightly cheaper for programs consisting of
+	/// many small files, but `std.thisFile` access will fail.
+	pub this_file: bool,
+	/// Maximum length of arrays, produced by `std.join` and `std.repeat`, unlimited if not set
+	pub max_array_len: Option<usize>,
 }
 
 fn extvar_source(name: &str, code: impl Into<IStr>) -> Source {
@@ -325,6 +455,22 @@ fn extvar_source(name: &str, code: impl Into<IStr>) -> Source {
 	Source::new_virtual(source_name.into(), code.into())
 }
 
+fn parse_ext_code(name: &str, code: impl Into<IStr>) -> Result<TlaArg> {
+	let code = code.into();
+	let source = extvar_source(name, code.clone());
+	let parsed = jrsonnet_parser::parse(
+		&code,
+		&jrsonnet_parser::ParserSettings {
+			source: source.clone(),
+		},
+	)
+	.map_err(|e| ImportSyntaxError {
+		path: source,
+		error: Box::new(e),
+	})?;
+	Ok(TlaArg::Code(parsed))
+}
+
 #[derive(Trace, Clone)]
 pub struct ContextInitializer {
 	/// std without applied thisFile overlay
@@ -338,6 +484,8 @@ impl ContextInitializer {
 			ext_natives: HashMap::new(),
 			trace_printer: Box::new(StdTracePrinter::new(resolver.clone())),
 			path_resolver: resolver,
+			this_file: true,
+			max_array_len: None,
 		};
 		let settings = Rc::new(RefCell::new(settings));
 		let stdlib_obj = stdlib_uncached(settings.clone());
@@ -362,25 +510,26 @@ impl ContextInitializer {
 			.ext_vars
 			.insert(name, TlaArg::String(value));
 	}
-	pub fn add_ext_code(&self, name: &str, code: impl Into<IStr>) -> Result<()> {
-		let code = code.into();
-		let source = extvar_source(name, code.clone());
-		let parsed = jrsonnet_parser::parse(
-			&code,
-			&jrsonnet_parser::ParserSettings {
-				source: source.clone(),
-			},
-		)
-		.map_err(|e| ImportSyntaxError {
-			path: source,
-			error: Box::new(e),
-		})?;
-		// self.data_mut().volatile_files.insert(source_name, code);
+	/// String external variable, which is read from `path` with import resolver on first use
+	pub fn add_ext_import_str(&self, name: IStr, path: PathBuf) {
 		self.settings_mut()
 			.ext_vars
-			.insert(name.into(), TlaArg::Code(parsed));
+			.insert(name, TlaArg::ImportStr(path));
+	}
+	pub fn add_ext_code(&self, name: &str, code: impl Into<IStr>) -> Result<()> {
+		let parsed = parse_ext_code(name, code)?;
+		// self.data_mut().volatile_files.insert(source_name, code);
+		self.settings_mut().ext_vars.insert(name.into(), parsed);
 		Ok(())
 	}
+	/// See [`Settings::this_file`]
+	pub fn set_this_file(&self, enabled: bool) {
+		self.settings_mut().this_file = enabled;
+	}
+	/// See [`Settings::max_array_len`]
+	pub fn set_max_array_len(&self, max: Option<usize>) {
+		self.settings_mut().max_array_len = max;
+	}
 	pub fn add_native(&self, name: impl Into<IStr>, cb: impl Into<FuncVal>) {
 		self.settings_mut()
 			.ext_natives
@@ -392,18 +541,84 @@ impl jrsonnet_evaluator::ContextInitializer for ContextInitializer {
 		1
 	}
 	fn populate(&self, source: Source, builder: &mut ContextBuilder) {
+		builder.bind("std", Thunk::evaluated(Val::Obj(self.std_for_file(source))));
+	}
+	fn as_any(&self) -> &dyn std::any::Any {
+		self
+	}
+}
+impl ContextInitializer {
+	fn std_for_file(&self, source: Source) -> ObjValue {
+		if !self.settings().this_file {
+			return self.stdlib_obj.clone();
+		}
 		let mut std = ObjValueBuilder::new();
 		std.with_super(self.stdlib_obj.clone());
-		std.field("thisFile").hide().value({
-			let source_path = source.source_path();
-			source_path.path().map_or_else(
-				|| source_path.to_string(),
-				|p| self.settings().path_resolver.resolve(p),
-			)
-		});
-		let stdlib_with_this_file = std.build();
+		let resolver = self.settings().path_resolver.clone();
+		std.field("thisFile")
+			.hide()
+			.try_thunk(Thunk!(move || {
+				let source_path = source.source_path();
+				Ok(Val::string(source_path.path().map_or_else(
+					|| source_path.to_string(),
+					|p| resolver.resolve(p),
+				)))
+			}))
+			.expect("std overlay is empty");
+		std.build()
+	}
+}
 
-		builder.bind("std", Thunk::evaluated(Val::Obj(stdlib_with_this_file)));
+/// Context initializer, layering additional `std.extVar` values over the wrapped [`ContextInitializer`]
+///
+/// Unlike [`ContextInitializer::add_ext_var`], shared settings are left untouched, so one initializer
+/// might be reused for concurrent evaluations with different ext vars, by passing this wrapper to
+/// [`State::evaluate_snippet_with`](jrsonnet_evaluator::State::evaluate_snippet_with).
+///
+/// Overrides are only visible to the evaluated snippet itself: imported files are initialized by
+/// the state's own context initializer, and only see the shared ext vars.
+#[derive(Trace, Clone)]
+pub struct ExtVarOverrides {
+	inner: ContextInitializer,
+	ext_vars: Rc<HashMap<IStr, TlaArg>>,
+}
+impl ExtVarOverrides {
+	pub fn new(inner: ContextInitializer) -> Self {
+		Self {
+			inner,
+			ext_vars: Rc::default(),
+		}
+	}
+	fn insert(&mut self, name: IStr, value: TlaArg) {
+		Rc::make_mut(&mut self.ext_vars).insert(name, value);
+	}
+	pub fn add_ext_var(&mut self, name: IStr, value: Val) {
+		self.insert(name, TlaArg::Val(value));
+	}
+	pub fn add_ext_str(&mut self, name: IStr, value: IStr) {
+		self.insert(name, TlaArg::String(value));
+	}
+	pub fn add_ext_code(&mut self, name: &str, code: impl Into<IStr>) -> Result<()> {
+		let parsed = parse_ext_code(name, code)?;
+		self.insert(name.into(), parsed);
+		Ok(())
+	}
+}
+impl jrsonnet_evaluator::ContextInitializer for ExtVarOverrides {
+	fn reserve_vars(&self) -> usize {
+		1
+	}
+	fn populate(&self, source: Source, builder: &mut ContextBuilder) {
+		let mut std = ObjValueBuilder::new();
+		std.with_super(self.inner.std_for_file(source));
+		std.method(
+			"extVar",
+			builtin_ext_var {
+				settings: self.inner.settings.clone(),
+				overrides: self.ext_vars.clone(),
+			},
+		);
+		builder.bind("std", Thunk::evaluated(Val::Obj(std.build())));
 	}
 	fn as_any(&self) -> &dyn std::any::Any {
 		self
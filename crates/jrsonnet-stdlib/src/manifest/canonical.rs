@@ -0,0 +1,116 @@
+use std::fmt::Write;
+
+use jrsonnet_evaluator::{
+	bail, in_description_frame,
+	manifest::{escape_string_json_buf, ManifestFormat},
+	Result, ResultExt, Val,
+};
+
+/// Deterministic minified JSON, intended for hashing
+///
+/// Object keys are sorted by code points at every level regardless of `exp-preserve-order`, hidden
+/// fields are omitted, there is no insignificant whitespace, and numbers are written in the shortest
+/// form, which roundtrips to the same value, using ECMAScript `Number.prototype.toString` rules,
+/// same as in RFC 8785 (`1e+21`, `1e-7`, `-0` is written as `0`).
+pub struct CanonicalJsonFormat;
+
+impl ManifestFormat for CanonicalJsonFormat {
+	fn manifest_buf(&self, val: Val, buf: &mut String) -> Result<()> {
+		manifest_canonical_buf(&val, buf)
+	}
+	fn file_trailing_newline(&self) -> bool {
+		false
+	}
+}
+
+fn write_number(n: f64, buf: &mut String) {
+	if n == 0.0 {
+		buf.push('0');
+		return;
+	}
+	if n < 0.0 {
+		buf.push('-');
+	}
+	// Rust produces shortest roundtripping digits, but doesn't follow ECMAScript rules of choosing
+	// between exponential and fixed notations
+	let formatted = format!("{:e}", n.abs());
+	let (mantissa, exponent) = formatted.split_once('e').expect("exponent is present");
+	let digits = mantissa.replace('.', "");
+	let k = digits.len() as i32;
+	// n in ECMAScript spec: value = digits * 10^(point - k)
+	let point = exponent.parse::<i32>().expect("exponent is integer") + 1;
+	#[allow(clippy::cast_sign_loss)]
+	if k <= point && point <= 21 {
+		buf.push_str(&digits);
+		buf.extend(std::iter::repeat('0').take((point - k) as usize));
+	} else if 0 < point && point <= 21 {
+		let (int, frac) = digits.split_at(point as usize);
+		write!(buf, "{int}.{frac}").unwrap();
+	} else if -6 < point && point <= 0 {
+		buf.push_str("0.");
+		buf.extend(std::iter::repeat('0').take(-point as usize));
+		buf.push_str(&digits);
+	} else {
+		let (first, rest) = digits.split_at(1);
+		buf.push_str(first);
+		if !rest.is_empty() {
+			write!(buf, ".{rest}").unwrap();
+		}
+		let exponent = point - 1;
+		let sign = if exponent < 0 { '-' } else { '+' };
+		write!(buf, "e{sign}{}", exponent.abs()).unwrap();
+	}
+}
+
+fn manifest_canonical_buf(val: &Val, buf: &mut String) -> Result<()> {
+	match val {
+		Val::Bool(true) => buf.push_str("true"),
+		Val::Bool(false) => buf.push_str("false"),
+		Val::Null => buf.push_str("null"),
+		Val::Str(s) => escape_string_json_buf(&s.clone().into_flat(), buf),
+		Val::Num(n) => write_number(n.get(), buf),
+		#[cfg(feature = "exp-bigint")]
+		Val::BigInt(n) => write!(buf, "{n}").unwrap(),
+		Val::Arr(items) => {
+			buf.push('[');
+			for (i, item) in items.iter().enumerate() {
+				if i != 0 {
+					buf.push(',');
+				}
+				let item = item.with_description(|| format!("elem <{i}> evaluation"))?;
+				in_description_frame(
+					|| format!("elem <{i}> manifestification"),
+					|| manifest_canonical_buf(&item, buf),
+				)?;
+			}
+			buf.push(']');
+		}
+		Val::Obj(obj) => {
+			obj.run_assertions()?;
+			let mut fields = obj.fields(
+				#[cfg(feature = "exp-preserve-order")]
+				false,
+			);
+			fields.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+			buf.push('{');
+			for (i, key) in fields.into_iter().enumerate() {
+				if i != 0 {
+					buf.push(',');
+				}
+				let value = obj
+					.get(key.clone())
+					.with_description(|| format!("field <{key}> evaluation"))?
+					.expect("field exists");
+				escape_string_json_buf(&key, buf);
+				buf.push(':');
+				in_description_frame(
+					|| format!("field <{key}> manifestification"),
+					|| manifest_canonical_buf(&value, buf),
+				)?;
+			}
+			buf.push('}');
+		}
+		Val::Func(_) => bail!("tried to manifest function"),
+	}
+	Ok(())
+}
@@ -30,13 +30,22 @@ impl IniFormat {
 	}
 }
 
+/// Object is treated as `{main, sections}` structure if it has visible `sections` field, and no
+/// other visible fields except `main`. Any other object is treated as the `main` section.
+fn is_sections_shape(obj: &ObjValue) -> bool {
+	obj.has_field("sections".into()) && obj.len() == 1 + usize::from(obj.has_field("main".into()))
+}
+
 impl ManifestFormat for IniFormat {
 	fn manifest_buf(&self, val: Val, buf: &mut String) -> Result<()> {
-		manifest_ini_obj(
-			self,
-			IniObj::from_untyped(val).description("ini object structure")?,
-			buf,
-		)
+		let obj = match val {
+			Val::Obj(obj) if !is_sections_shape(&obj) => IniObj {
+				main: Some(obj),
+				sections: BTreeMap::new(),
+			},
+			val => IniObj::from_untyped(val).description("ini object structure")?,
+		};
+		manifest_ini_obj(self, obj, buf)
 	}
 }
 
@@ -1,13 +1,16 @@
+mod canonical;
 mod ini;
 mod python;
 mod toml;
 mod xml;
 mod yaml;
 
+pub use canonical::CanonicalJsonFormat;
 pub use ini::IniFormat;
 use jrsonnet_evaluator::{
+	bail,
 	function::builtin,
-	manifest::{escape_string_json, JsonFormat, YamlStreamFormat},
+	manifest::{escape_string_json, JsonFormat, ManifestFormat, YamlStreamFormat},
 	IStr, ObjValue, Result, Val,
 };
 pub use python::{PythonFormat, PythonVarsFormat};
@@ -20,6 +23,18 @@ pub fn builtin_escape_string_json(str_: IStr) -> Result<String> {
 	Ok(escape_string_json(&str_))
 }
 
+/// Same as `std.escapeStringJson`, but without surrounding quotes
+#[builtin]
+pub fn builtin_escape_string_json_raw(str_: IStr) -> String {
+	let escaped = escape_string_json(&str_);
+	escaped[1..escaped.len() - 1].to_owned()
+}
+
+#[builtin]
+pub fn builtin_canonical_json(value: Val) -> Result<String> {
+	value.manifest(CanonicalJsonFormat)
+}
+
 #[builtin]
 pub fn builtin_escape_string_python(str: IStr) -> Result<String> {
 	Ok(escape_string_json(&str))
@@ -31,6 +46,7 @@ pub fn builtin_manifest_json_ex(
 	indent: String,
 	newline: Option<IStr>,
 	key_val_sep: Option<IStr>,
+	#[default(true)] indent_array_in_object: bool,
 
 	#[default(false)]
 	#[cfg(feature = "exp-preserve-order")]
@@ -38,13 +54,16 @@ pub fn builtin_manifest_json_ex(
 ) -> Result<String> {
 	let newline = newline.as_deref().unwrap_or("\n");
 	let key_val_sep = key_val_sep.as_deref().unwrap_or(": ");
-	value.manifest(JsonFormat::std_to_json(
-		indent,
-		newline,
-		key_val_sep,
-		#[cfg(feature = "exp-preserve-order")]
-		preserve_order,
-	))
+	value.manifest(
+		JsonFormat::std_to_json(
+			indent,
+			newline,
+			key_val_sep,
+			#[cfg(feature = "exp-preserve-order")]
+			preserve_order,
+		)
+		.with_indent_array_in_object(indent_array_in_object),
+	)
 }
 
 #[builtin]
@@ -60,11 +79,38 @@ pub fn builtin_manifest_json(
 		"    ".to_owned(),
 		None,
 		None,
+		true,
 		#[cfg(feature = "exp-preserve-order")]
 		preserve_order,
 	)
 }
 
+/// Same as `std.manifestJsonEx(value, '  ')`, but with a trailing newline control.
+///
+/// Keys are sorted by default, `sort: false` keeps fields in definition order, and requires `exp-preserve-order`
+#[builtin]
+pub fn builtin_manifest_json_pretty(
+	value: Val,
+	#[default(true)] sort: bool,
+	#[default(false)] trailing_newline: bool,
+) -> Result<String> {
+	#[cfg(not(feature = "exp-preserve-order"))]
+	if !sort {
+		bail!("std.manifestJsonPretty sort=false requires jrsonnet to be built with exp-preserve-order feature")
+	}
+	let mut out = value.manifest(JsonFormat::std_to_json(
+		"  ".to_owned(),
+		"\n",
+		": ",
+		#[cfg(feature = "exp-preserve-order")]
+		!sort,
+	))?;
+	if trailing_newline {
+		out.push('\n');
+	}
+	Ok(out)
+}
+
 #[builtin]
 pub fn builtin_manifest_json_minified(
 	value: Val,
@@ -79,24 +125,82 @@ pub fn builtin_manifest_json_minified(
 	))
 }
 
+/// Writer, which only counts written bytes, and discards the data itself
+#[derive(Default)]
+struct CountingWriter(usize);
+impl std::fmt::Write for CountingWriter {
+	fn write_str(&mut self, s: &str) -> std::fmt::Result {
+		self.0 += s.len();
+		Ok(())
+	}
+}
+
+/// Returns byte length of `value` manifested with `std.manifestJson` (`format: "json"`, default)
+/// or `std.manifestYamlDoc` (`format: "yaml"`).
+///
+/// Formats which support incremental output (json) never allocate the whole manifested string.
 #[builtin]
+pub fn builtin_manifest_size(
+	value: Val,
+	format: Option<IStr>,
+
+	#[default(false)]
+	#[cfg(feature = "exp-preserve-order")]
+	preserve_order: bool,
+) -> Result<usize> {
+	let format: Box<dyn ManifestFormat> = match format.as_deref().unwrap_or("json") {
+		"json" => Box::new(JsonFormat::std_to_json(
+			"    ".to_owned(),
+			"\n",
+			": ",
+			#[cfg(feature = "exp-preserve-order")]
+			preserve_order,
+		)),
+		"yaml" => Box::new(YamlFormat::std_to_yaml(
+			false,
+			true,
+			#[cfg(feature = "exp-preserve-order")]
+			preserve_order,
+		)),
+		other => bail!("unknown manifest format: {other}, expected json or yaml"),
+	};
+	let mut out = CountingWriter::default();
+	value.manifest_to(&mut out, format)?;
+	Ok(out.0)
+}
+
+/// `sort_keys` forces lexicographic key order, even if `preserve_order` is set
+///
+/// `flow_style` writes the whole document inline, i.e `{a: 1, b: [2, 3]}`, `indent_array_in_object`
+/// has no effect then
+#[builtin]
+#[allow(clippy::fn_params_excessive_bools)]
 pub fn builtin_manifest_yaml_doc(
 	value: Val,
 	#[default(false)] indent_array_in_object: bool,
 	#[default(true)] quote_keys: bool,
+	#[default(false)] sort_keys: bool,
+	#[default(false)] flow_style: bool,
 
 	#[default(false)]
 	#[cfg(feature = "exp-preserve-order")]
 	preserve_order: bool,
 ) -> Result<String> {
-	value.manifest(YamlFormat::std_to_yaml(
-		indent_array_in_object,
-		quote_keys,
-		#[cfg(feature = "exp-preserve-order")]
-		preserve_order,
-	))
+	#[cfg(not(feature = "exp-preserve-order"))]
+	let _ = sort_keys;
+	value.manifest(
+		YamlFormat::std_to_yaml(
+			indent_array_in_object,
+			quote_keys,
+			#[cfg(feature = "exp-preserve-order")]
+			(preserve_order && !sort_keys),
+		)
+		.with_flow_style(flow_style),
+	)
 }
 
+/// With `allow_single`, non-array `value` is manifested as a stream of one document,
+/// instead of failing
 #[builtin]
 #[allow(clippy::fn_params_excessive_bools)]
 pub fn builtin_manifest_yaml_stream(
@@ -104,11 +208,17 @@ pub fn builtin_manifest_yaml_stream(
 	#[default(false)] indent_array_in_object: bool,
 	#[default(true)] c_document_end: bool,
 	#[default(true)] quote_keys: bool,
+	#[default(false)] allow_single: bool,
 
 	#[default(false)]
 	#[cfg(feature = "exp-preserve-order")]
 	preserve_order: bool,
 ) -> Result<String> {
+	let value = match value {
+		Val::Arr(_) => value,
+		single if allow_single => Val::Arr(vec![single].into()),
+		value => value,
+	};
 	value.manifest(YamlStreamFormat::std_yaml_stream(
 		YamlFormat::std_to_yaml(
 			indent_array_in_object,
@@ -189,11 +299,25 @@ pub fn builtin_escape_string_xml(str_: String) -> String {
 	xml::escape_string_xml(str_.as_str())
 }
 
+/// Elements without children are written as `<a/>`, `self_closing: false` writes them as `<a></a>`
+/// instead, for compatibility with other implementations.
+///
+/// `["!CDATA", text]` element is written as a CDATA section.
 #[builtin]
-pub fn builtin_manifest_xml_jsonml(value: Val) -> Result<String> {
-	value.manifest(XmlJsonmlFormat::std_to_xml())
+pub fn builtin_manifest_xml_jsonml(
+	value: Val,
+	#[default(true)] self_closing: bool,
+) -> Result<String> {
+	value.manifest(if self_closing {
+		XmlJsonmlFormat::self_closing()
+	} else {
+		XmlJsonmlFormat::std_to_xml()
+	})
 }
 
+/// Accepts either `{main, sections}` structure, or a flat object, which is written as the main
+/// section. Object is considered structured if it has `sections` field, and no other fields
+/// except `main`.
 #[builtin]
 pub fn builtin_manifest_ini(
 	ini: Val,
@@ -30,14 +30,24 @@ pub struct TomlFormat<'s> {
 	#[cfg(feature = "exp-preserve-order")]
 	preserve_order: bool,
 }
-impl TomlFormat<'_> {
+impl<'s> TomlFormat<'s> {
 	pub fn cli(
 		padding: usize,
 		#[cfg(feature = "exp-preserve-order")] preserve_order: bool,
 	) -> Self {
-		let padding = " ".repeat(padding);
+		Self::cli_indent(
+			" ".repeat(padding),
+			#[cfg(feature = "exp-preserve-order")]
+			preserve_order,
+		)
+	}
+	/// Same as [`Self::cli`], but indents with arbitrary string, i.e `"\t"`
+	pub fn cli_indent(
+		indent: impl Into<Cow<'s, str>>,
+		#[cfg(feature = "exp-preserve-order")] preserve_order: bool,
+	) -> Self {
 		Self {
-			padding: Cow::Owned(padding),
+			padding: indent.into(),
 			skip_empty_sections: true,
 			#[cfg(feature = "exp-preserve-order")]
 			preserve_order,
@@ -56,16 +66,30 @@ impl TomlFormat<'_> {
 	}
 }
 
+/// Bare keys may only consist of `A-Za-z0-9_-`, and can't be empty
 fn bare_allowed(s: &str) -> bool {
-	s.bytes()
-		.all(|c| matches!(c, b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-'))
+	!s.is_empty()
+		&& s.bytes()
+			.all(|c| matches!(c, b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-'))
+}
+
+/// TOML basic string is the same as JSON string, except DEL also needs to be escaped
+fn escape_string_toml_buf(s: &str, buf: &mut String) {
+	if s.contains('\x7f') {
+		let mut escaped = String::new();
+		escape_string_json_buf(s, &mut escaped);
+		buf.push_str(&escaped.replace('\x7f', "\\u007f"));
+	} else {
+		escape_string_json_buf(s, buf);
+	}
 }
 
+/// Writes single key segment, dotted paths should be written segment-by-segment
 fn escape_key_toml_buf(key: &str, buf: &mut String) {
 	if bare_allowed(key) {
 		buf.push_str(key);
 	} else {
-		escape_string_json_buf(key, buf);
+		escape_string_toml_buf(key, buf);
 	}
 }
 
@@ -100,7 +124,7 @@ fn manifest_value(
 		Val::Bool(true) => buf.push_str("true"),
 		Val::Bool(false) => buf.push_str("false"),
 		Val::Str(s) => {
-			escape_string_json_buf(&s.clone().into_flat(), buf);
+			escape_string_toml_buf(&s.clone().into_flat(), buf);
 		}
 		Val::Num(n) => write!(buf, "{n}").unwrap(),
 		#[cfg(feature = "exp-bigint")]
@@ -15,11 +15,15 @@ impl XmlJsonmlFormat {
 			force_closing: true,
 		}
 	}
-	pub fn cli() -> Self {
+	/// Elements without children are written as `<a/>`
+	pub fn self_closing() -> Self {
 		Self {
 			force_closing: false,
 		}
 	}
+	pub fn cli() -> Self {
+		Self::self_closing()
+	}
 }
 
 enum JSONMLValue {
@@ -29,6 +33,8 @@ enum JSONMLValue {
 		children: Vec<JSONMLValue>,
 	},
 	String(String),
+	/// `["!CDATA", text]`
+	CData(String),
 }
 impl Typed for JSONMLValue {
 	const TYPE: &'static ComplexValType = &ComplexValType::Simple(ValType::Arr);
@@ -54,6 +60,18 @@ impl Typed for JSONMLValue {
 		)
 		.description("parsing JSONML tag")?;
 
+		if tag == "!CDATA" {
+			if arr.len() != 2 {
+				bail!(
+					"JSONML CDATA should have exactly one child, got {}",
+					arr.len() - 1
+				);
+			}
+			let text = String::from_untyped(arr.get(1)?.expect("length checked"))
+				.description("parsing JSONML CDATA text")?;
+			return Ok(Self::CData(text));
+		}
+
 		let (has_attrs, attrs) = if arr.len() >= 2 {
 			let maybe_attrs = arr
 				.get(1)
@@ -139,9 +157,20 @@ fn manifest_jsonml(v: &JSONMLValue, buf: &mut String, opts: &XmlJsonmlFormat) ->
 			escape_string_xml_buf(s, buf);
 			Ok(())
 		}
+		JSONMLValue::CData(s) => {
+			buf.push_str("<![CDATA[");
+			// CDATA can't contain its terminator, split it between two sections
+			buf.push_str(&s.replace("]]>", "]]]]><![CDATA[>"));
+			buf.push_str("]]>");
+			Ok(())
+		}
 	}
 }
 
+/// Escapes all five predefined XML entities (`&`, `<`, `>`, `"`, `'`), so the output is safe to use
+/// both in text and in attribute values.
+///
+/// Control characters are kept as-is, same as in go-jsonnet.
 pub fn escape_string_xml(str: &str) -> String {
 	let mut out = String::new();
 	escape_string_xml_buf(str, &mut out);
@@ -28,21 +28,40 @@ pub struct YamlFormat<'s> {
 	/// safe_key: 1
 	/// ```
 	quote_keys: bool,
+	/// Write nested values inline, i.e
+	/// ```yaml
+	/// {a: 1, b: [2, 3]}
+	/// ```
+	flow_style: bool,
 	/// If true - then order of fields is preserved as written,
 	/// instead of sorting alphabetically
 	#[cfg(feature = "exp-preserve-order")]
 	preserve_order: bool,
 }
-impl YamlFormat<'_> {
+impl<'s> YamlFormat<'s> {
 	pub fn cli(
 		padding: usize,
 		#[cfg(feature = "exp-preserve-order")] preserve_order: bool,
 	) -> Self {
-		let padding = " ".repeat(padding);
+		Self::cli_indent(
+			" ".repeat(padding),
+			#[cfg(feature = "exp-preserve-order")]
+			preserve_order,
+		)
+	}
+	/// Same as [`Self::cli`], but indents with arbitrary string
+	///
+	/// Note that YAML doesn't allow tabs in indentation
+	pub fn cli_indent(
+		indent: impl Into<Cow<'s, str>>,
+		#[cfg(feature = "exp-preserve-order")] preserve_order: bool,
+	) -> Self {
+		let indent = indent.into();
 		Self {
-			padding: Cow::Owned(padding.clone()),
-			arr_element_padding: Cow::Owned(padding),
+			padding: indent.clone(),
+			arr_element_padding: indent,
 			quote_keys: false,
+			flow_style: false,
 			#[cfg(feature = "exp-preserve-order")]
 			preserve_order,
 		}
@@ -56,13 +75,23 @@ impl YamlFormat<'_> {
 			padding: Cow::Borrowed("  "),
 			arr_element_padding: Cow::Borrowed(if indent_array_in_object { "  " } else { "" }),
 			quote_keys,
+			flow_style: false,
 			#[cfg(feature = "exp-preserve-order")]
 			preserve_order,
 		}
 	}
+	/// Write nested values inline, i.e `{a: 1, b: [2, 3]}`
+	#[must_use]
+	pub fn with_flow_style(mut self, flow_style: bool) -> Self {
+		self.flow_style = flow_style;
+		self
+	}
 }
 impl ManifestFormat for YamlFormat<'_> {
 	fn manifest_buf(&self, val: Val, buf: &mut String) -> Result<()> {
+		if self.flow_style {
+			return manifest_yaml_flow_buf(&val, buf, self);
+		}
 		manifest_yaml_ex_buf(&val, buf, &mut String::new(), self)
 	}
 }
@@ -99,6 +128,28 @@ fn yaml_needs_quotes(string: &str) -> bool {
 		|| string.parse::<f64>().is_ok()
 }
 
+/// Can multiline string be written as `|`/`|-` block scalar without changing its value
+fn yaml_block_scalar_safe(string: &str) -> bool {
+	// Single trailing newline is handled by `|`, more need `|+` chomping
+	let body = string.strip_suffix('\n').unwrap_or(string);
+	!body.ends_with('\n')
+		// Leading whitespace would require explicit indentation indicator
+		&& !body.starts_with([' ', '\t'])
+		&& !body.contains(|c: char| c != '\n' && c != '\t' && c.is_control())
+		&& body
+			.split('\n')
+			.all(|line| !line.ends_with([' ', '\t']) && !line.starts_with('\t'))
+}
+
+/// Writes single-line string, either plain or double-quoted
+fn write_yaml_str(s: &str, buf: &mut String, options: &YamlFormat<'_>) {
+	if !options.quote_keys && !yaml_needs_quotes(s) {
+		buf.push_str(s);
+	} else {
+		escape_string_json_buf(s, buf);
+	}
+}
+
 #[allow(dead_code)]
 fn manifest_yaml_ex(val: &Val, options: &YamlFormat<'_>) -> Result<String> {
 	let mut out = String::new();
@@ -126,6 +177,8 @@ fn manifest_yaml_ex_buf(
 			let s = s.clone().into_flat();
 			if s.is_empty() {
 				buf.push_str("\"\"");
+			} else if s.contains('\n') && !yaml_block_scalar_safe(&s) {
+				escape_string_json_buf(&s, buf);
 			} else if let Some(s) = s.strip_suffix('\n') {
 				buf.push('|');
 				for line in s.split('\n') {
@@ -142,10 +195,8 @@ fn manifest_yaml_ex_buf(
 					buf.push_str(&options.padding);
 					buf.push_str(line);
 				}
-			} else if !options.quote_keys && !yaml_needs_quotes(&s) {
-				buf.push_str(&s);
 			} else {
-				escape_string_json_buf(&s, buf);
+				write_yaml_str(&s, buf, options);
 			}
 		}
 		Val::Num(n) => write!(buf, "{}", *n).unwrap(),
@@ -203,11 +254,7 @@ fn manifest_yaml_ex_buf(
 					buf.push('\n');
 					buf.push_str(cur_padding);
 				}
-				if !options.quote_keys && !yaml_needs_quotes(&key) {
-					buf.push_str(&key);
-				} else {
-					escape_string_json_buf(&key, buf);
-				}
+				write_yaml_str(&key, buf, options);
 				buf.push(':');
 				let prev_len = cur_padding.len();
 				match &value {
@@ -239,3 +286,61 @@ fn manifest_yaml_ex_buf(
 	}
 	Ok(())
 }
+
+/// Flow style is a superset of JSON, so the structure is written the same way as with
+/// [`jrsonnet_evaluator::manifest::JsonFormat`], but strings are only quoted when YAML requires it
+fn manifest_yaml_flow_buf(val: &Val, buf: &mut String, options: &YamlFormat<'_>) -> Result<()> {
+	match val {
+		Val::Str(s) => {
+			let s = s.clone().into_flat();
+			// Block scalars can't be used in flow collections
+			if s.contains('\n') {
+				escape_string_json_buf(&s, buf);
+			} else {
+				write_yaml_str(&s, buf, options);
+			}
+		}
+		Val::Arr(a) => {
+			buf.push('[');
+			for (i, item) in a.iter().enumerate() {
+				let item = item.with_description(|| format!("elem <{i}> evaluation"))?;
+				if i != 0 {
+					buf.push_str(", ");
+				}
+				in_description_frame(
+					|| format!("elem <{i}> manifestification"),
+					|| manifest_yaml_flow_buf(&item, buf, options),
+				)?;
+			}
+			buf.push(']');
+		}
+		Val::Obj(o) => {
+			buf.push('{');
+			for (i, (key, value)) in o
+				.iter(
+					#[cfg(feature = "exp-preserve-order")]
+					options.preserve_order,
+				)
+				.enumerate()
+			{
+				let value = value.with_description(|| format!("field <{key}> evaluation"))?;
+				if i != 0 {
+					buf.push_str(", ");
+				}
+				write_yaml_str(&key, buf, options);
+				buf.push_str(": ");
+				in_description_frame(
+					|| format!("field <{key}> manifestification"),
+					|| manifest_yaml_flow_buf(&value, buf, options),
+				)?;
+			}
+			buf.push('}');
+		}
+		Val::Bool(_) | Val::Null | Val::Num(_) | Val::Func(_) => {
+			manifest_yaml_ex_buf(val, buf, &mut String::new(), options)?;
+		}
+		#[cfg(feature = "exp-bigint")]
+		Val::BigInt(_) => manifest_yaml_ex_buf(val, buf, &mut String::new(), options)?,
+	}
+	Ok(())
+}
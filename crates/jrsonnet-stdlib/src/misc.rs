@@ -1,9 +1,13 @@
-use std::{cell::RefCell, collections::BTreeSet, rc::Rc};
+use std::{
+	cell::RefCell,
+	collections::{BTreeSet, HashMap},
+	rc::Rc,
+};
 
 use jrsonnet_evaluator::{
 	bail,
 	error::{ErrorKind::*, Result},
-	function::{builtin, ArgLike, CallLocation, FuncVal},
+	function::{builtin, ArgLike, CallLocation, FuncVal, TlaArg},
 	manifest::JsonFormat,
 	typed::{Either2, Either4},
 	val::{equals, ArrValue},
@@ -23,6 +27,27 @@ pub fn builtin_length(x: Either![IStr, ArrValue, ObjValue, FuncVal]) -> usize {
 	}
 }
 
+/// Array of `{name, hasDefault}` objects, one per parameter of `func`.
+///
+/// Fails for functions with unnamed parameters, i.e destructured ones, or ones of native closures,
+/// as they can't be passed by name.
+#[builtin]
+pub fn builtin_function_params(func: FuncVal) -> Result<ArrValue> {
+	let mut out = Vec::new();
+	for param in func.params() {
+		let Some(name) = param.name().as_str() else {
+			bail!("function {} has unnamed parameters", func.name());
+		};
+		let mut param_obj = ObjValueBuilder::with_capacity(2);
+		param_obj.field("name").value(Val::string(name));
+		param_obj
+			.field("hasDefault")
+			.value(Val::Bool(param.has_default()));
+		out.push(Val::Obj(param_obj.build()));
+	}
+	Ok(out.into())
+}
+
 #[builtin]
 pub fn builtin_get(
 	o: ObjValue,
@@ -48,14 +73,14 @@ pub fn builtin_get(
 
 #[builtin(fields(
 	settings: Rc<RefCell<Settings>>,
+	/// Consulted before [`Settings::ext_vars`], see [`crate::ExtVarOverrides`]
+	overrides: Rc<HashMap<IStr, TlaArg>>,
 ))]
 pub fn builtin_ext_var(this: &builtin_ext_var, ctx: Context, x: IStr) -> Result<Val> {
 	let ctx = ctx.state().create_default_context(extvar_source(&x, ""));
-	this.settings
-		.borrow()
-		.ext_vars
-		.get(&x)
-		.cloned()
+	let value = this.overrides.get(&x).cloned();
+	value
+		.or_else(|| this.settings.borrow().ext_vars.get(&x).cloned())
 		.ok_or_else(|| UndefinedExternalVariable(x))?
 		.evaluate_arg(ctx, true)?
 		.evaluate()
@@ -93,6 +118,60 @@ pub fn builtin_trace(
 	rest.map_or_else(|| Ok(str), |rest| rest.evaluate())
 }
 
+/// Manifested values longer than this are truncated in `std.traceLabeled` output
+const TRACE_LABELED_MAX_LEN: usize = 1024;
+
+/// Collects up to `left` chars, then fails the write, so the manifestification is stopped early
+struct TruncatingWriter {
+	buf: String,
+	left: usize,
+	truncated: bool,
+}
+impl std::fmt::Write for TruncatingWriter {
+	fn write_str(&mut self, s: &str) -> std::fmt::Result {
+		if let Some((end, _)) = s.char_indices().nth(self.left) {
+			self.buf.push_str(&s[..end]);
+			self.truncated = true;
+			return Err(std::fmt::Error);
+		}
+		self.left -= s.chars().count();
+		self.buf.push_str(s);
+		Ok(())
+	}
+}
+
+#[builtin(fields(
+	settings: Rc<RefCell<Settings>>,
+))]
+pub fn builtin_trace_labeled(
+	this: &builtin_trace_labeled,
+	loc: CallLocation,
+	label: IStr,
+	val: Val,
+) -> Result<Val> {
+	let mut out = TruncatingWriter {
+		buf: String::new(),
+		left: TRACE_LABELED_MAX_LEN,
+		truncated: false,
+	};
+	match val.manifest_to(
+		&mut out,
+		JsonFormat::minify(
+			#[cfg(feature = "exp-preserve-order")]
+			false,
+		),
+	) {
+		Ok(()) => {}
+		Err(_) if out.truncated => out.buf.push_str("..."),
+		Err(e) => return Err(e),
+	}
+	this.settings
+		.borrow()
+		.trace_printer
+		.print_trace(loc, format!("{label}: {}", out.buf).into());
+	Ok(val)
+}
+
 #[allow(clippy::comparison_chain)]
 #[builtin]
 pub fn builtin_starts_with(a: Either![IStr, ArrValue], b: Either![IStr, ArrValue]) -> Result<bool> {
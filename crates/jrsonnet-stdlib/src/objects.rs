@@ -1,7 +1,9 @@
 use jrsonnet_evaluator::{
-	function::builtin,
+	bail,
+	function::{builtin, FuncVal},
+	typed::Typed,
 	val::{ArrValue, Val},
-	IStr, ObjValue, ObjValueBuilder,
+	IStr, ObjValue, ObjValueBuilder, Result, Thunk,
 };
 
 #[builtin]
@@ -53,6 +55,65 @@ pub fn builtin_object_fields_all(
 	)
 }
 
+#[builtin]
+pub fn builtin_object_fields_n(
+	o: ObjValue,
+	n: usize,
+
+	#[default(false)]
+	#[cfg(feature = "exp-preserve-order")]
+	preserve_order: bool,
+) -> Vec<Val> {
+	let out = o.fields_ex_limited(
+		false,
+		n,
+		#[cfg(feature = "exp-preserve-order")]
+		preserve_order,
+	);
+	out.into_iter().map(Val::string).collect::<Vec<_>>()
+}
+
+/// Visible fields in the order of their declaration, unlike `std.objectFields`, which sorts them.
+///
+/// Declaration order is only tracked with `exp-preserve-order` feature, without it this builtin
+/// always fails.
+#[builtin]
+pub fn builtin_object_fields_order(o: ObjValue) -> Result<Vec<Val>> {
+	#[cfg(feature = "exp-preserve-order")]
+	{
+		Ok(builtin_object_fields_ex(o, false, true))
+	}
+	#[cfg(not(feature = "exp-preserve-order"))]
+	{
+		let _ = o;
+		bail!("std.objectFieldsOrder requires jrsonnet to be built with exp-preserve-order feature")
+	}
+}
+
+/// Visible fields, for which `func(key)` is true. Field values are not evaluated
+#[builtin]
+pub fn builtin_object_fields_filter(
+	o: ObjValue,
+	func: FuncVal,
+
+	#[default(false)]
+	#[cfg(feature = "exp-preserve-order")]
+	preserve_order: bool,
+) -> Result<Vec<Val>> {
+	let mut out = Vec::new();
+	for field in o.fields_ex(
+		false,
+		#[cfg(feature = "exp-preserve-order")]
+		preserve_order,
+	) {
+		let key = Val::string(field);
+		if bool::from_untyped(func.evaluate_simple(&(key.clone(),), false)?)? {
+			out.push(key);
+		}
+	}
+	Ok(out)
+}
+
 pub fn builtin_object_values_ex(
 	o: ObjValue,
 	include_hidden: bool,
@@ -96,6 +157,27 @@ pub fn builtin_object_values_all(
 	)
 }
 
+/// Visible field values mapped with `func(value)`, in the same order as `std.objectValues`.
+///
+/// Unlike `std.objectMap`, no object is built, and every element is only evaluated on access.
+#[builtin]
+pub fn builtin_map_object_values(
+	obj: ObjValue,
+	func: FuncVal,
+
+	#[default(false)]
+	#[cfg(feature = "exp-preserve-order")]
+	preserve_order: bool,
+) -> ArrValue {
+	builtin_object_values_ex(
+		obj,
+		false,
+		#[cfg(feature = "exp-preserve-order")]
+		preserve_order,
+	)
+	.map(func)
+}
+
 pub fn builtin_object_keys_values_ex(
 	o: ObjValue,
 	include_hidden: bool,
@@ -139,6 +221,35 @@ pub fn builtin_object_keys_values_all(
 	)
 }
 
+#[derive(Typed)]
+pub struct ObjectEntry {
+	key: IStr,
+	value: Thunk<Val>,
+	hidden: bool,
+}
+
+#[builtin]
+pub fn builtin_object_entries_ex(
+	obj: ObjValue,
+
+	#[default(false)]
+	#[cfg(feature = "exp-preserve-order")]
+	preserve_order: bool,
+) -> Vec<ObjectEntry> {
+	obj.fields_ex(
+		true,
+		#[cfg(feature = "exp-preserve-order")]
+		preserve_order,
+	)
+	.into_iter()
+	.map(|key| ObjectEntry {
+		hidden: !obj.has_field(key.clone()),
+		value: obj.get_lazy_or_bail(key.clone()),
+		key,
+	})
+	.collect()
+}
+
 #[builtin]
 pub fn builtin_object_has_ex(obj: ObjValue, fname: IStr, hidden: bool) -> bool {
 	obj.has_field_ex(fname, hidden)
@@ -178,3 +289,124 @@ pub fn builtin_object_remove_key(
 
 	new_obj.build()
 }
+
+/// Object with visible fields of `a`, which are (or are not, if `common` is false) also visible in `b`.
+///
+/// Values are taken lazily from `a`
+fn object_filter_keys(
+	a: &ObjValue,
+	b: &ObjValue,
+	common: bool,
+	#[cfg(feature = "exp-preserve-order")] preserve_order: bool,
+) -> Result<ObjValue> {
+	let mut out = ObjValueBuilder::new();
+	for key in a.fields(
+		#[cfg(feature = "exp-preserve-order")]
+		preserve_order,
+	) {
+		if b.has_field(key.clone()) != common {
+			continue;
+		}
+		out.field(key.clone()).try_thunk(a.get_lazy_or_bail(key))?;
+	}
+	Ok(out.build())
+}
+
+#[builtin]
+pub fn builtin_object_intersect(
+	a: ObjValue,
+	b: ObjValue,
+
+	#[default(false)]
+	#[cfg(feature = "exp-preserve-order")]
+	preserve_order: bool,
+) -> Result<ObjValue> {
+	object_filter_keys(
+		&a,
+		&b,
+		true,
+		#[cfg(feature = "exp-preserve-order")]
+		preserve_order,
+	)
+}
+
+#[builtin]
+pub fn builtin_object_diff(
+	a: ObjValue,
+	b: ObjValue,
+
+	#[default(false)]
+	#[cfg(feature = "exp-preserve-order")]
+	preserve_order: bool,
+) -> Result<ObjValue> {
+	object_filter_keys(
+		&a,
+		&b,
+		false,
+		#[cfg(feature = "exp-preserve-order")]
+		preserve_order,
+	)
+}
+
+/// Walks `path` starting from `obj`, string segments are used as object field names, and number
+/// segments as array indexes.
+///
+/// Returns `None` if any segment is missing. Indexing a value which is not an object/array (or
+/// using segment of the wrong type) is treated as missing segment too, unless `strict` is set.
+fn lookup_path(obj: ObjValue, path: &ArrValue, strict: bool) -> Result<Option<Val>> {
+	let mut current = Val::Obj(obj);
+	for (i, segment) in path.iter().enumerate() {
+		let next = match (&current, segment?) {
+			(Val::Obj(obj), Val::Str(key)) => obj.get(key.into_flat())?,
+			(Val::Arr(arr), Val::Num(idx)) => {
+				let idx = idx.get();
+				if idx.fract() != 0.0 || idx < 0.0 {
+					None
+				} else {
+					#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+					arr.get(idx as usize)?
+				}
+			}
+			(current, segment) => {
+				if strict {
+					bail!(
+						"can't index {} with {} at path index {i}",
+						current.value_type(),
+						segment.value_type(),
+					);
+				}
+				None
+			}
+		};
+		let Some(next) = next else {
+			return Ok(None);
+		};
+		current = next;
+	}
+	Ok(Some(current))
+}
+
+#[builtin]
+pub fn builtin_get_path(
+	obj: ObjValue,
+	path: ArrValue,
+	default: Option<Thunk<Val>>,
+	#[default(false)] strict: bool,
+) -> Result<Val> {
+	if let Some(v) = lookup_path(obj, &path, strict)? {
+		return Ok(v);
+	}
+	let Some(default) = default else {
+		return Ok(Val::Null);
+	};
+	default.evaluate()
+}
+
+#[builtin]
+pub fn builtin_object_has_path(
+	obj: ObjValue,
+	path: ArrValue,
+	#[default(false)] strict: bool,
+) -> Result<bool> {
+	Ok(lookup_path(obj, &path, strict)?.is_some())
+}
@@ -4,10 +4,11 @@
 use jrsonnet_evaluator::{
 	function::builtin,
 	operator::evaluate_mod_op,
+	stack::check_depth,
 	stdlib::std_format,
 	typed::{Either, Either2},
 	val::{equals, primitive_equals, NumValue},
-	IStr, Result, Val,
+	IStr, ObjValue, Result, Val,
 };
 
 #[builtin]
@@ -32,6 +33,56 @@ pub fn builtin_equals(a: Val, b: Val) -> Result<bool> {
 	equals(&a, &b)
 }
 
+/// Same as [`equals`], but functions are never equal to anything, instead of raising an error.
+///
+/// Numbers are always finite, so there is no NaN to special-case: operations producing it fail before comparison.
+fn deep_equal(a: &Val, b: &Val) -> Result<bool> {
+	let _guard = check_depth()?;
+	Ok(match (a, b) {
+		(Val::Func(_), _) | (_, Val::Func(_)) => false,
+		(Val::Arr(a), Val::Arr(b)) => {
+			if a.len() != b.len() {
+				return Ok(false);
+			}
+			for (a, b) in a.iter().zip(b.iter()) {
+				if !deep_equal(&a?, &b?)? {
+					return Ok(false);
+				}
+			}
+			true
+		}
+		(Val::Obj(a), Val::Obj(b)) => deep_equal_obj(a, b)?,
+		(a, b) => equals(a, b)?,
+	})
+}
+fn deep_equal_obj(a: &ObjValue, b: &ObjValue) -> Result<bool> {
+	let fields = a.fields(
+		#[cfg(feature = "exp-preserve-order")]
+		false,
+	);
+	if fields
+		!= b.fields(
+			#[cfg(feature = "exp-preserve-order")]
+			false,
+		) {
+		return Ok(false);
+	}
+	for field in fields {
+		if !deep_equal(
+			&a.get(field.clone())?.expect("field exists"),
+			&b.get(field)?.expect("field exists"),
+		)? {
+			return Ok(false);
+		}
+	}
+	Ok(true)
+}
+
+#[builtin]
+pub fn builtin_deep_equal(a: Val, b: Val) -> Result<bool> {
+	deep_equal(&a, &b)
+}
+
 #[builtin]
 pub fn builtin_xor(x: bool, y: bool) -> bool {
 	x ^ y
@@ -1,4 +1,4 @@
-use jrsonnet_evaluator::{function::builtin, runtime_error, IStr, Result, Val};
+use jrsonnet_evaluator::{bail, function::builtin, runtime_error, IStr, Result, Val};
 use serde::Deserialize;
 
 #[builtin]
@@ -8,17 +8,42 @@ pub fn builtin_parse_json(str: IStr) -> Result<Val> {
 	Ok(value)
 }
 
+/// Jsonnet has a single number type, so int/float distinction of YAML scalars is not preserved:
+/// `1.0` and `1` are parsed to the same value, which is manifested back as `1`.
+/// Non-integral numbers survive `std.manifestYamlDoc(std.parseYaml(x))` round-trip unchanged.
+///
+/// Duplicate mapping keys are allowed by default, with the last value winning, `strict` makes
+/// them an error.
 #[builtin]
-pub fn builtin_parse_yaml(str: IStr) -> Result<Val> {
+pub fn builtin_parse_yaml(str: IStr, #[default(false)] strict: bool) -> Result<Val> {
 	use serde_yaml_with_quirks::DeserializingQuirks;
+	if strict {
+		if let Some((key, mark)) = find_duplicate_yaml_key(&str) {
+			bail!(
+				"failed to parse yaml: duplicate key {key:?} at line {} column {}",
+				mark.line(),
+				mark.col() + 1,
+			);
+		}
+	}
 	let value = serde_yaml_with_quirks::Deserializer::from_str_with_quirks(
 		&str,
 		DeserializingQuirks { old_octals: true },
 	);
 	let mut out = vec![];
 	for item in value {
-		let val =
-			Val::deserialize(item).map_err(|e| runtime_error!("failed to parse yaml: {e}"))?;
+		let val = Val::deserialize(item).map_err(|e| {
+			// Deserializer expands aliases, and fails on recursive ones with confusing recursion
+			// limit error. Jsonnet values are acyclic, so such documents can't be parsed anyway.
+			if let Some(mark) = find_recursive_yaml_alias(&str) {
+				return runtime_error!(
+					"failed to parse yaml: recursive alias at line {} column {}",
+					mark.line(),
+					mark.col() + 1,
+				);
+			}
+			runtime_error!("failed to parse yaml: {e}")
+		})?;
 		out.push(val);
 	}
 	Ok(if out.is_empty() {
@@ -29,3 +54,228 @@ pub fn builtin_parse_yaml(str: IStr) -> Result<Val> {
 		Val::Arr(out.into())
 	})
 }
+
+/// Finds alias, which refers to anchored node containing this alias
+fn find_recursive_yaml_alias(str: &str) -> Option<yaml_rust::scanner::Marker> {
+	use yaml_rust::{
+		parser::{Event, MarkedEventReceiver, Parser},
+		scanner::Marker,
+	};
+
+	#[derive(Default)]
+	struct Receiver {
+		/// Anchors of currently open collections, `0` for collections without anchor
+		open: Vec<usize>,
+		found: Option<Marker>,
+	}
+	impl MarkedEventReceiver for Receiver {
+		fn on_event(&mut self, ev: Event, mark: Marker) {
+			match ev {
+				Event::SequenceStart(anchor) | Event::MappingStart(anchor) => {
+					self.open.push(anchor);
+				}
+				Event::SequenceEnd | Event::MappingEnd => {
+					self.open.pop();
+				}
+				Event::Alias(anchor) if self.found.is_none() && self.open.contains(&anchor) => {
+					self.found = Some(mark);
+				}
+				_ => {}
+			}
+		}
+	}
+
+	let mut receiver = Receiver::default();
+	// Syntax errors are reported by deserializer
+	let _ = Parser::new(str.chars()).load(&mut receiver, true);
+	receiver.found
+}
+
+/// Finds the first mapping key, which was already defined in the same mapping
+fn find_duplicate_yaml_key(str: &str) -> Option<(String, yaml_rust::scanner::Marker)> {
+	use std::collections::HashSet;
+
+	use yaml_rust::{
+		parser::{Event, MarkedEventReceiver, Parser},
+		scanner::Marker,
+	};
+
+	enum Collection {
+		Sequence,
+		Mapping {
+			expecting_key: bool,
+			keys: HashSet<String>,
+		},
+	}
+
+	#[derive(Default)]
+	struct Receiver {
+		open: Vec<Collection>,
+		found: Option<(String, Marker)>,
+	}
+	impl Receiver {
+		/// Called for every node, returns `true` if node is a mapping key
+		fn on_node(&mut self) -> bool {
+			match self.open.last_mut() {
+				Some(Collection::Mapping { expecting_key, .. }) => {
+					let is_key = *expecting_key;
+					*expecting_key = !is_key;
+					is_key
+				}
+				_ => false,
+			}
+		}
+	}
+	impl MarkedEventReceiver for Receiver {
+		fn on_event(&mut self, ev: Event, mark: Marker) {
+			if self.found.is_some() {
+				return;
+			}
+			match ev {
+				Event::Scalar(value, ..) => {
+					if !self.on_node() {
+						return;
+					}
+					let Some(Collection::Mapping { keys, .. }) = self.open.last_mut() else {
+						unreachable!("keys only exist in mappings");
+					};
+					if !keys.insert(value.clone()) {
+						self.found = Some((value, mark));
+					}
+				}
+				// Complex and aliased keys are not checked
+				Event::Alias(_) => {
+					self.on_node();
+				}
+				Event::SequenceStart(_) => {
+					self.on_node();
+					self.open.push(Collection::Sequence);
+				}
+				Event::MappingStart(_) => {
+					self.on_node();
+					self.open.push(Collection::Mapping {
+						expecting_key: true,
+						keys: HashSet::new(),
+					});
+				}
+				Event::SequenceEnd | Event::MappingEnd => {
+					self.open.pop();
+				}
+				_ => {}
+			}
+		}
+	}
+
+	let mut receiver = Receiver::default();
+	// Syntax errors are reported by deserializer
+	let _ = Parser::new(str.chars()).load(&mut receiver, true);
+	receiver.found
+}
+
+/// Strips `//` and `/* */` comments, and trailing commas in arrays/objects, so the result may be
+/// parsed as plain JSON.
+///
+/// String literals are kept as-is, even if they contain comment-like sequences.
+#[cfg(feature = "exp-json5")]
+fn strip_json5(input: &str) -> Result<String> {
+	fn drop_trailing_comma(out: &mut String) {
+		let trimmed = out.trim_end().len();
+		if out[..trimmed].ends_with(',') {
+			out.remove(trimmed - 1);
+		}
+	}
+
+	let mut out = String::with_capacity(input.len());
+	let mut chars = input.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'"' => {
+				out.push(c);
+				while let Some(c) = chars.next() {
+					out.push(c);
+					match c {
+						'\\' => {
+							if let Some(escaped) = chars.next() {
+								out.push(escaped);
+							}
+						}
+						'"' => break,
+						_ => {}
+					}
+				}
+			}
+			'/' if chars.peek() == Some(&'/') => {
+				for c in chars.by_ref() {
+					if c == '\n' {
+						out.push(c);
+						break;
+					}
+				}
+			}
+			'/' if chars.peek() == Some(&'*') => {
+				chars.next();
+				let mut prev = None;
+				loop {
+					let Some(c) = chars.next() else {
+						bail!("failed to parse json5: unterminated block comment");
+					};
+					if prev == Some('*') && c == '/' {
+						break;
+					}
+					prev = Some(c);
+				}
+				out.push(' ');
+			}
+			']' | '}' => {
+				drop_trailing_comma(&mut out);
+				out.push(c);
+			}
+			_ => out.push(c),
+		}
+	}
+	Ok(out)
+}
+
+/// Same as [`builtin_parse_json`], but also accepts comments and trailing commas
+#[cfg(feature = "exp-json5")]
+#[builtin]
+pub fn builtin_parse_json5(str: IStr) -> Result<Val> {
+	let stripped = strip_json5(&str)?;
+	let value: Val = serde_json::from_str(&stripped)
+		.map_err(|e| runtime_error!("failed to parse json5: {e}"))?;
+	Ok(value)
+}
+
+#[cfg(all(test, feature = "exp-json5"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn strip_comments() {
+		assert_eq!(
+			strip_json5("{\"a\": 1, // line\n/* block */ \"b\": 2}").unwrap(),
+			"{\"a\": 1, \n  \"b\": 2}"
+		);
+	}
+
+	#[test]
+	fn keep_comments_in_strings() {
+		let input = r#"{"url": "http://example.com/*x*/", "esc": "\"//"}"#;
+		assert_eq!(strip_json5(input).unwrap(), input);
+	}
+
+	#[test]
+	fn strip_trailing_commas() {
+		assert_eq!(strip_json5("[1, 2, ]").unwrap(), "[1, 2 ]");
+		assert_eq!(
+			strip_json5("{\"a\": [1,], // c\n}").unwrap(),
+			"{\"a\": [1] \n}"
+		);
+		assert_eq!(strip_json5("[\",]\"]").unwrap(), "[\",]\"]");
+	}
+
+	#[test]
+	fn unterminated_comment() {
+		assert!(strip_json5("[1 /* ").is_err());
+	}
+}
@@ -8,6 +8,8 @@ use jrsonnet_evaluator::{
 };
 use jrsonnet_parser::BinaryOpType;
 
+/// Binary search, `arr` should be sorted by `keyF` (e.g produced by `std.set` with the same `keyF`),
+/// otherwise result is unspecified. Use `std.member` for unsorted arrays.
 #[builtin]
 #[allow(non_snake_case)]
 pub fn builtin_set_member(x: Thunk<Val>, arr: ArrValue, keyF: Option<FuncVal>) -> Result<bool> {
@@ -39,13 +39,14 @@ fn get_sort_type<T>(values: &[T], key_getter: impl Fn(&T) -> &Val) -> Result<Sor
 
 fn sort_identity(mut values: Vec<Val>) -> Result<Vec<Val>> {
 	// Fast path, identity key getter
+	// Stable sort is used even there, as equal values might still be distinguishable (`0` and `-0`)
 	let sort_type = get_sort_type(&values, |k| k)?;
 	match sort_type {
-		SortKeyType::Number => values.sort_unstable_by_key(|v| match v {
+		SortKeyType::Number => values.sort_by_key(|v| match v {
 			Val::Num(n) => *n,
 			_ => unreachable!(),
 		}),
-		SortKeyType::String => values.sort_unstable_by_key(|v| match v {
+		SortKeyType::String => values.sort_by_key(|v| match v {
 			Val::Str(s) => s.clone(),
 			_ => unreachable!(),
 		}),
@@ -53,7 +54,7 @@ fn sort_identity(mut values: Vec<Val>) -> Result<Vec<Val>> {
 			let mut err = None;
 			// evaluate_compare_op will never return equal on types, which are different from
 			// jsonnet perspective
-			values.sort_unstable_by(|a, b| match evaluate_compare_op(a, b, BinaryOpType::Lt) {
+			values.sort_by(|a, b| match evaluate_compare_op(a, b, BinaryOpType::Lt) {
 				Ok(ord) => ord,
 				Err(e) if err.is_none() => {
 					let _ = err.insert(e);
@@ -110,7 +111,9 @@ fn sort_keyf(values: ArrValue, keyf: FuncVal) -> Result<Vec<Thunk<Val>>> {
 	Ok(vk.into_iter().map(|v| v.0).collect())
 }
 
-/// * `key_getter` - None, if identity sort required
+/// Sorts array by keys returned from `key_getter`
+///
+/// Sort is stable: elements with equal keys are kept in the same order as in input array
 pub fn sort(values: ArrValue, key_getter: FuncVal) -> Result<ArrValue> {
 	if values.len() <= 1 {
 		return Ok(values);
@@ -124,6 +127,7 @@ pub fn sort(values: ArrValue, key_getter: FuncVal) -> Result<ArrValue> {
 	}
 }
 
+/// `std.sort`, is stable, see [`sort`]
 #[builtin]
 pub fn builtin_sort(
 	arr: ArrValue,
@@ -38,6 +38,21 @@ pub fn builtin_escape_string_bash(str_: String) -> String {
 	out
 }
 
+/// Quotes every argument for POSIX shell, and joins them with spaces, result is suitable for `sh -c`
+#[builtin]
+pub fn builtin_escape_string_shell_argv(argv: Vec<String>) -> String {
+	let mut out = String::new();
+	for (i, arg) in argv.iter().enumerate() {
+		if i != 0 {
+			out.push(' ');
+		}
+		out.push('\'');
+		out.push_str(&arg.replace('\'', "'\\''"));
+		out.push('\'');
+	}
+	out
+}
+
 #[builtin]
 pub fn builtin_escape_string_dollars(str_: String) -> String {
 	str_.replace('$', "$$")
@@ -87,6 +102,17 @@ pub fn builtin_split(str: IStr, c: IStr) -> ArrValue {
 	builtin_splitlimit(str, c, B(M1))
 }
 
+/// Splits `str` on the first occurrence of `c`, returns `null` if there is no such occurrence
+#[builtin]
+pub fn builtin_split_once(str: IStr, c: IStr) -> Result<Option<ArrValue>> {
+	if c.is_empty() {
+		bail!("separator can't be empty")
+	}
+	Ok(str
+		.split_once(&c as &str)
+		.map(|(before, after)| vec![Val::string(before), Val::string(after)].into()))
+}
+
 #[builtin]
 pub fn builtin_ascii_upper(str: IStr) -> String {
 	str.to_ascii_uppercase()
@@ -224,6 +250,19 @@ pub fn builtin_string_chars(str: IStr) -> ArrValue {
 	ArrValue::chars(str.chars())
 }
 
+/// Splits string into extended grapheme clusters, unlike `std.stringChars`, which splits it into
+/// codepoints.
+#[cfg(feature = "exp-unicode")]
+#[builtin]
+pub fn builtin_string_graphemes(str: IStr) -> ArrValue {
+	use unicode_segmentation::UnicodeSegmentation;
+
+	str.graphemes(true)
+		.map(Val::string)
+		.collect::<Vec<_>>()
+		.into()
+}
+
 #[builtin]
 pub fn builtin_lstrip_chars(str: IStr, chars: IndexableVal) -> Result<IStr> {
 	if str.is_empty() || chars.is_empty() {
@@ -294,4 +333,21 @@ mod tests {
 		assert_eq!(parse_nat::<16>("a9").unwrap(), f64::from(0xA9));
 		assert_eq!(parse_nat::<16>("BbC").unwrap(), f64::from(0xBBC));
 	}
+
+	#[cfg(feature = "exp-unicode")]
+	#[test]
+	fn string_graphemes() {
+		// Man, woman, girl, boy joined with ZWJ
+		let family: IStr = "a\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}b".into();
+		assert_eq!(builtin_string_chars(family.clone()).len(), 9);
+		let graphemes = builtin_string_graphemes(family);
+		assert_eq!(graphemes.len(), 3);
+		let Some(Val::Str(family)) = graphemes.get(1).unwrap() else {
+			panic!("expected string");
+		};
+		assert_eq!(
+			family.to_string(),
+			"\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}"
+		);
+	}
 }
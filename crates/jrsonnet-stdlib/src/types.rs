@@ -1,10 +1,54 @@
-use jrsonnet_evaluator::{function::builtin, IStr, Val};
+use std::fmt::Write;
+
+use jrsonnet_evaluator::{
+	bail,
+	error::ErrorKind,
+	function::builtin,
+	stack::check_depth,
+	typed::{CheckType, ComplexValType, ValType},
+	val::ArrValue,
+	IStr, ObjValue, ObjValueBuilder, Result, Val,
+};
 
 #[builtin]
 pub fn builtin_type(x: Val) -> IStr {
 	x.value_type().name().into()
 }
 
+/// Type name of every element, as returned by `std.type`
+#[builtin]
+pub fn builtin_types(arr: ArrValue) -> Result<Vec<IStr>> {
+	arr.iter()
+		.map(|v| Ok(v?.value_type().name().into()))
+		.collect()
+}
+
+/// Object with the same visible fields, mapped to the type names of their values
+#[builtin]
+pub fn builtin_types_object(obj: ObjValue) -> Result<ObjValue> {
+	let mut out = ObjValueBuilder::new();
+	for (k, v) in obj.iter(
+		#[cfg(feature = "exp-preserve-order")]
+		true,
+	) {
+		out.field(k).value(Val::string(v?.value_type().name()));
+	}
+	Ok(out.build())
+}
+
+#[builtin]
+pub fn builtin_is_type(val: Val, typename: IStr) -> Result<bool> {
+	let Some(ty) = ValType::ALL.iter().find(|ty| ty.name() == &*typename) else {
+		let valid = ValType::ALL
+			.iter()
+			.map(ValType::name)
+			.collect::<Vec<_>>()
+			.join(", ");
+		bail!("unknown type name {typename:?}, expected one of: {valid}");
+	};
+	Ok(val.value_type() == *ty)
+}
+
 #[builtin]
 pub fn builtin_is_string(v: Val) -> bool {
 	matches!(v, Val::Str(_))
@@ -29,3 +73,102 @@ pub fn builtin_is_array(v: Val) -> bool {
 pub fn builtin_is_function(v: Val) -> bool {
 	matches!(v, Val::Func(_))
 }
+
+fn shape_type(name: &str) -> Result<ComplexValType> {
+	if name == "any" {
+		return Ok(ComplexValType::Any);
+	}
+	let Some(ty) = ValType::ALL.iter().find(|ty| ty.name() == name) else {
+		let valid = ValType::ALL
+			.iter()
+			.map(ValType::name)
+			.collect::<Vec<_>>()
+			.join(", ");
+		bail!("unknown type name {name:?} in shape, expected any or one of: {valid}");
+	};
+	Ok((*ty).into())
+}
+
+/// Checks value against type, type errors are collected, other errors are returned
+fn check_shape_type(
+	ty: &ComplexValType,
+	val: &Val,
+	path: &str,
+	errors: &mut Vec<String>,
+) -> Result<bool> {
+	match ty.check(val) {
+		Ok(()) => Ok(true),
+		Err(e) => match e.error() {
+			ErrorKind::TypeError(e) => {
+				errors.push(format!("{e} at {path}"));
+				Ok(false)
+			}
+			_ => Err(e),
+		},
+	}
+}
+
+fn check_shape(val: &Val, shape: &Val, path: &mut String, errors: &mut Vec<String>) -> Result<()> {
+	// Both value and shape might be recursive
+	let _guard = check_depth()?;
+	match shape {
+		Val::Str(name) => {
+			check_shape_type(&shape_type(&name.clone().into_flat())?, val, path, errors)?;
+		}
+		Val::Obj(shape) => {
+			if !check_shape_type(&ValType::Obj.into(), val, path, errors)? {
+				return Ok(());
+			}
+			let Val::Obj(obj) = val else {
+				unreachable!("checked above");
+			};
+			for (field, field_shape) in shape.iter(
+				#[cfg(feature = "exp-preserve-order")]
+				true,
+			) {
+				let field_shape = field_shape?;
+				let len = path.len();
+				write!(path, ".{:?}", &*field).expect("writing to string");
+				match obj.get(field)? {
+					Some(field) => check_shape(&field, &field_shape, path, errors)?,
+					None => errors.push(format!("missing field at {path}")),
+				}
+				path.truncate(len);
+			}
+		}
+		Val::Arr(shape) if shape.len() == 1 => {
+			if !check_shape_type(&ValType::Arr.into(), val, path, errors)? {
+				return Ok(());
+			}
+			let Val::Arr(arr) = val else {
+				unreachable!("checked above");
+			};
+			let elem_shape = shape.get(0)?.expect("length is checked");
+			for (i, elem) in arr.iter().enumerate() {
+				let len = path.len();
+				write!(path, "[{i}]").expect("writing to string");
+				check_shape(&elem?, &elem_shape, path, errors)?;
+				path.truncate(len);
+			}
+		}
+		_ => bail!(
+			"shape should be a type name, an object of shapes, or an array with a single element shape, got {}",
+			shape.value_type()
+		),
+	}
+	Ok(())
+}
+
+/// Asserts that value matches the shape, and returns `true`
+///
+/// Shape is either a type name (as returned by `std.type`, or `any`), an object with shapes of the required fields,
+/// or an array with a single shape, which every element should match. All mismatches are reported at once.
+#[builtin]
+pub fn builtin_assert_shape(val: Val, shape: Val) -> Result<bool> {
+	let mut errors = Vec::new();
+	check_shape(&val, &shape, &mut "self".to_owned(), &mut errors)?;
+	if !errors.is_empty() {
+		bail!("value does not match shape:\n  - {}", errors.join("\n  - "));
+	}
+	Ok(true)
+}
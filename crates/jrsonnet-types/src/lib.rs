@@ -18,6 +18,19 @@ pub enum ValType {
 }
 
 impl ValType {
+	/// Every value type, in the order of declaration
+	pub const ALL: &'static [Self] = &[
+		Self::Bool,
+		Self::Null,
+		Self::Str,
+		Self::Num,
+		#[cfg(feature = "exp-bigint")]
+		Self::BigInt,
+		Self::Arr,
+		Self::Obj,
+		Self::Func,
+	];
+
 	pub const fn name(&self) -> &'static str {
 		use ValType::*;
 		match self {
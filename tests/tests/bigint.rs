@@ -0,0 +1,77 @@
+#![cfg(feature = "exp-bigint")]
+
+use jrsonnet_evaluator::{trace::PathResolver, Result, State, Val};
+use jrsonnet_stdlib::ContextInitializer;
+
+mod common;
+
+fn state() -> State {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::Absolute));
+	s.build()
+}
+
+/// Bigints are compared in jsonnet, as they are manifested as strings
+fn eval_eq(s: &State, code: &str, expected: &str) -> Result<()> {
+	let v = s.evaluate_snippet("snip", format!("({code}) == std.bigint('{expected}')"))?;
+	ensure_val_eq!(v, Val::Bool(true));
+	Ok(())
+}
+
+#[test]
+fn bigint_exact_multiplication() -> Result<()> {
+	let s = state();
+	// 2^64 * 2^64 = 2^128, f64 can't hold it exactly
+	eval_eq(
+		&s,
+		"std.bigint('18446744073709551616') * std.bigint('18446744073709551616')",
+		"340282366920938463463374607431768211456",
+	)?;
+	eval_eq(
+		&s,
+		"std.bigint('99999999999999999999') * 99999999999",
+		"9999999999899999999900000000001",
+	)?;
+	eval_eq(
+		&s,
+		"std.bigint('340282366920938463463374607431768211457') - 1",
+		"340282366920938463463374607431768211456",
+	)?;
+	Ok(())
+}
+
+#[test]
+fn bigint_arithmetic() -> Result<()> {
+	let s = state();
+	eval_eq(&s, "std.bigint(5) + std.bigint(7)", "12")?;
+	eval_eq(&s, "10 + std.bigint(7)", "17")?;
+	eval_eq(&s, "std.bigint(5) - 7", "-2")?;
+	// Division and modulo are truncating
+	eval_eq(&s, "std.bigint(-7) / 2", "-3")?;
+	eval_eq(&s, "std.bigint(-7) % 2", "-1")?;
+	eval_eq(&s, "std.bigint(7) % std.bigint(-2)", "1")?;
+	// Integral floats outside of i64 range are converted exactly
+	eval_eq(&s, "std.bigint(0) + 1e20", "100000000000000000000")?;
+	Ok(())
+}
+
+#[test]
+fn bigint_arithmetic_errors() -> Result<()> {
+	let s = state();
+	for code in [
+		"std.bigint(1) / 0",
+		"std.bigint(1) % std.bigint(0)",
+		"1 / std.bigint(0)",
+	] {
+		let err = s.evaluate_snippet("snip", code).expect_err(code);
+		ensure_eq!(err.error().to_string(), "attempted to divide by zero");
+	}
+	let err = s
+		.evaluate_snippet("snip", "std.bigint(1) + 0.5")
+		.expect_err("float is mixed with bigint");
+	ensure_eq!(
+		err.error().to_string(),
+		"runtime error: can't mix bigint with non-integral number 0.5",
+	);
+	Ok(())
+}
@@ -1,8 +1,12 @@
 mod common;
 
+use std::cell::Cell;
+
 use jrsonnet_evaluator::{
+	error::ErrorKind,
 	function::{builtin, builtin::Builtin, CallLocation, FuncVal},
 	parser::Source,
+	stack,
 	trace::PathResolver,
 	typed::Typed,
 	ContextBuilder, ContextInitializer, FileImportResolver, Result, State, Thunk, Val,
@@ -119,3 +123,149 @@ fn nonstatic_builtin() -> Result<()> {
 	ensure_val_eq!(v, Val::Null);
 	Ok(())
 }
+
+#[builtin]
+fn stack_depth() -> usize {
+	stack::current_depth()
+}
+#[derive(Trace)]
+struct StackDepthContextInitializer;
+impl ContextInitializer for StackDepthContextInitializer {
+	fn populate(&self, _for_file: Source, builder: &mut ContextBuilder) {
+		builder.bind(
+			"stackDepth",
+			Thunk::evaluated(Val::function(stack_depth::INST)),
+		);
+	}
+
+	fn as_any(&self) -> &dyn std::any::Any {
+		self
+	}
+}
+
+#[test]
+fn stack_depth_is_visible_to_builtins() -> Result<()> {
+	let mut s = State::builder();
+	s.context_initializer((
+		StdContextInitializer::new(PathResolver::new_cwd_fallback()),
+		StackDepthContextInitializer,
+	))
+	.import_resolver(FileImportResolver::default());
+	let s = s.build();
+
+	ensure_eq!(stack::current_depth(), 0);
+	let v = s.evaluate_snippet(
+		"snip".to_owned(),
+		"
+            local nested(n) = if n == 0 then stackDepth() else nested(n - 1);
+            [nested(0), nested(10)]
+        ",
+	)?;
+	let depths = Vec::<usize>::from_untyped(v)?;
+	ensure!(depths[0] > 0);
+	ensure!(depths[1] >= depths[0] + 10);
+	ensure_eq!(stack::current_depth(), 0);
+	Ok(())
+}
+
+#[test]
+fn builtin_catalog_matches_stdlib() -> Result<()> {
+	let catalog = jrsonnet_stdlib::builtin_catalog();
+
+	let lines = catalog
+		.iter()
+		.find(|b| b.name == "lines")
+		.expect("std.lines is cataloged");
+	let params = lines
+		.params
+		.iter()
+		.map(|p| p.name().as_str().expect("named"))
+		.collect::<Vec<_>>();
+	ensure_eq!(params, vec!["arr", "sep"]);
+	// Builtins depending on stdlib settings are listed too
+	for name in ["extVar", "native", "trace", "join", "repeat", "fill", "id"] {
+		ensure!(catalog.iter().any(|b| b.name == name));
+	}
+
+	let mut s = State::builder();
+	s.context_initializer(StdContextInitializer::new(PathResolver::new_cwd_fallback()));
+	let s = s.build();
+	let v = s.evaluate_snippet(
+		"snip".to_owned(),
+		"std.length([f for f in std.objectFieldsAll(std) if std.isFunction(std[f])])",
+	)?;
+	ensure_val_eq!(v, Val::num(u32::try_from(catalog.len()).expect("fits")));
+	for builtin in &catalog {
+		let v = s.evaluate_snippet(
+			"snip".to_owned(),
+			format!("std.isFunction(std[{:?}])", builtin.name),
+		)?;
+		ensure_val_eq!(v, Val::Bool(true));
+	}
+	Ok(())
+}
+
+#[test]
+fn max_array_len_limits_join() -> Result<()> {
+	let std = StdContextInitializer::new(PathResolver::new_cwd_fallback());
+	std.set_max_array_len(Some(10));
+	let mut s = State::builder();
+	s.context_initializer(std);
+	let s = s.build();
+
+	let v = s.evaluate_snippet("snip".to_owned(), "std.join([0], [[1, 2], [3, 4]])")?;
+	ensure_eq!(Vec::<u32>::from_untyped(v)?, vec![1, 2, 0, 3, 4]);
+
+	let Err(e) = s.evaluate_snippet("snip".to_owned(), "std.join([0, 0], std.repeat([[1]], 5))")
+	else {
+		jrsonnet_evaluator::bail!("join over the limit should fail");
+	};
+	ensure!(matches!(e.error(), ErrorKind::ArrayTooLong(13, 10)));
+
+	let Err(e) = s.evaluate_snippet("snip".to_owned(), "std.repeat([1, 2], 6)") else {
+		jrsonnet_evaluator::bail!("repeat over the limit should fail");
+	};
+	ensure!(matches!(e.error(), ErrorKind::ArrayTooLong(12, 10)));
+	Ok(())
+}
+
+thread_local! {
+	static NATIVE_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+#[test]
+fn fill_shares_value_thunk() -> Result<()> {
+	let std = StdContextInitializer::new(PathResolver::new_cwd_fallback());
+	std.add_native(
+		"counted",
+		FuncVal::from_closure_1(|v| {
+			NATIVE_CALLS.with(|c| c.set(c.get() + 1));
+			Ok(v)
+		}),
+	);
+	let mut s = State::builder();
+	s.context_initializer(std);
+	let s = s.build();
+
+	let v = s.evaluate_snippet(
+		"snip".to_owned(),
+		"std.foldl(function(a, x) a + x, std.fill(std.native('counted')(2), 1000), 0)",
+	)?;
+	ensure_val_eq!(v, Val::num(2000));
+	ensure_eq!(NATIVE_CALLS.with(Cell::get), 1);
+
+	let v = s.evaluate_snippet(
+		"snip".to_owned(),
+		"std.foldl(function(a, x) a + x, std.makeArray(1000, function(_) std.native('counted')(2)), 0)",
+	)?;
+	ensure_val_eq!(v, Val::num(2000));
+	ensure_eq!(NATIVE_CALLS.with(Cell::get), 1001);
+
+	let v = s.evaluate_snippet(
+		"snip".to_owned(),
+		"std.length(std.fill(std.native('counted')(2), 1000000000))",
+	)?;
+	ensure_val_eq!(v, Val::num(1_000_000_000));
+	ensure_eq!(NATIVE_CALLS.with(Cell::get), 1001);
+	Ok(())
+}
@@ -0,0 +1,51 @@
+use jrsonnet_evaluator::{error::ErrorKind, trace::PathResolver, Result, State};
+use jrsonnet_stdlib::ContextInitializer;
+
+mod common;
+
+fn state() -> State {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()));
+	s.build()
+}
+
+#[test]
+fn check_valid_snippet() -> Result<()> {
+	let errors = state().check_snippet(
+		"snip",
+		"{a: 1, b: [1, 2, {c: 'd'}], f(x): error 'not called'}",
+	);
+	ensure!(errors.is_empty());
+	Ok(())
+}
+
+#[test]
+fn check_collects_multiple_errors() -> Result<()> {
+	let errors = state().check_snippet(
+		"snip",
+		"{a: error 'first', b: [1, error 'second'], c: {d: std.length(1)}, e: 1}",
+	);
+	ensure_eq!(errors.len(), 3);
+	ensure!(matches!(errors[0].error(), ErrorKind::RuntimeError(m) if m.as_str() == "first"));
+	ensure!(matches!(errors[1].error(), ErrorKind::RuntimeError(m) if m.as_str() == "second"));
+	Ok(())
+}
+
+#[test]
+fn check_respects_budget() -> Result<()> {
+	let errors =
+		state().check_snippet_with_budget("snip", "{a: error 'a', b: error 'b', c: error 'c'}", 2);
+	ensure_eq!(errors.len(), 2);
+	Ok(())
+}
+
+#[test]
+fn check_reports_syntax_errors() -> Result<()> {
+	let errors = state().check_snippet("snip", "{a: }");
+	ensure_eq!(errors.len(), 1);
+	ensure!(matches!(
+		errors[0].error(),
+		ErrorKind::ImportSyntaxError { .. }
+	));
+	Ok(())
+}
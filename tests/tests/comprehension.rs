@@ -0,0 +1,49 @@
+use std::{cell::Cell, rc::Rc};
+
+use jrsonnet_evaluator::{function::FuncVal, trace::PathResolver, Result, State};
+use jrsonnet_stdlib::ContextInitializer;
+
+mod common;
+
+/// State with `std.native('count')(v)`, which returns `v` and counts its calls
+fn counting_state() -> (State, Rc<Cell<usize>>) {
+	let calls = Rc::new(Cell::new(0));
+	let std = ContextInitializer::new(PathResolver::Absolute);
+	std.add_native(
+		"count",
+		FuncVal::from_closure_1_with(calls.clone(), |calls, v| {
+			calls.set(calls.get() + 1);
+			Ok(v)
+		}),
+	);
+	let mut state = State::builder();
+	state.context_initializer(std);
+	(state.build(), calls)
+}
+
+#[test]
+fn invariant_locals_are_evaluated_once() -> Result<()> {
+	let (s, calls) = counting_state();
+	let v = s.evaluate_snippet(
+		"snip",
+		"local x = std.native('count')(1); std.sum([x + i for i in std.range(0, 99)])",
+	)?;
+	ensure_eq!(v.as_num(), Some(5050.0));
+	ensure_eq!(calls.get(), 1);
+
+	calls.set(0);
+	s.evaluate_snippet(
+		"snip",
+		"local x = std.native('count')(1); std.sum(std.objectValues({ [std.toString(i)]: x for i in std.range(0, 99) }))",
+	)?;
+	ensure_eq!(calls.get(), 1);
+
+	// Locals are invariant only for the comprehensions defined in their scope
+	calls.set(0);
+	s.evaluate_snippet(
+		"snip",
+		"std.toString([local x = std.native('count')(i); [x + j for j in std.range(0, 9)] for i in std.range(0, 9)])",
+	)?;
+	ensure_eq!(calls.get(), 10);
+	Ok(())
+}
@@ -0,0 +1,52 @@
+use std::any::Any;
+
+use jrsonnet_evaluator::{
+	parser::Source, trace::PathResolver, ContextBuilder, ContextInitializer, InitialUnderscore,
+	Result, State, Thunk, Val,
+};
+use jrsonnet_gcmodule::Trace;
+
+mod common;
+
+#[derive(Trace)]
+struct Global(&'static str, Val);
+impl ContextInitializer for Global {
+	fn reserve_vars(&self) -> usize {
+		1
+	}
+	fn populate(&self, _for_file: Source, builder: &mut ContextBuilder) {
+		builder.bind(self.0, Thunk::evaluated(self.1.clone()));
+	}
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+#[test]
+fn layered_initializers() -> Result<()> {
+	let mut s = State::builder();
+	s.add_context_initializer(jrsonnet_stdlib::ContextInitializer::new(
+		PathResolver::new_cwd_fallback(),
+	))
+	.add_context_initializer(Global("global", Val::string("mine")))
+	.add_context_initializer(InitialUnderscore(Thunk::evaluated(Val::num(3))));
+	let s = s.build();
+
+	let v = s.evaluate_snippet("snip", "std.length(global) + _")?;
+	ensure_val_eq!(v, Val::num(7));
+	Ok(())
+}
+
+#[test]
+fn set_initializer_replaces_added() -> Result<()> {
+	let mut s = State::builder();
+	s.add_context_initializer(Global("a", Val::num(1)))
+		.add_context_initializer(Global("b", Val::num(2)))
+		.context_initializer(Global("c", Val::num(3)));
+	let s = s.build();
+
+	ensure!(s.evaluate_snippet("snip", "a").is_err());
+	let v = s.evaluate_snippet("snip", "c")?;
+	ensure_val_eq!(v, Val::num(3));
+	Ok(())
+}
@@ -0,0 +1,86 @@
+use jrsonnet_evaluator::{error::ErrorKind, trace::PathResolver, Result, State, Val};
+use jrsonnet_stdlib::ContextInitializer;
+
+mod common;
+
+const CONFIG: &str = r"
+{
+	broken: error 'sibling is not evaluated',
+	spec: {
+		replicas: error 'neither is this one',
+		template: {
+			spec: {
+				containers: [
+					{ image: 'nginx:' + 'latest', ports: [error 'unused'] },
+					error 'other containers are not evaluated',
+				],
+			},
+		},
+		'a/b': { '~': 1 },
+	},
+}
+";
+
+fn state() -> State {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::Absolute));
+	s.build()
+}
+
+#[test]
+fn evaluate_path_is_lazy() -> Result<()> {
+	let s = state();
+	let v = s.evaluate_path("snip", CONFIG, "/spec/template/spec/containers/0/image")?;
+	ensure_val_eq!(v, Val::string("nginx:latest"));
+	// Escaped segments
+	let v = s.evaluate_path("snip", CONFIG, "/spec/a~1b/~0")?;
+	ensure_val_eq!(v, Val::num(1));
+	// Empty pointer refers to the whole document
+	let v = s.evaluate_path("snip", "[1]", "")?;
+	ensure_val_eq!(v, Val::Arr(vec![Val::num(1)].into()));
+	// Errors on the path are reported
+	ensure!(s.evaluate_path("snip", CONFIG, "/broken").is_err());
+	Ok(())
+}
+
+#[test]
+fn evaluate_path_missing_segment() -> Result<()> {
+	let s = state();
+	for (pointer, prefix, segment) in [
+		("/spec/template/missing/x", "/spec/template", "missing"),
+		(
+			"/spec/template/spec/containers/5",
+			"/spec/template/spec/containers",
+			"5",
+		),
+		(
+			"/spec/template/spec/containers/00",
+			"/spec/template/spec/containers",
+			"00",
+		),
+		(
+			"/spec/template/spec/containers/0/image/x",
+			"/spec/template/spec/containers/0/image",
+			"x",
+		),
+		("/spec/a~1b/x", "/spec/a~1b", "x"),
+	] {
+		let err = s
+			.evaluate_path("snip", CONFIG, pointer)
+			.expect_err("segment is missing");
+		let ErrorKind::JsonPointerNotFound {
+			prefix: found_prefix,
+			segment: found_segment,
+		} = err.error()
+		else {
+			panic!("unexpected error: {err:?}");
+		};
+		ensure_eq!(found_prefix, prefix);
+		ensure_eq!(found_segment, segment);
+	}
+	let err = s
+		.evaluate_path("snip", CONFIG, "spec")
+		.expect_err("pointer is invalid");
+	ensure!(matches!(err.error(), ErrorKind::InvalidJsonPointer(_)));
+	Ok(())
+}
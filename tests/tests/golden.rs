@@ -25,6 +25,7 @@ fn run(file: &Path) -> String {
 		resolver: PathResolver::FileName,
 		max_trace: 20,
 		padding: 4,
+		elision: None,
 	};
 
 	let v = match s.import(file) {
@@ -0,0 +1,562 @@
+use std::{
+	any::Any,
+	cell::Cell,
+	fs,
+	path::{Path, PathBuf},
+};
+
+use jrsonnet_evaluator::{
+	error::ErrorKind,
+	manifest::JsonFormat,
+	parser::SourcePath,
+	trace::{CompactFormat, PathResolver, TraceFormat},
+	FileImportResolver, ImportResolver, Result, State, Val,
+};
+use jrsonnet_gcmodule::Trace;
+use jrsonnet_stdlib::{ContextInitializer, ExtVarOverrides};
+
+mod common;
+
+fn fixture(name: &str, contents: &str) -> PathBuf {
+	let mut path = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+	path.push(name);
+	fs::write(&path, contents).expect("fixture is writable");
+	path
+}
+
+fn state_with_limit(limit: u64) -> State {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()))
+		.import_resolver(FileImportResolver::default().with_max_file_size(limit));
+	s.build()
+}
+
+#[test]
+fn max_file_size_allows_small_files() -> Result<()> {
+	let small = fixture("max_file_size_small.jsonnet", "1 + 2");
+	let s = state_with_limit(16);
+
+	let v = s.import(&small)?;
+	ensure_val_eq!(v, Val::num(3));
+	Ok(())
+}
+
+#[test]
+fn max_file_size_rejects_oversized_files() -> Result<()> {
+	let big = fixture(
+		"max_file_size_big.jsonnet",
+		&format!("'{}'", "a".repeat(1024)),
+	);
+	let s = state_with_limit(16);
+
+	let Err(e) = s.import(&big) else {
+		jrsonnet_evaluator::bail!("oversized import should fail");
+	};
+	ensure!(matches!(e.error(), ErrorKind::ImportFileTooLarge(_, 16)));
+	Ok(())
+}
+
+#[test]
+fn import_cycle_is_reported() -> Result<()> {
+	let a = fixture(
+		"import_cycle_a.jsonnet",
+		"(import 'import_cycle_b.jsonnet') + 1",
+	);
+	fixture(
+		"import_cycle_b.jsonnet",
+		"(import 'import_cycle_a.jsonnet') + 1",
+	);
+	let s = state_with_limit(1024);
+
+	let Err(e) = s.import(&a) else {
+		jrsonnet_evaluator::bail!("import cycle should fail");
+	};
+	let ErrorKind::InfiniteRecursionDetected(cycle) = e.error() else {
+		jrsonnet_evaluator::bail!("unexpected error: {e}");
+	};
+	let names = cycle
+		.iter()
+		.map(|p| {
+			p.path()
+				.and_then(|p| p.file_name())
+				.map(|n| n.to_string_lossy().into_owned())
+		})
+		.collect::<Vec<_>>();
+	ensure_eq!(
+		names,
+		vec![
+			Some("import_cycle_a.jsonnet".to_owned()),
+			Some("import_cycle_b.jsonnet".to_owned()),
+			Some("import_cycle_a.jsonnet".to_owned()),
+		]
+	);
+
+	let formatted = CompactFormat {
+		resolver: PathResolver::FileName,
+		..CompactFormat::default()
+	}
+	.format(&e)
+	.expect("format");
+	ensure!(formatted.starts_with(
+		"infinite recursion detected\nimport cycle: import_cycle_a.jsonnet -> import_cycle_b.jsonnet -> import_cycle_a.jsonnet\n"
+	));
+	Ok(())
+}
+
+#[test]
+fn import_depth_is_limited() -> Result<()> {
+	const CHAIN: usize = 8;
+	let mut files = Vec::new();
+	for i in 0..CHAIN {
+		let code = if i + 1 == CHAIN {
+			"0".to_owned()
+		} else {
+			format!("(import 'import_depth_{}.jsonnet') + 1", i + 1)
+		};
+		files.push(fixture(&format!("import_depth_{i}.jsonnet"), &code));
+	}
+	let state_with_depth = |depth| {
+		let mut s = State::builder();
+		s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()))
+			.import_resolver(FileImportResolver::default())
+			.max_import_depth(depth);
+		s.build()
+	};
+
+	let v = state_with_depth(CHAIN).import(&files[0])?;
+	ensure_val_eq!(v, Val::num(7));
+
+	let Err(e) = state_with_depth(5).import(&files[0]) else {
+		jrsonnet_evaluator::bail!("too deep import chain should fail");
+	};
+	let ErrorKind::ImportTooDeep(5, chain) = e.error() else {
+		jrsonnet_evaluator::bail!("unexpected error: {e}");
+	};
+	ensure_eq!(chain.len(), 6);
+	ensure_eq!(chain[0].path(), Some(files[0].as_path()));
+	ensure_eq!(chain[5].path(), Some(files[5].as_path()));
+
+	let formatted = CompactFormat {
+		resolver: PathResolver::FileName,
+		..CompactFormat::default()
+	}
+	.format(&e)
+	.expect("format");
+	ensure!(formatted.starts_with(
+		"import depth limit exceeded: more than 5 nested imports\nimport chain: import_depth_0.jsonnet -> import_depth_1.jsonnet -> "
+	));
+	Ok(())
+}
+
+#[test]
+fn this_file_is_resolved_per_file() -> Result<()> {
+	let main = fixture(
+		"this_file_main.jsonnet",
+		"[std.thisFile, import 'this_file_lib.libsonnet']",
+	);
+	fixture("this_file_lib.libsonnet", "std.thisFile");
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::FileName))
+		.import_resolver(FileImportResolver::default());
+	let s = s.build();
+
+	let v = s.import(&main)?;
+	ensure_val_eq!(
+		v,
+		Val::Arr(
+			vec![
+				Val::string("this_file_main.jsonnet"),
+				Val::string("this_file_lib.libsonnet"),
+			]
+			.into()
+		)
+	);
+	Ok(())
+}
+
+#[test]
+fn this_file_can_be_disabled() -> Result<()> {
+	let main = fixture("this_file_disabled.jsonnet", "std.thisFile");
+	let ctx = ContextInitializer::new(PathResolver::FileName);
+	ctx.set_this_file(false);
+	let mut s = State::builder();
+	s.context_initializer(ctx)
+		.import_resolver(FileImportResolver::default());
+	let s = s.build();
+
+	ensure!(s.import(&main).is_err());
+	Ok(())
+}
+
+#[test]
+fn ext_var_overrides_are_independent() -> Result<()> {
+	let init = ContextInitializer::new(PathResolver::new_cwd_fallback());
+	init.add_ext_str("shared".into(), "base".into());
+	init.add_ext_str("a".into(), "base".into());
+	let mut s = State::builder();
+	s.context_initializer(init.clone());
+	let s = s.build();
+
+	let mut first = ExtVarOverrides::new(init.clone());
+	first.add_ext_str("a".into(), "first".into());
+	let mut second = ExtVarOverrides::new(init);
+	second.add_ext_code("a", "2 + 2")?;
+	second.add_ext_str("b".into(), "second".into());
+
+	let code = "[std.extVar('shared'), std.extVar('a'), std.thisFile]";
+	let v = s.evaluate_snippet_with("first", code, first.clone())?;
+	let out = v.manifest(JsonFormat::minify(
+		#[cfg(feature = "exp-preserve-order")]
+		false,
+	))?;
+	ensure_eq!(out.as_str(), r#"["base","first","first"]"#);
+	let v = s.evaluate_snippet_with("second", code, second)?;
+	let out = v.manifest(JsonFormat::minify(
+		#[cfg(feature = "exp-preserve-order")]
+		false,
+	))?;
+	ensure_eq!(out.as_str(), r#"["base",4,"second"]"#);
+	// Variables of other override set are not visible
+	ensure!(s
+		.evaluate_snippet_with("first", "std.extVar('b')", first)
+		.is_err());
+	// Shared settings are left untouched
+	let v = s.evaluate_snippet("plain", "std.extVar('a')")?;
+	ensure_val_eq!(v, Val::string("base"));
+	Ok(())
+}
+
+#[test]
+fn ext_code_syntax_error_location() -> Result<()> {
+	let init = ContextInitializer::new(PathResolver::new_cwd_fallback());
+	let e = init
+		.add_ext_code("foo", "{a: 1,, b: 2}")
+		.expect_err("ext code is invalid");
+	let ErrorKind::ImportSyntaxError { path, error } = e.error() else {
+		panic!("unexpected error: {e}");
+	};
+	ensure_eq!(path.source_path().to_string(), "<extvar:foo>");
+	ensure_eq!(error.location.line, 1);
+	ensure_eq!(error.location.column, 7);
+
+	let formatted = CompactFormat::default().format(&e).expect("format");
+	ensure!(formatted.ends_with("\n    <extvar:foo>:1:7"));
+	Ok(())
+}
+
+/// `<tmp>/<name>/root/{main,inner}.jsonnet`, `<tmp>/<name>/outside.jsonnet`,
+/// and symlinks `root/inner_link.jsonnet -> inner.jsonnet`, `root/outside_link.jsonnet -> ../outside.jsonnet`
+#[cfg(unix)]
+fn jail_fixture(name: &str) -> PathBuf {
+	let mut dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+	dir.push(name);
+	let _ = fs::remove_dir_all(&dir);
+	let root = dir.join("root");
+	fs::create_dir_all(&root).expect("fixture dir is writable");
+	let dir = dir.canonicalize().expect("fixture dir exists");
+	let root = dir.join("root");
+
+	fs::write(dir.join("outside.jsonnet"), "'outside'").expect("fixture is writable");
+	fs::write(root.join("inner.jsonnet"), "'inner'").expect("fixture is writable");
+	std::os::unix::fs::symlink(root.join("inner.jsonnet"), root.join("inner_link.jsonnet"))
+		.expect("symlink is creatable");
+	std::os::unix::fs::symlink(
+		dir.join("outside.jsonnet"),
+		root.join("outside_link.jsonnet"),
+	)
+	.expect("symlink is creatable");
+	root
+}
+
+#[cfg(unix)]
+fn jail_import(resolver: FileImportResolver, root: &Path, path: &str) -> Result<Val> {
+	let main = root.join("main.jsonnet");
+	fs::write(&main, format!("import '{path}'")).expect("fixture is writable");
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()))
+		.import_resolver(resolver);
+	s.build().import(&main)
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_followed_by_default() -> Result<()> {
+	let root = jail_fixture("jail_default");
+	let v = jail_import(FileImportResolver::default(), &root, "outside_link.jsonnet")?;
+	ensure_val_eq!(v, Val::string("outside"));
+	Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn root_jail_rejects_escaping_symlink() -> Result<()> {
+	let root = jail_fixture("jail_escape");
+	let resolver = || FileImportResolver::default().with_root_jail(root.clone());
+
+	let v = jail_import(resolver(), &root, "inner_link.jsonnet")?;
+	ensure_val_eq!(v, Val::string("inner"));
+
+	for path in ["outside_link.jsonnet", "../outside.jsonnet"] {
+		let Err(e) = jail_import(resolver(), &root, path) else {
+			jrsonnet_evaluator::bail!("{path} import should fail");
+		};
+		ensure!(matches!(
+			e.error(),
+			ErrorKind::ImportOutsideRootJail(_, r) if r == &root
+		));
+	}
+	Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn reject_symlinks_rejects_any_symlink() -> Result<()> {
+	let root = jail_fixture("jail_symlinks");
+	let resolver = || FileImportResolver::default().with_reject_symlinks(true);
+
+	let v = jail_import(resolver(), &root, "inner.jsonnet")?;
+	ensure_val_eq!(v, Val::string("inner"));
+
+	let Err(e) = jail_import(resolver(), &root, "inner_link.jsonnet") else {
+		jrsonnet_evaluator::bail!("symlink import should fail");
+	};
+	ensure!(matches!(
+		e.error(),
+		ErrorKind::ImportThroughSymlink(p) if p == &root.join("inner_link.jsonnet")
+	));
+	Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn reject_symlinks_trusts_entry_directories() -> Result<()> {
+	let root = jail_fixture("jail_symlinks_entry");
+	let linked_root = root.with_file_name("root_link");
+	std::os::unix::fs::symlink(&root, &linked_root).expect("symlink is creatable");
+	let state = || {
+		let mut s = State::builder();
+		s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()))
+			.import_resolver(FileImportResolver::default().with_reject_symlinks(true));
+		s.build()
+	};
+
+	let v = state().import(linked_root.join("inner.jsonnet"))?;
+	ensure_val_eq!(v, Val::string("inner"));
+
+	let Err(e) = state().import(linked_root.join("inner_link.jsonnet")) else {
+		jrsonnet_evaluator::bail!("symlink import should fail");
+	};
+	ensure!(matches!(
+		e.error(),
+		ErrorKind::ImportThroughSymlink(p) if p == &linked_root.join("inner_link.jsonnet")
+	));
+	Ok(())
+}
+
+#[test]
+fn snippet_imports_use_default_base() -> Result<()> {
+	let mut base = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+	base.push("default_base");
+	fs::create_dir_all(&base).expect("fixture dir is writable");
+	fs::write(base.join("lib.libsonnet"), "{ answer: 42 }").expect("fixture is writable");
+
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()))
+		.import_resolver(FileImportResolver::default().with_default_base(base));
+	let s = s.build();
+
+	let v = s.evaluate_snippet("<cmdline>", "(import 'lib.libsonnet').answer")?;
+	ensure_val_eq!(v, Val::num(42));
+	let v = s.evaluate_snippet("<cmdline>", "importstr 'lib.libsonnet'")?;
+	ensure_val_eq!(v, Val::string("{ answer: 42 }"));
+	Ok(())
+}
+
+#[derive(Trace)]
+struct CountingResolver {
+	inner: FileImportResolver,
+	#[trace(skip)]
+	loads: Cell<usize>,
+}
+impl ImportResolver for CountingResolver {
+	fn resolve_from(&self, from: &SourcePath, path: &str) -> Result<SourcePath> {
+		self.inner.resolve_from(from, path)
+	}
+	fn resolve(&self, path: &Path) -> Result<SourcePath> {
+		self.inner.resolve(path)
+	}
+	fn load_file_contents(&self, resolved: &SourcePath) -> Result<Vec<u8>> {
+		self.loads.set(self.loads.get() + 1);
+		self.inner.load_file_contents(resolved)
+	}
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+}
+
+#[test]
+fn mixed_str_bin_imports_load_once() -> Result<()> {
+	let data = fixture("mixed_import.txt", "hello");
+
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()))
+		.import_resolver(CountingResolver {
+			inner: FileImportResolver::default(),
+			loads: Cell::new(0),
+		});
+	let s = s.build();
+
+	let path = data.display().to_string();
+	let v = s.evaluate_snippet(
+		"<cmdline>",
+		format!(
+			"local bin = importbin '{path}', str = importstr '{path}';
+			[str, std.length(bin), importstr '{path}', std.length(importbin '{path}')]"
+		),
+	)?;
+	ensure_val_eq!(
+		v,
+		Val::Arr(
+			vec![
+				Val::string("hello"),
+				Val::num(5),
+				Val::string("hello"),
+				Val::num(5)
+			]
+			.into()
+		)
+	);
+
+	let resolver = s.import_resolver();
+	let resolver = resolver
+		.as_any()
+		.downcast_ref::<CountingResolver>()
+		.expect("counting resolver");
+	ensure_eq!(resolver.loads.get(), 1);
+	Ok(())
+}
+
+#[test]
+fn loaded_sources_lists_imported_files() -> Result<()> {
+	let mut root = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+	root.push("loaded_sources");
+	fs::create_dir_all(&root).expect("fixture dir is writable");
+	fs::write(root.join("a.libsonnet"), "{ b: import 'b.libsonnet' }")
+		.expect("fixture is writable");
+	fs::write(root.join("b.libsonnet"), "2").expect("fixture is writable");
+	fs::write(root.join("c.txt"), "text").expect("fixture is writable");
+	fs::write(root.join("d.bin"), [0xff, 0xfe]).expect("fixture is writable");
+
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()))
+		.import_resolver(FileImportResolver::default().with_default_base(root.clone()));
+	let s = s.build();
+
+	s.evaluate_snippet(
+		"<cmdline>",
+		"std.toString([(import 'a.libsonnet').b, importstr 'c.txt', importbin 'd.bin'])",
+	)?;
+	ensure!(s
+		.evaluate_snippet("<cmdline>", "import 'missing.libsonnet'")
+		.is_err());
+
+	let sources = s
+		.loaded_sources()
+		.into_iter()
+		.map(|(path, code)| (path.path().expect("file path").to_owned(), code.to_string()))
+		.collect::<Vec<_>>();
+	ensure_eq!(
+		sources,
+		vec![
+			(
+				root.join("a.libsonnet"),
+				"{ b: import 'b.libsonnet' }".to_owned()
+			),
+			(root.join("b.libsonnet"), "2".to_owned()),
+			(root.join("c.txt"), "text".to_owned()),
+		]
+	);
+	Ok(())
+}
+
+fn rot13(data: &[u8]) -> Vec<u8> {
+	data.iter()
+		.map(|&c| match c {
+			b'a'..=b'z' => (c - b'a' + 13) % 26 + b'a',
+			b'A'..=b'Z' => (c - b'A' + 13) % 26 + b'A',
+			c => c,
+		})
+		.collect()
+}
+
+/// Decodes files with `.rot13` extension
+#[derive(Trace)]
+struct Rot13Resolver {
+	inner: FileImportResolver,
+}
+impl ImportResolver for Rot13Resolver {
+	fn resolve_from(&self, from: &SourcePath, path: &str) -> Result<SourcePath> {
+		self.inner.resolve_from(from, path)
+	}
+	fn resolve(&self, path: &Path) -> Result<SourcePath> {
+		self.inner.resolve(path)
+	}
+	fn load_file_contents(&self, resolved: &SourcePath) -> Result<Vec<u8>> {
+		self.inner.load_file_contents(resolved)
+	}
+	fn transform_contents(&self, resolved: &SourcePath, contents: Vec<u8>) -> Result<Vec<u8>> {
+		let encoded = resolved
+			.path()
+			.is_some_and(|p| p.extension().is_some_and(|e| e == "rot13"));
+		Ok(if encoded { rot13(&contents) } else { contents })
+	}
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+}
+
+#[test]
+fn transform_contents_is_applied_to_imports() -> Result<()> {
+	let mut root = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+	root.push("transform_contents");
+	fs::create_dir_all(&root).expect("fixture dir is writable");
+	fs::write(
+		root.join("secret.rot13"),
+		rot13(b"{ password: 'hunter2', plain: import 'plain.libsonnet' }"),
+	)
+	.expect("fixture is writable");
+	fs::write(root.join("plain.libsonnet"), "'not encoded'").expect("fixture is writable");
+	fs::write(root.join("text.rot13"), rot13(b"Hello")).expect("fixture is writable");
+
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()))
+		.import_resolver(Rot13Resolver {
+			inner: FileImportResolver::default().with_default_base(root),
+		});
+	let s = s.build();
+
+	let v = s.evaluate_snippet(
+		"<cmdline>",
+		"local secret = import 'secret.rot13';
+		[secret.password, secret.plain, importstr 'text.rot13', std.length(importbin 'text.rot13')]",
+	)?;
+	ensure_val_eq!(
+		v,
+		Val::Arr(
+			vec![
+				Val::string("hunter2"),
+				Val::string("not encoded"),
+				Val::string("Hello"),
+				Val::num(5),
+			]
+			.into()
+		)
+	);
+	Ok(())
+}
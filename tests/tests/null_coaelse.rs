@@ -0,0 +1,52 @@
+use jrsonnet_evaluator::{trace::PathResolver, Result, State};
+use jrsonnet_stdlib::ContextInitializer;
+
+mod common;
+
+fn state() -> State {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::Absolute));
+	s.build()
+}
+
+#[cfg(feature = "exp-null-coaelse")]
+#[test]
+fn null_coaelse_operator() -> Result<()> {
+	use jrsonnet_evaluator::Val;
+
+	let s = state();
+	let v = s.evaluate_snippet("snip", "(null ?? 5) == 5")?;
+	ensure_val_eq!(v, Val::Bool(true));
+	// Right side is not evaluated if left side is not null
+	let v = s.evaluate_snippet("snip", "(3 ?? error 'x') == 3")?;
+	ensure_val_eq!(v, Val::Bool(true));
+	// Only null is replaced, other falsy values are kept
+	let v = s.evaluate_snippet("snip", "[false ?? 1, 0 ?? 1, '' ?? 1, [] ?? 1]")?;
+	ensure_val_eq!(
+		v,
+		Val::Arr(
+			vec![
+				Val::Bool(false),
+				Val::num(0),
+				Val::string(""),
+				Val::Arr(Vec::<Val>::new().into()),
+			]
+			.into()
+		)
+	);
+	let v = s.evaluate_snippet("snip", "null ?? null ?? 'c'")?;
+	ensure_val_eq!(v, Val::string("c"));
+	let v = s.evaluate_snippet("snip", "local o = { a: null }; o?.b ?? o.a ?? 'default'")?;
+	ensure_val_eq!(v, Val::string("default"));
+	// Errors in left side are not suppressed
+	ensure!(s.evaluate_snippet("snip", "error 'x' ?? 1").is_err());
+	Ok(())
+}
+
+#[cfg(not(feature = "exp-null-coaelse"))]
+#[test]
+fn null_coaelse_requires_feature() -> Result<()> {
+	let s = state();
+	ensure!(s.evaluate_snippet("snip", "null ?? 5").is_err());
+	Ok(())
+}
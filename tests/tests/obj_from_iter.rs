@@ -0,0 +1,60 @@
+use jrsonnet_evaluator::{bail, IStr, ObjValue, Result, Thunk, Val};
+
+mod common;
+
+#[test]
+fn from_vec() -> Result<()> {
+	let obj: ObjValue = vec![
+		(IStr::from("c"), Val::num(1)),
+		(IStr::from("a"), Val::string("x")),
+		(IStr::from("b"), Val::Bool(true)),
+	]
+	.into_iter()
+	.collect();
+
+	ensure_eq!(obj.len(), 3);
+	ensure_eq!(
+		obj.fields(
+			#[cfg(feature = "exp-preserve-order")]
+			false
+		),
+		vec![IStr::from("a"), IStr::from("b"), IStr::from("c")]
+	);
+	#[cfg(feature = "exp-preserve-order")]
+	ensure_eq!(
+		obj.fields(true),
+		vec![IStr::from("c"), IStr::from("a"), IStr::from("b")]
+	);
+	ensure_val_eq!(obj.get_or_bail("c".into())?, Val::num(1));
+	ensure_val_eq!(obj.get_or_bail("a".into())?, Val::string("x"));
+	ensure_val_eq!(obj.get_or_bail("b".into())?, Val::Bool(true));
+	Ok(())
+}
+
+#[test]
+fn duplicate_keys_last_wins() -> Result<()> {
+	let obj = ObjValue::from_iter([
+		(IStr::from("a"), Val::num(1)),
+		(IStr::from("b"), Val::num(2)),
+		(IStr::from("a"), Val::num(3)),
+	]);
+
+	ensure_eq!(obj.len(), 2);
+	ensure_val_eq!(obj.get_or_bail("a".into())?, Val::num(3));
+	#[cfg(feature = "exp-preserve-order")]
+	ensure_eq!(obj.fields(true), vec![IStr::from("b"), IStr::from("a")]);
+	Ok(())
+}
+
+#[test]
+fn thunks_are_lazy() -> Result<()> {
+	let obj = ObjValue::from_thunk_iter([
+		(IStr::from("ok"), Thunk::evaluated(Val::num(1))),
+		(IStr::from("lazy"), Thunk!(move || bail!("forced"))),
+	]);
+
+	ensure_eq!(obj.len(), 2);
+	ensure_val_eq!(obj.get_or_bail("ok".into())?, Val::num(1));
+	ensure!(obj.get("lazy".into()).is_err());
+	Ok(())
+}
@@ -0,0 +1,95 @@
+use jrsonnet_evaluator::{trace::PathResolver, Result, State};
+use jrsonnet_stdlib::ContextInitializer;
+
+mod common;
+
+fn state() -> State {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::Absolute));
+	s.build()
+}
+
+#[cfg(feature = "exp-preserve-order")]
+#[test]
+fn declaration_order() -> Result<()> {
+	use jrsonnet_evaluator::Val;
+
+	let s = state();
+	let v = s.evaluate_snippet(
+		"snip",
+		"std.objectFieldsOrder({ c: 1, a: 2, h:: 3 } + { b: 4, a: 5 })",
+	)?;
+	ensure_val_eq!(
+		v,
+		Val::Arr(vec![Val::string("c"), Val::string("a"), Val::string("b")].into())
+	);
+	let v = s.evaluate_snippet("snip", "std.objectFields({ c: 1, a: 2 })")?;
+	ensure_val_eq!(v, Val::Arr(vec![Val::string("a"), Val::string("c")].into()));
+	Ok(())
+}
+
+#[cfg(feature = "exp-preserve-order")]
+#[test]
+fn manifest_yaml_sort_keys() -> Result<()> {
+	use jrsonnet_evaluator::Val;
+
+	let s = state();
+	let manifest = |args: &str| {
+		s.evaluate_snippet(
+			"snip",
+			format!("std.manifestYamlDoc({{ b: 1, a: {{ d: 2, c: 3 }} }}, quote_keys=false{args})"),
+		)
+	};
+	ensure_val_eq!(
+		manifest(", preserve_order=true")?,
+		Val::string("b: 1\na:\n  d: 2\n  c: 3")
+	);
+	ensure_val_eq!(
+		manifest(", preserve_order=true, sort_keys=true")?,
+		Val::string("a:\n  c: 3\n  d: 2\nb: 1")
+	);
+	ensure_val_eq!(manifest("")?, Val::string("a:\n  c: 3\n  d: 2\nb: 1"));
+	Ok(())
+}
+
+#[cfg(feature = "exp-preserve-order")]
+#[test]
+fn manifest_json_pretty_unsorted() -> Result<()> {
+	use jrsonnet_evaluator::Val;
+
+	let s = state();
+	let v = s.evaluate_snippet(
+		"snip",
+		"std.manifestJsonPretty({ b: 1, a: { d: 2, c: 3 } }, sort=false)",
+	)?;
+	ensure_val_eq!(
+		v,
+		Val::string("{\n  \"b\": 1,\n  \"a\": {\n    \"d\": 2,\n    \"c\": 3\n  }\n}")
+	);
+	Ok(())
+}
+
+#[cfg(not(feature = "exp-preserve-order"))]
+#[test]
+fn requires_preserve_order() -> Result<()> {
+	let s = state();
+	let Err(e) = s.evaluate_snippet("snip", "std.objectFieldsOrder({ c: 1, a: 2 })") else {
+		jrsonnet_evaluator::bail!("objectFieldsOrder should fail without exp-preserve-order");
+	};
+	ensure_eq!(
+		e.error().to_string(),
+		"runtime error: std.objectFieldsOrder requires jrsonnet to be built with exp-preserve-order feature"
+	);
+
+	let Err(e) = s.evaluate_snippet("snip", "std.manifestJsonPretty({ b: 1, a: 2 }, sort=false)")
+	else {
+		jrsonnet_evaluator::bail!(
+			"unsorted manifestJsonPretty should fail without exp-preserve-order"
+		);
+	};
+	ensure_eq!(
+		e.error().to_string(),
+		"runtime error: std.manifestJsonPretty sort=false requires jrsonnet to be built with exp-preserve-order feature"
+	);
+	Ok(())
+}
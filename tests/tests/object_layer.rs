@@ -0,0 +1,134 @@
+mod common;
+
+use std::cell::Cell;
+
+use jrsonnet_evaluator::{
+	manifest::JsonFormat,
+	parser::Visibility,
+	typed::{ComplexValType, Typed, ValType},
+	IStr, NativeObject, ObjValue, ObjectLayer, Result, State, Val,
+};
+use jrsonnet_gcmodule::Trace;
+use jrsonnet_stdlib::ContextInitializer;
+
+thread_local! {
+	static CONVERTED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Number, which counts its conversions to jsonnet values
+#[derive(Clone, Trace, Debug)]
+struct Counted(u32);
+impl Typed for Counted {
+	const TYPE: &'static ComplexValType = &ComplexValType::Simple(ValType::Num);
+
+	fn into_untyped(typed: Self) -> Result<Val> {
+		CONVERTED.with(|c| c.set(c.get() + 1));
+		Ok(Val::num(typed.0))
+	}
+
+	fn from_untyped(untyped: Val) -> Result<Self> {
+		u32::from_untyped(untyped).map(Self)
+	}
+}
+
+fn converted() -> usize {
+	CONVERTED.with(Cell::get)
+}
+
+#[derive(Trace, Debug)]
+struct Big(Vec<Counted>);
+impl ObjectLayer for Big {
+	fn enum_fields(&self, handler: &mut dyn FnMut(IStr, Visibility) -> bool) -> bool {
+		(0..self.0.len()).any(|i| handler(format!("f{i}").into(), Visibility::Normal))
+	}
+	fn field_visibility(&self, name: &str) -> Option<Visibility> {
+		self.index(name).map(|_| Visibility::Normal)
+	}
+	fn get(&self, name: &str) -> Result<Option<Val>> {
+		self.index(name)
+			.map(|i| Counted::into_untyped(self.0[i].clone()))
+			.transpose()
+	}
+}
+impl Big {
+	fn index(&self, name: &str) -> Option<usize> {
+		let index: usize = name.strip_prefix('f')?.parse().ok()?;
+		(index < self.0.len()).then_some(index)
+	}
+}
+
+#[derive(ObjectLayer, Trace, Debug)]
+struct Config {
+	name: String,
+	#[typed(rename = "replicas")]
+	count: Counted,
+	#[typed(hide)]
+	secret: String,
+	comment: Option<String>,
+}
+
+fn call(code: &str, obj: ObjValue) -> Result<Val> {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(
+		jrsonnet_evaluator::trace::PathResolver::new_cwd_fallback(),
+	));
+	let s = s.build();
+	let func = s.evaluate_snippet("<test>", code)?;
+	let Val::Func(func) = func else {
+		jrsonnet_evaluator::bail!("expected function");
+	};
+	func.evaluate_simple(&(Val::Obj(obj),), false)
+}
+
+#[test]
+fn native_object_is_lazy() -> Result<()> {
+	let obj = ObjValue::new(NativeObject::new(Big((0..1000).map(Counted).collect())));
+	let before = converted();
+
+	let field = call("function(o) o.f500", obj.clone())?;
+	ensure_val_eq!(field, Val::num(500));
+	ensure_eq!(converted() - before, 1);
+
+	// Cached, and enumeration doesn't touch values
+	ensure_eq!(
+		call("function(o) [o.f500, std.length(o), 'f999' in o]", obj)?.manifest(
+			JsonFormat::minify(
+				#[cfg(feature = "exp-preserve-order")]
+				false,
+			)
+		)?,
+		"[500,1000,true]",
+	);
+	ensure_eq!(converted() - before, 1);
+	Ok(())
+}
+
+#[test]
+fn derived_object_layer() -> Result<()> {
+	let config = Config {
+		name: "app".to_owned(),
+		count: Counted(3),
+		secret: "hunter2".to_owned(),
+		comment: None,
+	};
+	let obj = ObjValue::new(NativeObject::new(config));
+	let before = converted();
+
+	let v = call(
+		"function(o) [std.objectFields(o), o.name, o.secret, 'comment' in o, (o + { name+: '!' }).name]",
+		obj.clone(),
+	)?;
+	ensure_eq!(converted() - before, 0);
+	ensure_eq!(
+		v.manifest(JsonFormat::minify(
+			#[cfg(feature = "exp-preserve-order")]
+			false,
+		))?,
+		r#"[["name","replicas"],"app","hunter2",false,"app!"]"#,
+	);
+
+	let replicas = call("function(o) o.replicas", obj)?;
+	ensure_val_eq!(replicas, Val::num(3));
+	ensure_eq!(converted() - before, 1);
+	Ok(())
+}
@@ -1,6 +1,7 @@
 use jrsonnet_evaluator::{
 	bail,
-	trace::{CompactFormat, PathResolver, TraceFormat},
+	budget::limit_thunks,
+	trace::{CompactFormat, PathResolver, TraceElision, TraceFormat},
 	FileImportResolver, Result, State, Val,
 };
 use jrsonnet_stdlib::ContextInitializer;
@@ -48,3 +49,172 @@ fn assert_negative() -> Result<()> {
 
 	Ok(())
 }
+
+#[test]
+fn object_assertion_location() -> Result<()> {
+	let s = State::default();
+	let format = CompactFormat {
+		resolver: PathResolver::FileName,
+		..CompactFormat::default()
+	};
+
+	let Err(e) = s.evaluate_snippet("snip".to_owned(), "{assert self.x > 0, x: -1}.x") else {
+		bail!("assertion should fail");
+	};
+	ensure_eq!(
+		format.format(&e).unwrap(),
+		"assert failed: null\n    \
+		 snip:1:9-20:  assertion failure\n    \
+		 snip:1:1-28:  object assertion on field <x>\n    \
+		 snip:1:28-30: field <x> access",
+	);
+
+	let Err(e) = s.evaluate_snippet("snip".to_owned(), "{assert 1 == 2 : 'fail', x: -1}.x") else {
+		bail!("assertion should fail");
+	};
+	let e = format.format(&e).unwrap();
+	ensure!(e.contains("\n    snip:1:1-33:  object assertion\n"));
+	Ok(())
+}
+
+#[test]
+fn trace_elision() -> Result<()> {
+	let s = State::default();
+	let Err(e) = s.evaluate_snippet(
+		"snip".to_owned(),
+		"local f(n) = if n == 0 then error 'fail' else f(n - 1); f(10)",
+	) else {
+		bail!("error should be thrown");
+	};
+	let format = |elision| {
+		CompactFormat {
+			resolver: PathResolver::FileName,
+			elision,
+			..CompactFormat::default()
+		}
+		.format(&e)
+		.unwrap()
+	};
+
+	let full = format(None);
+	ensure_eq!(full.lines().count(), 13);
+
+	let elided = format(Some(TraceElision { head: 2, tail: 1 }));
+	let lines = elided.lines().collect::<Vec<_>>();
+	let full_lines = full.lines().collect::<Vec<_>>();
+	ensure_eq!(lines.len(), 5);
+	ensure_eq!(&lines[..3], &full_lines[..3]);
+	ensure_eq!(lines[3], "    ... (9 frames omitted) ...");
+	ensure_eq!(lines[4], full_lines[12]);
+
+	// Nothing to elide
+	ensure_eq!(format(Some(TraceElision { head: 6, tail: 6 })), full);
+	Ok(())
+}
+
+#[test]
+fn max_trace() -> Result<()> {
+	let s = State::default();
+	let Err(e) = s.evaluate_snippet(
+		"snip".to_owned(),
+		"local f(n) = if n == 0 then error 'fail' else f(n - 1); f(30)",
+	) else {
+		bail!("error should be thrown");
+	};
+	let format = |max_trace| {
+		CompactFormat {
+			resolver: PathResolver::FileName,
+			max_trace,
+			..CompactFormat::default()
+		}
+		.format(&e)
+		.unwrap()
+	};
+
+	// `0` means unlimited, as in libjsonnet
+	let full = format(0);
+	ensure_eq!(full.lines().count(), 33);
+	ensure_eq!(format(100), full);
+
+	let limited = format(5);
+	let lines = limited.lines().collect::<Vec<_>>();
+	let full_lines = full.lines().collect::<Vec<_>>();
+	ensure_eq!(lines.len(), 7);
+	ensure_eq!(&lines[..4], &full_lines[..4]);
+	ensure_eq!(lines[4], "    ... (27 frames omitted) ...");
+	ensure_eq!(&lines[5..], &full_lines[31..]);
+	Ok(())
+}
+
+#[test]
+fn binding_suggestions_are_stable() -> Result<()> {
+	let s = State::default();
+	let Err(e) = s.evaluate_snippet(
+		"snip".to_owned(),
+		"local valueD = 1, valueB = 2, valueA = 3, valueC = 4; valueX",
+	) else {
+		bail!("error should be thrown");
+	};
+	ensure_eq!(
+		e.error().to_string(),
+		"local is not defined: valueX\nThere are locals with similar names present: valueA, valueB, valueC, valueD"
+	);
+	Ok(())
+}
+
+#[test]
+fn strict_duplicate_fields() -> Result<()> {
+	let code = "{\n  a: 1,\n  b: 2,\n  a: 3,\n}";
+
+	let s = State::default();
+	let Err(e) = s.evaluate_snippet("snip".to_owned(), code) else {
+		bail!("duplicate field should fail");
+	};
+	ensure_eq!(e.error().to_string(), "duplicate field name: a");
+
+	let mut s = State::builder();
+	s.strict_duplicate_fields(true);
+	let s = s.build();
+	let Err(e) = s.evaluate_snippet("snip".to_owned(), code) else {
+		bail!("duplicate field should fail");
+	};
+	ensure_eq!(
+		e.error().to_string(),
+		"duplicate field name: a, first defined at 2:6, redefined at 4:6"
+	);
+
+	// Only fixed names are checked, and only in a single literal
+	let v = s.evaluate_snippet("snip".to_owned(), "{ a: 1 } + { a: 2 }")?;
+	ensure_val_eq!(
+		v,
+		Val::Obj({
+			let mut b = jrsonnet_evaluator::ObjValueBuilder::new();
+			b.field("a").value(Val::num(2));
+			b.build()
+		})
+	);
+	Ok(())
+}
+
+#[test]
+fn thunk_budget() -> Result<()> {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()));
+	let s = s.build();
+	let _budget = limit_thunks(Some(1000));
+
+	let v = s.evaluate_snippet("snip".to_owned(), "std.sum([x for x in std.range(1, 10)])")?;
+	ensure_eq!(v.as_num(), Some(55.0));
+
+	let Err(e) = s.evaluate_snippet(
+		"snip".to_owned(),
+		"std.sum([x for x in std.range(1, 100000)])",
+	) else {
+		bail!("budget should be exceeded");
+	};
+	ensure_eq!(
+		e.error().to_string(),
+		"evaluation budget exceeded: more than 1000 thunks evaluated, try to set --max-thunks to bigger value"
+	);
+	Ok(())
+}
@@ -1,4 +1,9 @@
-use jrsonnet_evaluator::{function::builtin, trace::PathResolver, State};
+use jrsonnet_evaluator::{
+	bail,
+	function::{builtin, FuncVal},
+	trace::PathResolver,
+	Pending, State, Val,
+};
 use jrsonnet_stdlib::ContextInitializer;
 
 #[builtin]
@@ -20,3 +25,44 @@ fn std_native() {
 		.as_bool()
 		.expect("boolean output"));
 }
+
+#[test]
+fn std_native_closure() {
+	let mut state = State::builder();
+	let std = ContextInitializer::new(PathResolver::Absolute);
+	std.add_native(
+		"add",
+		FuncVal::from_closure_2(|a, b| match (a, b) {
+			(Val::Num(a), Val::Num(b)) => Ok(Val::try_num(a.get() + b.get())?),
+			_ => bail!("numbers expected"),
+		}),
+	);
+	state.context_initializer(std);
+	let state = state.build();
+
+	assert!(state
+		.evaluate_snippet("test", "std.native('add')(1, 3) == 4")
+		.unwrap()
+		.as_bool()
+		.expect("boolean output"));
+	let err = state
+		.evaluate_snippet("test", "std.native('add')(1)")
+		.expect_err("missing argument");
+	assert_eq!(
+		err.error().to_string(),
+		"function argument is not passed: <unnamed>\nFunction has the following signature: (<unnamed>, <unnamed>)"
+	);
+}
+
+#[test]
+fn std_native_closure_state_is_traced() {
+	let tracked = jrsonnet_gcmodule::count_thread_tracked();
+	{
+		// Function, which returns itself
+		let this = Pending::<Val>::new();
+		let f = FuncVal::from_closure_1_with(this.clone(), |this, _| Ok(this.unwrap()));
+		this.fill(Val::Func(f));
+	}
+	jrsonnet_gcmodule::collect_thread_cycles();
+	assert_eq!(jrsonnet_gcmodule::count_thread_tracked(), tracked);
+}
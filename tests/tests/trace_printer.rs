@@ -0,0 +1,56 @@
+use jrsonnet_evaluator::{bail, trace::PathResolver, IStr, Result, State, Val};
+use jrsonnet_stdlib::{BufferedTracePrinter, ContextInitializer};
+
+mod common;
+
+#[test]
+fn trace_labeled() -> Result<()> {
+	let printer = BufferedTracePrinter::default();
+	let std = ContextInitializer::new(PathResolver::Absolute);
+	std.settings_mut().trace_printer = Box::new(printer.clone());
+	let mut s = State::builder();
+	s.context_initializer(std);
+	let s = s.build();
+
+	let v = s.evaluate_snippet(
+		"snip",
+		"std.traceLabeled('config', { b: [1, 'two'], a: null }).b[0]",
+	)?;
+	ensure_val_eq!(v, Val::num(1));
+	ensure_eq!(
+		printer.take(),
+		vec![IStr::from(r#"config: {"a":null,"b":[1,"two"]}"#)]
+	);
+
+	s.evaluate_snippet("snip", "std.traceLabeled('long', std.repeat('a', 2000))")?;
+	let traces = printer.take();
+	ensure_eq!(traces.len(), 1);
+	ensure!(traces[0].starts_with("long: \"aaa"));
+	ensure!(traces[0].ends_with("a..."));
+	ensure_eq!(
+		traces[0].chars().count(),
+		"long: ".len() + 1024 + "...".len()
+	);
+
+	// Manifestification is stopped once the limit is reached
+	s.evaluate_snippet(
+		"snip",
+		"std.traceLabeled('lazy', std.makeArray(1e6, function(i) if i > 1000 then error 'unreachable' else i))",
+	)?;
+	let traces = printer.take();
+	ensure_eq!(traces.len(), 1);
+	ensure!(traces[0].starts_with("lazy: [0,1,2,"));
+	ensure!(traces[0].ends_with("..."));
+
+	let Err(e) = s.evaluate_snippet("snip", "std.traceLabeled('f', function() 1)") else {
+		bail!("functions can't be manifested");
+	};
+	ensure_eq!(
+		e.error().to_string(),
+		"runtime error: tried to manifest function"
+	);
+
+	s.evaluate_snippet("snip", "std.trace('plain', 1)")?;
+	ensure_eq!(printer.take(), vec![IStr::from("plain")]);
+	Ok(())
+}
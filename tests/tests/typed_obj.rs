@@ -1,8 +1,11 @@
 mod common;
 
-use std::fmt::Debug;
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::Debug,
+};
 
-use jrsonnet_evaluator::{trace::PathResolver, typed::Typed, Result, State};
+use jrsonnet_evaluator::{trace::PathResolver, typed::Typed, IStr, Result, State};
 use jrsonnet_stdlib::ContextInitializer;
 
 #[derive(Clone, Typed, PartialEq, Debug)]
@@ -185,3 +188,52 @@ fn flatten_optional_none() -> Result<()> {
 	test_roundtrip(d)?;
 	Ok(())
 }
+
+#[derive(Clone, Typed, PartialEq, Debug)]
+struct Limits {
+	name: String,
+	limits: HashMap<IStr, i64>,
+}
+
+#[test]
+fn map_fields() -> Result<()> {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::new_cwd_fallback()));
+	let s = s.build();
+
+	let map = HashMap::<IStr, i64>::from_untyped(s.evaluate_snippet(
+		"snip".to_owned(),
+		"{a: 1, b: -2, c:: 3, ['d' + 'e']: 9007199254740991}",
+	)?)?;
+	ensure_eq!(
+		map,
+		HashMap::from([
+			("a".into(), 1),
+			("b".into(), -2),
+			("de".into(), 9_007_199_254_740_991),
+		])
+	);
+	test_roundtrip(map)?;
+	ensure!(
+		HashMap::<IStr, i64>::from_untyped(s.evaluate_snippet("snip".to_owned(), "{a: 1.5}")?)
+			.is_err()
+	);
+
+	let limits = Limits::from_untyped(
+		s.evaluate_snippet("snip".to_owned(), "{name: 'x', limits: {cpu: 2, mem: 512}}")?,
+	)?;
+	ensure_eq!(
+		&Limits::into_untyped(limits.clone())?.to_string()? as &str,
+		r#"{"limits": {"cpu": 2, "mem": 512}, "name": "x"}"#,
+	);
+	test_roundtrip(limits)?;
+
+	let sorted = BTreeMap::<IStr, i64>::from_untyped(
+		s.evaluate_snippet("snip".to_owned(), "{b: 2, a: 1}")?,
+	)?;
+	ensure_eq!(
+		sorted.into_iter().collect::<Vec<_>>(),
+		vec![("a".into(), 1), ("b".into(), 2)]
+	);
+	Ok(())
+}
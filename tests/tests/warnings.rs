@@ -0,0 +1,39 @@
+use jrsonnet_evaluator::{trace::PathResolver, Result, State, Warning};
+use jrsonnet_stdlib::ContextInitializer;
+
+mod common;
+
+#[test]
+fn deprecated_builtin() -> Result<()> {
+	let mut s = State::builder();
+	s.context_initializer(ContextInitializer::new(PathResolver::Absolute));
+	let s = s.build();
+
+	s.evaluate_snippet("snip", "std.__compare_array([1], [2])")?;
+	ensure_eq!(
+		s.take_warnings(),
+		vec![Warning::DeprecatedBuiltin {
+			name: "__compare_array",
+			replacement: Some("comparison operators"),
+		}],
+	);
+	ensure!(s.take_warnings().is_empty());
+
+	s.evaluate_snippet("snip", "std.__array_less([1], [2]) && [1] < [2]")?;
+	ensure_eq!(
+		s.take_warnings()
+			.iter()
+			.map(ToString::to_string)
+			.collect::<Vec<_>>(),
+		vec!["std.__array_less is deprecated, use comparison operators instead"],
+	);
+
+	// Repeated warnings are not accumulated
+	s.evaluate_snippet(
+		"snip",
+		"std.all([std.__array_less([i], [i + 1]) for i in std.range(1, 1000)])",
+	)?;
+	ensure_eq!(s.take_warnings().len(), 1);
+
+	Ok(())
+}